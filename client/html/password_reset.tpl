@@ -13,8 +13,8 @@
             </ul>
 
             <p><small>Proceeding will send an e-mail that contains a password reset
-            link. Clicking it is going to generate a new password for your account.
-            It is recommended to change that password to something else.</small></p>
+            link. Clicking it within the next hour lets you choose a new password
+            for your account.</small></p>
 
             <div class='messages'></div>
             <div class='buttons'>
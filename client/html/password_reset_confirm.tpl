@@ -0,0 +1,23 @@
+<div class='content-wrapper' id='password-reset'>
+    <h1>Password reset</h1>
+    <form autocomplete='off'>
+        <input class='anticomplete' type='password' name='fakepass'/>
+
+        <ul class='input'>
+            <li>
+                <%= ctx.makePasswordInput({
+                    text: 'New password',
+                    name: 'password',
+                    placeholder: '5+ characters',
+                    required: true,
+                    pattern: ctx.passwordPattern,
+                }) %>
+            </li>
+        </ul>
+
+        <div class='messages'></div>
+        <div class='buttons'>
+            <input type='submit' value='Change password'/>
+        </div>
+    </form>
+</div>
@@ -5,6 +5,7 @@ const api = require("../api.js");
 const uri = require("../util/uri.js");
 const topNavigation = require("../models/top_navigation.js");
 const PasswordResetView = require("../views/password_reset_view.js");
+const PasswordResetConfirmView = require("../views/password_reset_confirm_view.js");
 
 class PasswordResetController {
     constructor() {
@@ -22,13 +23,14 @@ class PasswordResetController {
         this._passwordResetView.disableForm();
         api.forget();
         api.logout();
-        api.get(
-            uri.formatApiLink("password-reset", e.detail.userNameOrEmail)
-        ).then(
+        api.post(uri.formatApiLink("password-reset"), {
+            identifier: e.detail.userNameOrEmail,
+        }).then(
             () => {
                 this._passwordResetView.showSuccess(
-                    "E-mail has been sent. To finish the procedure, " +
-                        "please click the link it contains."
+                    "If the account exists and has an e-mail address, " +
+                        "an e-mail has been sent to it. To finish the " +
+                        "procedure, please click the link it contains."
                 );
             },
             (error) => {
@@ -39,26 +41,38 @@ class PasswordResetController {
     }
 }
 
-class PasswordResetFinishController {
-    constructor(name, token) {
+class PasswordResetConfirmController {
+    constructor(token) {
+        topNavigation.activate("login");
+        topNavigation.setTitle("Password reset");
+
+        this._token = token;
+        this._passwordResetConfirmView = new PasswordResetConfirmView();
+        this._passwordResetConfirmView.addEventListener("submit", (e) =>
+            this._evtConfirm(e)
+        );
+    }
+
+    _evtConfirm(e) {
+        this._passwordResetConfirmView.clearMessages();
+        this._passwordResetConfirmView.disableForm();
         api.forget();
         api.logout();
-        let password = null;
-        api.post(uri.formatApiLink("password-reset", name), { token: token })
-            .then((response) => {
-                password = response.password;
-                return api.login(name, password, false);
-            })
-            .then(
-                () => {
-                    const ctx = router.show(uri.formatClientLink());
-                    ctx.controller.showSuccess("New password: " + password);
-                },
-                (error) => {
-                    const ctx = router.show(uri.formatClientLink());
-                    ctx.controller.showError(error.message);
-                }
-            );
+        api.post(uri.formatApiLink("password-reset", "confirm"), {
+            token: this._token,
+            password: e.detail.password,
+        }).then(
+            () => {
+                const ctx = router.show(uri.formatClientLink());
+                ctx.controller.showSuccess(
+                    "Password changed. You can now log in with it."
+                );
+            },
+            (error) => {
+                this._passwordResetConfirmView.showError(error.message);
+                this._passwordResetConfirmView.enableForm();
+            }
+        );
     }
 }
 
@@ -66,8 +80,10 @@ module.exports = (router) => {
     router.enter(["password-reset"], (ctx, next) => {
         ctx.controller = new PasswordResetController();
     });
-    router.enter(["password-reset", ":descriptor"], (ctx, next) => {
-        const [name, token] = ctx.parameters.descriptor.split(":", 2);
-        ctx.controller = new PasswordResetFinishController(name, token);
+    // Links sent by POST /password-reset look like password-reset/confirm/token=<token>
+    router.enter(["password-reset", "confirm"], (ctx, next) => {
+        ctx.controller = new PasswordResetConfirmController(
+            ctx.parameters.token
+        );
     });
 };
@@ -13,7 +13,7 @@ const EmptyView = require("../views/empty_view.js");
 
 const fields = [
     "id",
-    "thumbnailUrl",
+    "thumbnail",
     "type",
     "safety",
     "score",
@@ -496,7 +496,7 @@ class Post extends events.EventTarget {
                 response.contentUrl,
                 document.getElementsByTagName("base")[0].href
             ).href,
-            _thumbnailUrl: response.thumbnailUrl,
+            _thumbnailUrl: response.thumbnail ? response.thumbnail.url : null,
             _source: response.source,
             _canvasWidth: response.canvasWidth,
             _canvasHeight: response.canvasHeight,
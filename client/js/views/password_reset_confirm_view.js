@@ -0,0 +1,60 @@
+"use strict";
+
+const events = require("../events.js");
+const api = require("../api.js");
+const views = require("../util/views.js");
+
+const template = views.getTemplate("password-reset-confirm");
+
+class PasswordResetConfirmView extends events.EventTarget {
+    constructor() {
+        super();
+        this._hostNode = document.getElementById("content-holder");
+
+        views.replaceContent(
+            this._hostNode,
+            template({
+                passwordPattern: api.getPasswordRegex(),
+            })
+        );
+        views.syncScrollPosition();
+
+        views.decorateValidator(this._formNode);
+        this._formNode.addEventListener("submit", (e) => {
+            e.preventDefault();
+            this.dispatchEvent(
+                new CustomEvent("submit", {
+                    detail: {
+                        password: this._passwordFieldNode.value,
+                    },
+                })
+            );
+        });
+    }
+
+    showError(message) {
+        views.showError(this._hostNode, message);
+    }
+
+    clearMessages() {
+        views.clearMessages(this._hostNode);
+    }
+
+    enableForm() {
+        views.enableForm(this._formNode);
+    }
+
+    disableForm() {
+        views.disableForm(this._formNode);
+    }
+
+    get _formNode() {
+        return this._hostNode.querySelector("form");
+    }
+
+    get _passwordFieldNode() {
+        return this._formNode.querySelector("[name=password]");
+    }
+}
+
+module.exports = PasswordResetConfirmView;
@@ -0,0 +1,25 @@
+CREATE FUNCTION update_default_tag_category_statistics() RETURNS TRIGGER AS $$
+BEGIN
+    UPDATE "tag_category_statistics"
+    SET "usage_count" = "usage_count" + (SELECT "usage_count" FROM "tag_category_statistics" WHERE "category_id" = OLD."id")
+    WHERE "category_id" = 0;
+
+    RETURN OLD;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE FUNCTION update_default_pool_category_statistics() RETURNS TRIGGER AS $$
+BEGIN
+    UPDATE "pool_category_statistics"
+    SET "usage_count" = "usage_count" + (SELECT "usage_count" FROM "pool_category_statistics" WHERE "category_id" = OLD."id")
+    WHERE "category_id" = 0;
+
+    RETURN OLD;
+END;
+$$ LANGUAGE plpgsql;
+
+CREATE TRIGGER default_category_update_trigger BEFORE DELETE ON "tag_category"
+FOR EACH ROW EXECUTE FUNCTION update_default_tag_category_statistics();
+
+CREATE TRIGGER default_pool_category_update_trigger BEFORE DELETE ON "pool_category"
+FOR EACH ROW EXECUTE FUNCTION update_default_pool_category_statistics();
@@ -0,0 +1,12 @@
+-- Tags and pools of a deleted category are already counted towards the default category
+-- by their own update triggers when ON DELETE SET DEFAULT reassigns them
+DROP TRIGGER default_category_update_trigger ON "tag_category";
+DROP TRIGGER default_pool_category_update_trigger ON "pool_category";
+DROP FUNCTION update_default_tag_category_statistics, update_default_pool_category_statistics;
+
+-- Recompute usage counts that were inflated by past category deletions
+UPDATE "tag_category_statistics"
+SET "usage_count" = (SELECT COUNT(*) FROM "tag" WHERE "tag"."category_id" = "tag_category_statistics"."category_id");
+
+UPDATE "pool_category_statistics"
+SET "usage_count" = (SELECT COUNT(*) FROM "pool" WHERE "pool"."category_id" = "pool_category_statistics"."category_id");
@@ -0,0 +1,2 @@
+ALTER TABLE "user" DROP COLUMN "email_verification_token";
+ALTER TABLE "user" DROP COLUMN "email_verified";
@@ -0,0 +1,6 @@
+CREATE TABLE "password_reset_token" (
+    "user_id" BIGINT PRIMARY KEY REFERENCES "user" ON DELETE CASCADE,
+    "token_hash" TEXT NOT NULL UNIQUE,
+    "expiration_time" TIMESTAMP WITH TIME ZONE NOT NULL,
+    "creation_time" TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
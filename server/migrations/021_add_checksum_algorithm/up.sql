@@ -0,0 +1,2 @@
+-- Existing checksums were all computed with BLAKE3, which is encoded as 0
+ALTER TABLE "post" ADD COLUMN "checksum_algorithm" SMALLINT NOT NULL DEFAULT 0;
@@ -0,0 +1 @@
+CREATE INDEX "idx_post_width_height" ON "post" USING BTREE ("width", "height");
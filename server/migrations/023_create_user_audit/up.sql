@@ -0,0 +1,9 @@
+CREATE TABLE "user_audit" (
+    "id" BIGSERIAL PRIMARY KEY,
+    "user_id" BIGINT NOT NULL REFERENCES "user" ON DELETE CASCADE,
+    "changed_by" BIGINT REFERENCES "user" ON DELETE SET NULL,
+    "old_rank" SMALLINT NOT NULL,
+    "new_rank" SMALLINT NOT NULL,
+    "changed_at" TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+CREATE INDEX "idx_user_audit_user_id" ON "user_audit" USING BTREE ("user_id");
@@ -0,0 +1 @@
+ALTER TABLE "post_statistics" DROP COLUMN "view_count";
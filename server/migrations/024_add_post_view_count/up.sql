@@ -0,0 +1,2 @@
+-- Views are counted in memory and flushed periodically, so they are not maintained by triggers
+ALTER TABLE "post_statistics" ADD COLUMN "view_count" BIGINT NOT NULL DEFAULT 0;
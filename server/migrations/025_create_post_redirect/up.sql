@@ -0,0 +1,7 @@
+-- Posts removed by a merge redirect to the post they were merged into
+CREATE TABLE "post_redirect" (
+    "removed_id" BIGINT PRIMARY KEY,
+    "target_id" BIGINT NOT NULL REFERENCES "post" ON DELETE CASCADE,
+    "merged_at" TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
+CREATE INDEX "idx_post_redirect_target_id" ON "post_redirect" USING BTREE ("target_id");
@@ -0,0 +1,11 @@
+-- Long-running administrative jobs started through the API
+CREATE TABLE "background_job" (
+    "id" BIGSERIAL PRIMARY KEY,
+    "name" TEXT NOT NULL,
+    "total" BIGINT NOT NULL,
+    "processed" BIGINT NOT NULL DEFAULT 0,
+    "errors" BIGINT NOT NULL DEFAULT 0,
+    "started_at" TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    "completed_at" TIMESTAMP WITH TIME ZONE
+);
+CREATE INDEX "idx_background_job_name" ON "background_job" USING BTREE ("name");
@@ -0,0 +1,2 @@
+-- Pinned comments are listed before all others on their post
+ALTER TABLE "comment" ADD COLUMN "pinned" BOOLEAN NOT NULL DEFAULT FALSE;
@@ -0,0 +1 @@
+ALTER TABLE "background_job" DROP COLUMN "failed";
@@ -0,0 +1,2 @@
+-- Jobs that panicked or were interrupted by a server restart are marked as failed
+ALTER TABLE "background_job" ADD COLUMN "failed" BOOLEAN NOT NULL DEFAULT FALSE;
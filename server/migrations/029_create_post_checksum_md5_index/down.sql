@@ -0,0 +1 @@
+DROP INDEX "idx_post_checksum_md5";
@@ -0,0 +1,2 @@
+-- Posts can be looked up by the MD5 checksum of their content
+CREATE INDEX "idx_post_checksum_md5" ON "post" USING BTREE ("checksum_md5");
@@ -155,13 +155,23 @@ pub fn reset_thumbnail_sizes(conn: &mut PgConnection) -> ApiResult<()> {
     Ok(())
 }
 
-pub fn reset_relation_stats() -> ApiResult<()> {
-    let mut conn = db::get_connection()?;
-    let comment_count: i64 = comment::table.count().first(&mut conn)?;
-    let pool_count: i64 = pool::table.count().first(&mut conn)?;
-    let post_count: i64 = post::table.count().first(&mut conn)?;
-    let tag_count: i64 = tag::table.count().first(&mut conn)?;
-    let user_count: i64 = user::table.count().first(&mut conn)?;
+/// Recounts the rows cached in `database_statistics` and updates the statistics
+/// used by the query planner. Cheap enough to be run periodically on a live server.
+pub fn refresh_statistics(conn: &mut PgConnection) -> QueryResult<()> {
+    let _timer = Timer::new("refresh_statistics");
+    reset_row_counts(conn)?;
+
+    // VACUUM can't run inside a transaction, so this must not be called from one
+    diesel::sql_query("VACUUM ANALYZE").execute(conn)?;
+    Ok(())
+}
+
+fn reset_row_counts(conn: &mut PgConnection) -> QueryResult<()> {
+    let comment_count: i64 = comment::table.count().first(conn)?;
+    let pool_count: i64 = pool::table.count().first(conn)?;
+    let post_count: i64 = post::table.count().first(conn)?;
+    let tag_count: i64 = tag::table.count().first(conn)?;
+    let user_count: i64 = user::table.count().first(conn)?;
     diesel::update(database_statistics::table)
         .set((
             database_statistics::comment_count.eq(comment_count),
@@ -170,7 +180,13 @@ pub fn reset_relation_stats() -> ApiResult<()> {
             database_statistics::tag_count.eq(tag_count),
             database_statistics::user_count.eq(user_count),
         ))
-        .execute(&mut conn)?;
+        .execute(conn)?;
+    Ok(())
+}
+
+pub fn reset_relation_stats() -> ApiResult<()> {
+    let mut conn = db::get_connection()?;
+    reset_row_counts(&mut conn)?;
 
     let comment_stats: Vec<(i64, Option<i64>)> = comment::table
         .left_join(comment_score::table)
@@ -0,0 +1,194 @@
+use crate::admin::{DatabaseResult, PRINT_INTERVAL, ProgressReporter, post};
+use crate::db;
+use crate::schema::background_job;
+use crate::time::{DateTime, Timer};
+use diesel::pg::Pg;
+use diesel::prelude::*;
+use serde::Serialize;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use utoipa::ToSchema;
+
+const RECOMPUTE_SIGNATURES: &str = "recompute_signatures";
+
+/// Time between writes of a running job's progress to the database.
+const PERSIST_INTERVAL: Duration = Duration::from_secs(10);
+
+/// The most recent signature recomputation started by this server instance.
+static SIGNATURE_JOB: Mutex<Option<Arc<SignatureJob>>> = Mutex::new(None);
+
+#[derive(Clone, Serialize, ToSchema, Queryable, Selectable)]
+#[diesel(table_name = background_job)]
+#[diesel(check_for_backend(Pg))]
+#[serde(rename_all = "camelCase")]
+pub struct JobProgress {
+    pub id: i64,
+    /// Number of posts the job will process.
+    pub total: i64,
+    /// Number of posts processed so far, including ones that failed.
+    pub processed: i64,
+    /// Number of posts whose content could not be read or decoded.
+    pub errors: i64,
+    pub started_at: DateTime,
+    /// Time the job finished. `null` while the job is still running.
+    pub completed_at: Option<DateTime>,
+    /// Whether the job was aborted by an error, a panic, or a server restart.
+    pub failed: bool,
+}
+
+struct SignatureJob {
+    id: i64,
+    total: i64,
+    started_at: DateTime,
+    computed: ProgressReporter,
+    errors: ProgressReporter,
+    completed_at: Mutex<Option<DateTime>>,
+    failed: AtomicBool,
+}
+
+impl SignatureJob {
+    fn progress(&self) -> JobProgress {
+        let errors = self.errors.count() as i64;
+        JobProgress {
+            id: self.id,
+            total: self.total,
+            processed: self.computed.count() as i64 + errors,
+            errors,
+            started_at: self.started_at,
+            completed_at: *self.completed_at.lock().unwrap(),
+            failed: self.failed.load(Ordering::SeqCst),
+        }
+    }
+
+    fn is_running(&self) -> bool {
+        self.completed_at.lock().unwrap().is_none()
+    }
+
+    /// Writes the current progress to the database, unless the job has already been completed.
+    fn persist_progress(&self) -> DatabaseResult<()> {
+        let progress = self.progress();
+        diesel::update(background_job::table.find(self.id))
+            .filter(background_job::completed_at.is_null())
+            .set((background_job::processed.eq(progress.processed), background_job::errors.eq(progress.errors)))
+            .execute(&mut db::get_connection()?)?;
+        Ok(())
+    }
+
+    /// Records the final progress of the job, both in memory and in the database.
+    fn complete(&self, failed: bool) {
+        let progress = self.progress();
+        let completed_at = DateTime::now();
+        let record_completion = || -> DatabaseResult<()> {
+            diesel::update(background_job::table.find(self.id))
+                .set((
+                    background_job::processed.eq(progress.processed),
+                    background_job::errors.eq(progress.errors),
+                    background_job::completed_at.eq(completed_at),
+                    background_job::failed.eq(failed),
+                ))
+                .execute(&mut db::get_connection()?)?;
+            Ok(())
+        };
+        if let Err(err) = record_completion() {
+            eprintln!("ERROR: Could not record completion of job {}: {err}", self.id);
+        }
+        self.failed.store(failed, Ordering::SeqCst);
+        *self.completed_at.lock().unwrap() = Some(completed_at);
+    }
+}
+
+/// Completes a job when dropped, so that a job which panics is still marked as failed.
+struct CompletionGuard<'a> {
+    job: &'a SignatureJob,
+    failed: bool,
+}
+
+impl Drop for CompletionGuard<'_> {
+    fn drop(&mut self) {
+        self.job.complete(self.failed || std::thread::panicking());
+    }
+}
+
+/// Starts recomputing the signatures of all posts in the background and returns its initial progress.
+/// Returns `None` if a recomputation is already running.
+pub fn start_signature_recomputation() -> DatabaseResult<Option<JobProgress>> {
+    let mut current_job = SIGNATURE_JOB.lock().unwrap();
+    if current_job.as_ref().is_some_and(|job| job.is_running()) {
+        return Ok(None);
+    }
+
+    let post_ids = post::prepare_signature_recomputation()?;
+    let total = post_ids.len() as i64;
+    let (id, started_at) = diesel::insert_into(background_job::table)
+        .values((background_job::name.eq(RECOMPUTE_SIGNATURES), background_job::total.eq(total)))
+        .returning((background_job::id, background_job::started_at))
+        .get_result(&mut db::get_connection()?)?;
+
+    let job = Arc::new(SignatureJob {
+        id,
+        total,
+        started_at,
+        computed: ProgressReporter::new("Signatures computed", PRINT_INTERVAL),
+        errors: ProgressReporter::new("Signature errors", PRINT_INTERVAL),
+        completed_at: Mutex::new(None),
+        failed: AtomicBool::new(false),
+    });
+    let progress = job.progress();
+    *current_job = Some(job.clone());
+    tokio::spawn(persist_progress_periodically(job.clone()));
+    tokio::task::spawn_blocking(move || run_signature_job(&job, post_ids));
+    Ok(Some(progress))
+}
+
+/// Returns the progress of the signature recomputation started most recently by this server instance.
+pub fn signature_job_progress() -> Option<JobProgress> {
+    SIGNATURE_JOB.lock().unwrap().as_ref().map(|job| job.progress())
+}
+
+/// Marks jobs left unfinished by a previous server instance as failed, as nothing will resume them.
+/// Must be called on startup, before any new job is started.
+pub fn fail_interrupted_jobs() -> DatabaseResult<()> {
+    let interrupted_jobs = diesel::update(background_job::table)
+        .filter(background_job::completed_at.is_null())
+        .set((background_job::completed_at.eq(DateTime::now()), background_job::failed.eq(true)))
+        .execute(&mut db::get_connection()?)?;
+    if interrupted_jobs > 0 {
+        println!("Marked {interrupted_jobs} interrupted background job(s) as failed");
+    }
+    Ok(())
+}
+
+/// Retrieves the most recent signature recomputation recorded in the database.
+pub fn last_signature_job(conn: &mut PgConnection) -> QueryResult<JobProgress> {
+    background_job::table
+        .select(JobProgress::as_select())
+        .filter(background_job::name.eq(RECOMPUTE_SIGNATURES))
+        .order_by(background_job::id.desc())
+        .first(conn)
+}
+
+async fn persist_progress_periodically(job: Arc<SignatureJob>) {
+    loop {
+        tokio::time::sleep(PERSIST_INTERVAL).await;
+        if !job.is_running() {
+            return;
+        }
+
+        let job = job.clone();
+        match tokio::task::spawn_blocking(move || job.persist_progress()).await {
+            Ok(Ok(())) => (),
+            Ok(Err(err)) => eprintln!("ERROR: Could not persist job progress: {err}"),
+            Err(err) => eprintln!("ERROR: Job progress task failed: {err}"),
+        }
+    }
+}
+
+fn run_signature_job(job: &SignatureJob, post_ids: Vec<i64>) {
+    let _timer = Timer::new("recompute_signatures");
+    let mut completion = CompletionGuard { job, failed: false };
+    if let Err(err) = post::recompute_signatures_of(post_ids, &job.computed, &job.errors) {
+        eprintln!("ERROR: Signature recomputation failed: {err}");
+        completion.failed = true;
+    }
+}
@@ -1,10 +1,10 @@
 pub mod database;
+pub mod job;
 mod post;
 mod user;
 
 use crate::db;
-use diesel::prelude::*;
-use diesel::r2d2::{ConnectionManager, PoolError, PooledConnection};
+use diesel::r2d2::PoolError;
 use std::io::Write;
 use std::path::Path;
 use std::str::FromStr;
@@ -83,12 +83,16 @@ impl ProgressReporter {
         }
     }
 
+    fn count(&self) -> u64 {
+        self.count.load(Ordering::SeqCst)
+    }
+
     fn report(&self) {
-        println!("{}: {}", self.message, self.count.load(Ordering::SeqCst));
+        println!("{}: {}", self.message, self.count());
     }
 }
 
-fn get_connection() -> Result<PooledConnection<ConnectionManager<PgConnection>>, String> {
+fn get_connection() -> Result<db::Connection, String> {
     db::get_connection().map_err(|err| format!("Could not connect to the database: {err}"))
 }
 
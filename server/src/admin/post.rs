@@ -32,17 +32,35 @@ pub fn recompute_checksums() -> DatabaseResult<()> {
 pub fn recompute_signatures() -> DatabaseResult<()> {
     let _timer = Timer::new("recompute_signatures");
     let progress = ProgressReporter::new("Signatures computed", PRINT_INTERVAL);
+    let error_count = ProgressReporter::new("Signature errors", PRINT_INTERVAL);
 
+    let post_ids = prepare_signature_recomputation()?;
+    recompute_signatures_of(post_ids, &progress, &error_count)?;
+    error_count.report();
+    Ok(())
+}
+
+/// Retrieves the ids of all posts and bumps the signature version.
+pub(super) fn prepare_signature_recomputation() -> DatabaseResult<Vec<i64>> {
     let post_ids: Vec<_> = post::table.select(post::id).load(&mut db::get_connection()?)?;
 
     // Update signature version only after a successful data retrieval
     diesel::update(database_statistics::table)
         .set(database_statistics::signature_version.eq(SIGNATURE_VERSION))
         .execute(&mut db::get_connection()?)?;
+    Ok(post_ids)
+}
 
+/// Recomputes signatures for the posts in `post_ids`. Posts whose content can't be read or
+/// decoded are skipped and counted in `error_count`.
+pub(super) fn recompute_signatures_of(
+    post_ids: Vec<i64>,
+    progress: &ProgressReporter,
+    error_count: &ProgressReporter,
+) -> DatabaseResult<()> {
     post_ids
         .into_par_iter()
-        .try_for_each(|post_id| recompute_signature(post_id, &progress))
+        .try_for_each(|post_id| recompute_signature(post_id, progress, error_count))
 }
 
 /// Recomputes post signature indexes.
@@ -146,7 +164,7 @@ pub fn recompute_checksum(
         }
     };
 
-    let checksum = hash::compute_checksum(&file_contents);
+    let (checksum, checksum_algorithm) = hash::compute_checksum(&file_contents);
     let md5_checksum = hash::compute_md5_checksum(&file_contents);
     let duplicate: Option<i64> = post::table
         .select(post::id)
@@ -161,14 +179,22 @@ pub fn recompute_checksum(
     }
 
     diesel::update(post::table.find(post_id))
-        .set((post::checksum.eq(checksum), post::checksum_md5.eq(md5_checksum)))
+        .set((
+            post::checksum.eq(checksum),
+            post::checksum_md5.eq(md5_checksum),
+            post::checksum_algorithm.eq(checksum_algorithm),
+        ))
         .execute(&mut conn)?;
     progress.increment();
     Ok(())
 }
 
 /// Recomputes signature for post with id `post_id`.
-fn recompute_signature(post_id: i64, progress: &ProgressReporter) -> DatabaseResult<()> {
+fn recompute_signature(
+    post_id: i64,
+    progress: &ProgressReporter,
+    error_count: &ProgressReporter,
+) -> DatabaseResult<()> {
     let mut conn = db::get_connection()?;
     let mime_type = match post::table
         .find(post_id)
@@ -180,6 +206,7 @@ fn recompute_signature(post_id: i64, progress: &ProgressReporter) -> DatabaseRes
         Ok(None) => return Ok(()), // Post must have been deleted after starting task, skip
         Err(err) => {
             eprintln!("ERROR: Cannot retrieve MIME type for post {post_id} for reason: {err}");
+            error_count.increment();
             return Ok(());
         }
     };
@@ -189,6 +216,7 @@ fn recompute_signature(post_id: i64, progress: &ProgressReporter) -> DatabaseRes
         Ok(contents) => contents,
         Err(err) => {
             eprintln!("ERROR: Unable to read file for post {post_id} for reason: {err}");
+            error_count.increment();
             return Ok(());
         }
     };
@@ -198,6 +226,7 @@ fn recompute_signature(post_id: i64, progress: &ProgressReporter) -> DatabaseRes
         Ok(image) => image,
         Err(err) => {
             eprintln!("ERROR: Unable to get representative image for post {post_id} for reason: {err}");
+            error_count.increment();
             return Ok(());
         }
     };
@@ -0,0 +1,144 @@
+use crate::admin::database;
+use crate::admin::job::{self, JobProgress};
+use crate::api::{ApiResult, AuthResult};
+use crate::{api, config, db};
+use futures::{Stream, StreamExt};
+use std::time::Duration;
+use warp::sse::Event;
+use warp::{Filter, Rejection, Reply};
+
+/// Time between progress events sent to clients watching a background job.
+const PROGRESS_INTERVAL: Duration = Duration::from_secs(1);
+
+pub fn routes() -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    let refresh_statistics = warp::post()
+        .and(api::auth())
+        .and(warp::path!("admin" / "refresh-statistics"))
+        .map(refresh_statistics)
+        .map(api::Reply::from);
+    let recompute_signatures = warp::post()
+        .and(api::auth())
+        .and(warp::path!("admin" / "recompute-signatures"))
+        .map(recompute_signatures)
+        .map(api::Reply::from);
+    let recompute_signatures_progress = warp::get()
+        .and(api::auth())
+        .and(warp::path!("admin" / "recompute-signatures" / "progress"))
+        .map(|auth| match recompute_signatures_progress(auth) {
+            Ok(events) => warp::sse::reply(warp::sse::keep_alive().stream(events)).into_response(),
+            Err(err) => api::Reply::from(Err::<(), _>(err)).into_response(),
+        });
+
+    refresh_statistics
+        .or(recompute_signatures)
+        .or(recompute_signatures_progress)
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/refresh-statistics",
+    summary = "Refreshes database statistics",
+    responses((status = 200))
+)]
+fn refresh_statistics(auth: AuthResult) -> ApiResult<()> {
+    let client = auth?;
+    api::verify_privilege(client, config::privileges().statistics_refresh)?;
+
+    let mut conn = db::get_connection()?;
+    database::refresh_statistics(&mut conn).map_err(api::Error::from)
+}
+
+#[utoipa::path(
+    post,
+    path = "/admin/recompute-signatures",
+    summary = "Starts recomputing the signatures of all posts",
+    responses((status = 200, body = JobProgress))
+)]
+fn recompute_signatures(auth: AuthResult) -> ApiResult<JobProgress> {
+    let client = auth?;
+    api::verify_privilege(client, config::privileges().signature_recompute)?;
+
+    job::start_signature_recomputation()?.ok_or(api::Error::JobAlreadyRunning)
+}
+
+#[utoipa::path(
+    get,
+    path = "/admin/recompute-signatures/progress",
+    summary = "Streams the progress of the latest signature recomputation as server-sent events",
+    responses((status = 200, content_type = "text/event-stream", body = JobProgress))
+)]
+fn recompute_signatures_progress(
+    auth: AuthResult,
+) -> ApiResult<impl Stream<Item = Result<Event, warp::Error>> + Send + 'static> {
+    let client = auth?;
+    api::verify_privilege(client, config::privileges().signature_recompute)?;
+
+    // Jobs started before the last restart are no longer running, so report their final state once
+    let progress = match job::signature_job_progress() {
+        Some(progress) => progress,
+        None => job::last_signature_job(&mut db::get_connection()?)?,
+    };
+    let updates = futures::stream::unfold(progress.completed_at.is_none(), |running| async move {
+        if !running {
+            return None;
+        }
+        tokio::time::sleep(PROGRESS_INTERVAL).await;
+        let progress = job::signature_job_progress()?;
+        let running = progress.completed_at.is_none();
+        Some((progress, running))
+    });
+    Ok(futures::stream::once(async { progress })
+        .chain(updates)
+        .map(|progress| Event::default().json_data(progress)))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::admin::job;
+    use crate::api::ApiResult;
+    use crate::test::*;
+    use serial_test::serial;
+    use warp::http::StatusCode;
+
+    #[tokio::test]
+    #[serial]
+    async fn refresh_statistics() {
+        const QUERY: &str = "POST /admin/refresh-statistics";
+        verify_status_with_user("regular_user", QUERY, StatusCode::FORBIDDEN).await;
+        verify_status_with_user("administrator", QUERY, StatusCode::OK).await;
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn recompute_signatures() -> ApiResult<()> {
+        const QUERY: &str = "POST /admin/recompute-signatures";
+        const PROGRESS_QUERY: &str = "GET /admin/recompute-signatures/progress";
+        verify_status_with_user("moderator", QUERY, StatusCode::FORBIDDEN).await;
+        verify_status_with_user("moderator", PROGRESS_QUERY, StatusCode::FORBIDDEN).await;
+        // No job has been recorded in the test database
+        verify_status_with_user("administrator", PROGRESS_QUERY, StatusCode::NOT_FOUND).await;
+
+        // None of the test posts have content on disk, so every post is counted as an error
+        verify_status_with_user("administrator", QUERY, StatusCode::OK).await;
+        let body = reply_body_with_user("administrator", PROGRESS_QUERY, StatusCode::OK).await;
+        let events: Vec<serde_json::Value> = body
+            .lines()
+            .filter_map(|line| line.strip_prefix("data:"))
+            .map(|data| serde_json::from_str(data.trim()).unwrap())
+            .collect();
+        assert!(!events.is_empty());
+
+        // The stream ends with the completed state of the job
+        let completion = events.last().unwrap();
+        assert!(!completion["completedAt"].is_null());
+        assert_eq!(completion["failed"], false);
+        assert_eq!(completion["processed"], completion["total"]);
+        assert_eq!(completion["errors"], completion["total"]);
+
+        let recorded_job = job::last_signature_job(&mut get_connection()?)?;
+        assert!(recorded_job.completed_at.is_some());
+        assert!(!recorded_job.failed);
+        assert_eq!(recorded_job.processed, recorded_job.total);
+        Ok(reset_database())
+    }
+}
@@ -9,6 +9,7 @@ use crate::{api, config, db, resource};
 use diesel::dsl::exists;
 use diesel::prelude::*;
 use serde::Deserialize;
+use utoipa::ToSchema;
 use warp::{Filter, Rejection, Reply};
 
 pub fn routes() -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
@@ -28,58 +29,81 @@ pub fn routes() -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone
         .and(api::auth())
         .and(warp::path!("comments"))
         .and(api::resource_query())
-        .and(warp::body::json())
+        .and(api::json_body())
         .map(create)
         .map(api::Reply::from);
     let update = warp::put()
         .and(api::auth())
         .and(warp::path!("comment" / i64))
         .and(api::resource_query())
-        .and(warp::body::json())
+        .and(api::json_body())
         .map(update)
         .map(api::Reply::from);
     let rate = warp::put()
         .and(api::auth())
         .and(warp::path!("comment" / i64 / "score"))
         .and(api::resource_query())
-        .and(warp::body::json())
+        .and(api::json_body())
         .map(rate)
         .map(api::Reply::from);
+    let pin = warp::post()
+        .and(api::auth())
+        .and(warp::path!("comment" / i64 / "pin"))
+        .and(api::resource_query())
+        .map(pin)
+        .map(api::Reply::from);
+    let unpin = warp::post()
+        .and(api::auth())
+        .and(warp::path!("comment" / i64 / "unpin"))
+        .and(api::resource_query())
+        .map(unpin)
+        .map(api::Reply::from);
     let delete = warp::delete()
         .and(api::auth())
         .and(warp::path!("comment" / i64))
-        .and(warp::body::json())
+        .and(api::json_body())
         .map(delete)
         .map(api::Reply::from);
 
-    list.or(get).or(create).or(update).or(rate).or(delete)
+    list.or(get).or(create).or(update).or(rate).or(pin).or(unpin).or(delete)
 }
 
-const MAX_COMMENTS_PER_PAGE: i64 = 1000;
-
+#[utoipa::path(
+    get,
+    path = "/comments",
+    summary = "Lists comments",
+    params(PageParams, ResourceParams),
+    responses((status = 200, body = PagedResponse<CommentInfo>))
+)]
 fn list(auth: AuthResult, params: PageParams) -> ApiResult<PagedResponse<CommentInfo>> {
     let client = auth?;
     api::verify_privilege(client, config::privileges().comment_list)?;
 
     let offset = params.offset.unwrap_or(0);
-    let limit = std::cmp::min(params.limit.get(), MAX_COMMENTS_PER_PAGE);
+    let pagination = config::pagination();
+    let limit = params.limit(pagination.default_comment_page_size, pagination.max_comment_page_size);
     let fields = resource::create_table(params.fields()).map_err(Box::from)?;
     db::get_connection()?.transaction(|conn| {
         let mut query_builder = QueryBuilder::new(params.criteria())?;
-        query_builder.set_offset_and_limit(offset, limit);
-
-        let total = query_builder.count(conn)?;
-        let selected_comments = query_builder.load(conn)?;
+        let page = api::load_page(conn, &mut query_builder, offset, limit)?;
         Ok(PagedResponse {
             query: params.into_query(),
             offset,
             limit,
-            total,
-            results: CommentInfo::new_batch_from_ids(conn, client, selected_comments, &fields)?,
+            total: page.total,
+            has_more: page.has_more,
+            results: CommentInfo::new_batch_from_ids(conn, client, page.ids, &fields)?,
         })
     })
 }
 
+#[utoipa::path(
+    get,
+    path = "/comment/{id}",
+    summary = "Gets a comment",
+    params(("id" = i64, Path), ResourceParams),
+    responses((status = 200, body = CommentInfo))
+)]
 fn get(auth: AuthResult, comment_id: i64, params: ResourceParams) -> ApiResult<CommentInfo> {
     let client = auth?;
     api::verify_privilege(client, config::privileges().comment_view)?;
@@ -94,14 +118,23 @@ fn get(auth: AuthResult, comment_id: i64, params: ResourceParams) -> ApiResult<C
     })
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 #[serde(deny_unknown_fields)]
 #[serde(rename_all = "camelCase")]
+#[schema(as = CommentCreateBody)]
 struct CreateBody {
     post_id: i64,
     text: String,
 }
 
+#[utoipa::path(
+    post,
+    path = "/comments",
+    summary = "Creates a comment",
+    params(ResourceParams),
+    request_body = CreateBody,
+    responses((status = 200, body = CommentInfo))
+)]
 fn create(auth: AuthResult, params: ResourceParams, body: CreateBody) -> ApiResult<CommentInfo> {
     let client = auth?;
     api::verify_privilege(client, config::privileges().comment_create)?;
@@ -123,13 +156,22 @@ fn create(auth: AuthResult, params: ResourceParams, body: CreateBody) -> ApiResu
     conn.transaction(|conn| CommentInfo::new_from_id(conn, client, comment_id, &fields).map_err(api::Error::from))
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 #[serde(deny_unknown_fields)]
+#[schema(as = CommentUpdateBody)]
 struct UpdateBody {
     version: DateTime,
     text: String,
 }
 
+#[utoipa::path(
+    put,
+    path = "/comment/{id}",
+    summary = "Updates a comment",
+    params(("id" = i64, Path), ResourceParams),
+    request_body = UpdateBody,
+    responses((status = 200, body = CommentInfo))
+)]
 fn update(auth: AuthResult, comment_id: i64, params: ResourceParams, body: UpdateBody) -> ApiResult<CommentInfo> {
     let client = auth?;
     let fields = resource::create_table(params.fields()).map_err(Box::from)?;
@@ -142,11 +184,13 @@ fn update(auth: AuthResult, comment_id: i64, params: ResourceParams, body: Updat
             .first(conn)?;
         api::verify_version(comment_version, body.version)?;
 
-        let required_rank = match client.id == comment_owner && comment_owner.is_some() {
-            true => config::privileges().comment_edit_own,
-            false => config::privileges().comment_edit_any,
-        };
-        api::verify_privilege(client, required_rank)?;
+        let privileges = config::privileges();
+        api::verify_self_or_any_privilege(
+            client,
+            comment_owner,
+            privileges.comment_edit_own,
+            privileges.comment_edit_any,
+        )?;
 
         diesel::update(comment::table.find(comment_id))
             .set((comment::text.eq(body.text), comment::last_edit_time.eq(DateTime::now())))
@@ -156,6 +200,14 @@ fn update(auth: AuthResult, comment_id: i64, params: ResourceParams, body: Updat
     conn.transaction(|conn| CommentInfo::new_from_id(conn, client, comment_id, &fields).map_err(api::Error::from))
 }
 
+#[utoipa::path(
+    put,
+    path = "/comment/{id}/score",
+    summary = "Rates a comment",
+    params(("id" = i64, Path), ResourceParams),
+    request_body = RatingBody,
+    responses((status = 200, body = CommentInfo))
+)]
 fn rate(auth: AuthResult, comment_id: i64, params: ResourceParams, body: RatingBody) -> ApiResult<CommentInfo> {
     let client = auth?;
     api::verify_privilege(client, config::privileges().comment_score)?;
@@ -182,6 +234,55 @@ fn rate(auth: AuthResult, comment_id: i64, params: ResourceParams, body: RatingB
     conn.transaction(|conn| CommentInfo::new_from_id(conn, client, comment_id, &fields).map_err(api::Error::from))
 }
 
+#[utoipa::path(
+    post,
+    path = "/comment/{id}/pin",
+    summary = "Pins a comment to the top of its post",
+    params(("id" = i64, Path), ResourceParams),
+    responses((status = 200, body = CommentInfo))
+)]
+fn pin(auth: AuthResult, comment_id: i64, params: ResourceParams) -> ApiResult<CommentInfo> {
+    set_pinned(auth, comment_id, params, true)
+}
+
+#[utoipa::path(
+    post,
+    path = "/comment/{id}/unpin",
+    summary = "Unpins a comment",
+    params(("id" = i64, Path), ResourceParams),
+    responses((status = 200, body = CommentInfo))
+)]
+fn unpin(auth: AuthResult, comment_id: i64, params: ResourceParams) -> ApiResult<CommentInfo> {
+    set_pinned(auth, comment_id, params, false)
+}
+
+/// Sets or clears the pinned flag of the comment with id `comment_id`.
+fn set_pinned(auth: AuthResult, comment_id: i64, params: ResourceParams, pinned: bool) -> ApiResult<CommentInfo> {
+    let client = auth?;
+    api::verify_privilege(client, config::privileges().comment_pin)?;
+
+    let fields = resource::create_table(params.fields()).map_err(Box::from)?;
+    let mut conn = db::get_connection()?;
+    conn.transaction(|conn| {
+        let updated_rows = diesel::update(comment::table.find(comment_id))
+            .set(comment::pinned.eq(pinned))
+            .execute(conn)?;
+        if updated_rows == 0 {
+            return Err(api::Error::NotFound(ResourceType::Comment));
+        }
+        Ok(())
+    })?;
+    conn.transaction(|conn| CommentInfo::new_from_id(conn, client, comment_id, &fields).map_err(api::Error::from))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/comment/{id}",
+    summary = "Deletes a comment",
+    params(("id" = i64, Path)),
+    request_body = DeleteBody,
+    responses((status = 200, description = "Comment was deleted"))
+)]
 fn delete(auth: AuthResult, comment_id: i64, client_version: DeleteBody) -> ApiResult<()> {
     let client = auth?;
 
@@ -192,11 +293,13 @@ fn delete(auth: AuthResult, comment_id: i64, client_version: DeleteBody) -> ApiR
             .first(conn)?;
         api::verify_version(comment_version, *client_version)?;
 
-        let required_rank = match client.id == comment_owner && comment_owner.is_some() {
-            true => config::privileges().comment_delete_own,
-            false => config::privileges().comment_delete_any,
-        };
-        api::verify_privilege(client, required_rank)?;
+        let privileges = config::privileges();
+        api::verify_self_or_any_privilege(
+            client,
+            comment_owner,
+            privileges.comment_delete_own,
+            privileges.comment_delete_any,
+        )?;
 
         diesel::delete(comment::table.find(comment_id)).execute(conn)?;
         Ok(())
@@ -213,6 +316,7 @@ mod test {
     use diesel::dsl::exists;
     use diesel::prelude::*;
     use serial_test::{parallel, serial};
+    use warp::http::StatusCode;
 
     // Exclude fields that involve creation_time or last_edit_time
     const FIELDS: &str = "&fields=id,postId,text,user,score,ownScore";
@@ -224,6 +328,9 @@ mod test {
         const SORT: &str = "-sort:id&limit=40";
         verify_query(&format!("{QUERY}={SORT}{FIELDS}"), "comment/list.json").await?;
         verify_query(&format!("{QUERY}=sort:score&limit=1{FIELDS}"), "comment/list_highest_score.json").await?;
+        verify_query(&format!("{QUERY}=score:1.. {SORT}{FIELDS}"), "comment/list_positive_score.json").await?;
+        verify_query(&format!("{QUERY}=score:..-1 {SORT}{FIELDS}"), "comment/list_negative_score.json").await?;
+        verify_query(&format!("{QUERY}=score:0 {SORT}{FIELDS}"), "comment/list_zero_score.json").await?;
         verify_query(&format!("{QUERY}=user:regular_user {SORT}{FIELDS}"), "comment/list_regular_user.json").await?;
         verify_query(&format!("{QUERY}=text:*this* {SORT}{FIELDS}"), "comment/list_text_filter.json").await
     }
@@ -360,4 +467,18 @@ mod test {
         assert_eq!(new_last_edit_time, last_edit_time);
         Ok(())
     }
+
+    #[tokio::test]
+    #[serial]
+    async fn pin() -> ApiResult<()> {
+        const COMMENT_ID: i64 = 2;
+        const POST_QUERY: &str = "GET /post/1/?fields=id,comments.id,comments.pinned";
+        verify_status_with_user("regular_user", "POST /comment/2/pin", StatusCode::FORBIDDEN).await;
+
+        verify_query(&format!("POST /comment/{COMMENT_ID}/pin/?{FIELDS},pinned"), "comment/pin.json").await?;
+        verify_query(POST_QUERY, "post/get_1_pinned_comment.json").await?;
+
+        verify_query(&format!("POST /comment/{COMMENT_ID}/unpin/?{FIELDS},pinned"), "comment/unpin.json").await?;
+        verify_query(POST_QUERY, "post/get_1_unpinned_comment.json").await
+    }
 }
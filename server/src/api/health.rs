@@ -0,0 +1,58 @@
+use crate::api::ApiResult;
+use crate::{api, db};
+use diesel::prelude::*;
+use warp::{Filter, Rejection, Reply};
+
+pub fn routes() -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    let liveness = warp::get()
+        .and(warp::path!("healthz"))
+        .map(liveness)
+        .map(api::Reply::from);
+    let readiness = warp::get()
+        .and(warp::path!("readyz"))
+        .map(readiness)
+        .map(api::Reply::from);
+
+    liveness.or(readiness)
+}
+
+#[utoipa::path(
+    get,
+    path = "/healthz",
+    summary = "Checks if the server is running",
+    responses((status = 200, description = "Server is running"))
+)]
+fn liveness() -> ApiResult<()> {
+    Ok(())
+}
+
+#[utoipa::path(
+    get,
+    path = "/readyz",
+    summary = "Checks if the server can reach the database",
+    responses(
+        (status = 200, description = "Server is ready to handle requests"),
+        (status = 503, description = "Database is unreachable"),
+    )
+)]
+fn readiness() -> ApiResult<()> {
+    let mut conn = db::get_connection()?;
+    diesel::sql_query("SELECT 1")
+        .execute(&mut conn)
+        .map(|_| ())
+        .map_err(api::Error::DatabaseUnavailable)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::test::*;
+    use serial_test::parallel;
+    use warp::http::StatusCode;
+
+    #[tokio::test]
+    #[parallel]
+    async fn probes() {
+        verify_status_with_user("regular_user", "GET /healthz", StatusCode::OK).await;
+        verify_status_with_user("regular_user", "GET /readyz", StatusCode::OK).await;
+    }
+}
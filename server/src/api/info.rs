@@ -7,6 +7,7 @@ use crate::time::DateTime;
 use crate::{api, config, db, resource};
 use diesel::prelude::*;
 use serde::Serialize;
+use utoipa::ToSchema;
 use warp::{Filter, Rejection, Reply};
 
 pub fn routes() -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
@@ -19,8 +20,9 @@ pub fn routes() -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone
 }
 
 // TODO: Remove renames by changing references to these names in client
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
+#[schema(as = InfoResponse)]
 struct Response {
     post_count: i64,
     disk_usage: i64,
@@ -29,8 +31,16 @@ struct Response {
     featuring_user: Option<SmallString>,
     server_time: DateTime,
     config: &'static config::PublicInfo,
+    pagination: &'static config::Pagination,
 }
 
+#[utoipa::path(
+    get,
+    path = "/info",
+    summary = "Gets global info",
+    params(ResourceParams),
+    responses((status = 200, body = Response))
+)]
 fn get(auth: AuthResult, params: ResourceParams) -> ApiResult<Response> {
     let client = auth?;
     params.bump_login(client)?;
@@ -68,6 +78,7 @@ fn get(auth: AuthResult, params: ResourceParams) -> ApiResult<Response> {
             featuring_user,
             server_time: DateTime::now(),
             config: &config::get().public_info,
+            pagination: config::pagination(),
         })
     })
 }
@@ -1,5 +1,9 @@
+mod admin;
 mod comment;
+mod health;
 mod info;
+mod note;
+mod openapi;
 mod password_reset;
 mod pool;
 mod pool_category;
@@ -10,19 +14,34 @@ mod upload;
 mod user;
 mod user_token;
 
+use crate::admin::DatabaseError;
 use crate::auth::header::{self, AuthenticationError, Client};
 use crate::config::RegexType;
 use crate::error::ErrorKind;
 use crate::model::enums::{MimeType, Rating, ResourceType, UserRank};
+use crate::search::Paginate;
 use crate::string::SmallString;
 use crate::time::DateTime;
 use crate::{config, update};
+use futures::{Stream, TryStreamExt};
+use serde::de::DeserializeOwned;
 use serde::{Deserialize, Deserializer, Serialize};
+use serde_with::skip_serializing_none;
 use std::convert::Infallible;
 use std::num::NonZero;
 use std::ops::Deref;
-use warp::http::StatusCode;
-use warp::reply::{Json, Response, WithStatus};
+use std::sync::LazyLock;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use utoipa::{IntoParams, ToSchema};
+use warp::http::header::{
+    CONTENT_SECURITY_POLICY, LOCATION, REFERRER_POLICY, STRICT_TRANSPORT_SECURITY, X_CONTENT_TYPE_OPTIONS,
+    X_FRAME_OPTIONS,
+};
+use warp::http::{HeaderMap, HeaderValue, StatusCode};
+use warp::hyper::body::Buf;
+use warp::multipart::FormData;
+use warp::reject::PayloadTooLarge;
+use warp::reply::{Json, Response, WithHeader, WithStatus};
 use warp::{Filter, Rejection};
 
 pub type ApiResult<T> = Result<T, Error>;
@@ -30,6 +49,7 @@ pub type ApiResult<T> = Result<T, Error>;
 pub enum Reply {
     Json(Json),
     WithStatus(WithStatus<Json>),
+    Redirect(WithHeader<WithStatus<Json>>),
 }
 
 impl warp::Reply for Reply {
@@ -37,6 +57,7 @@ impl warp::Reply for Reply {
         match self {
             Self::Json(reply) => reply.into_response(),
             Self::WithStatus(reply) => reply.into_response(),
+            Self::Redirect(reply) => reply.into_response(),
         }
     }
 }
@@ -46,9 +67,19 @@ impl<T: Serialize> From<ApiResult<T>> for Reply {
         match value {
             Ok(response) => Self::Json(warp::reply::json(&response)),
             Err(err) => {
-                eprintln!("{}: {err}", err.kind());
-                let response = warp::reply::json(&err.response());
-                Self::WithStatus(warp::reply::with_status(response, err.status_code()))
+                let response = warp::reply::with_status(warp::reply::json(&err.response()), err.status_code());
+                match err {
+                    Error::MovedPermanently(path) => {
+                        // The API is served under /api, which the reverse proxy strips before forwarding
+                        let domain = config::get().domain.as_deref().unwrap_or("");
+                        let location = format!("{domain}/api{path}");
+                        Self::Redirect(warp::reply::with_header(response, LOCATION, location))
+                    }
+                    err => {
+                        eprintln!("{}: {err}", err.kind());
+                        Self::WithStatus(response)
+                    }
+                }
             }
         }
     }
@@ -61,10 +92,14 @@ pub enum Error {
     BadHash(#[from] crate::auth::HashError),
     BadIncomingHeader(#[from] warp::http::header::ToStrError),
     BadResponseHeader(#[from] reqwest::header::ToStrError),
+    #[error("Uploaded content has expired or was never uploaded. Please upload it again.")]
+    ContentTokenExpired,
     #[error("File of type {0} did not match request with content-type '{1}'")]
     ContentTypeMismatch(MimeType, SmallString),
     #[error("Cyclic dependency detected in {0}s")]
     CyclicDependency(ResourceType),
+    #[error("Database is unavailable: {0}")]
+    DatabaseUnavailable(diesel::result::Error),
     #[error("Cannot delete default {0}")]
     DeleteDefault(ResourceType),
     #[error("SWF has no decodable images")]
@@ -78,19 +113,28 @@ pub enum Error {
     FailedEmailTransport(#[from] lettre::transport::smtp::Error),
     FailedQuery(#[from] diesel::result::Error),
     FromStr(#[from] Box<dyn std::error::Error>),
+    #[error("Tag implications would form a cycle: {0}")]
+    ImplicationCycle(String),
+    #[error("Old password is incorrect")]
+    IncorrectPassword,
     #[error("Insufficient privileges")]
     InsufficientPrivileges,
     InvalidEmailAddress(#[from] lettre::address::AddressError),
     InvalidEmail(#[from] lettre::error::Error),
+    InvalidFileSize(#[from] crate::search::FileSizeParsingError),
     InvalidHeader(#[from] reqwest::header::InvalidHeaderValue),
     #[error("Metadata must be application/json")]
     InvalidMetadataType,
+    #[error("Reordered posts must be distinct posts of the pool")]
+    InvalidPoolOrder,
     #[error("Invalid sort token")]
     InvalidSort,
     InvalidTime(#[from] crate::search::TimeParsingError),
     #[error("Cannot create an anonymous user")]
     InvalidUserRank,
     Image(#[from] image::ImageError),
+    #[error("Job is already running")]
+    JobAlreadyRunning,
     JsonSerialization(#[from] serde_json::Error),
     #[error("Missing {0} content")]
     MissingContent(ResourceType),
@@ -100,10 +144,10 @@ pub enum Error {
     MissingFormData,
     #[error("Missing metadata form")]
     MissingMetadata,
-    #[error("Missing smtp info")]
-    MissingSmtpInfo,
-    #[error("User has no email")]
-    NoEmail,
+    #[error("Resource has moved to {0}")]
+    MovedPermanently(String),
+    #[error("Post has no EXIF data")]
+    NoExif,
     #[error("{0} needs at least one name")]
     NoNamesGiven(ResourceType),
     NotAnInteger(#[from] std::num::ParseIntError),
@@ -111,16 +155,33 @@ pub enum Error {
     NotFound(ResourceType),
     #[error("This action requires you to be logged in")]
     NotLoggedIn,
+    #[error("Request body is too large")]
+    PayloadTooLarge,
+    #[error("Post is locked")]
+    PostLocked,
     Request(#[from] reqwest::Error),
     #[error("Someone else modified this in the meantime. Please try again.")]
-    ResourceModified,
+    ResourceModified {
+        client_version: DateTime,
+        current_version: DateTime,
+    },
+    #[error("Cannot favorite your own post")]
+    SelfFavorite,
     #[error("Cannot merge {0} with itself")]
     SelfMerge(ResourceType),
     StdIo(#[from] std::io::Error),
     SwfDecoding(#[from] swf::error::Error),
+    #[error("Tag name '{0}' is already taken")]
+    TagNameTaken(SmallString),
+    #[error("Cannot get more than {1} {0}s at once")]
+    TooManyResources(ResourceType, i64),
+    #[error("Email verification token is invalid")]
+    UnauthorizedEmailVerification,
     #[error("Password reset token is invalid")]
     UnauthorizedPasswordReset,
     Utf8Conversion(#[from] std::str::Utf8Error),
+    #[error("Request has {} invalid field(s)", .0.len())]
+    ValidationError(Vec<FieldError>),
     VideoDecoding(#[from] video_rs::Error),
     Warp(#[from] warp::Error),
 }
@@ -140,8 +201,10 @@ impl Error {
             Self::BadHash(_) => StatusCode::BAD_REQUEST,
             Self::BadIncomingHeader(_) => StatusCode::BAD_REQUEST,
             Self::BadResponseHeader(_) => StatusCode::BAD_REQUEST,
+            Self::ContentTokenExpired => StatusCode::GONE,
             Self::ContentTypeMismatch(..) => StatusCode::BAD_REQUEST,
             Self::CyclicDependency(_) => StatusCode::BAD_REQUEST,
+            Self::DatabaseUnavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
             Self::DeleteDefault(_) => StatusCode::BAD_REQUEST,
             Self::EmptySwf => StatusCode::BAD_REQUEST,
             Self::EmptyVideo => StatusCode::BAD_REQUEST,
@@ -155,15 +218,20 @@ impl Error {
             Self::FailedEmailTransport(_) => StatusCode::INTERNAL_SERVER_ERROR,
             Self::FailedQuery(err) => query_error_status_code(err),
             Self::FromStr(_) => StatusCode::BAD_REQUEST,
+            Self::ImplicationCycle(_) => StatusCode::BAD_REQUEST,
+            Self::IncorrectPassword => StatusCode::FORBIDDEN,
             Self::InsufficientPrivileges => StatusCode::FORBIDDEN,
             Self::InvalidEmailAddress(_) => StatusCode::BAD_REQUEST,
             Self::InvalidEmail(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::InvalidFileSize(_) => StatusCode::BAD_REQUEST,
             Self::InvalidHeader(_) => StatusCode::INTERNAL_SERVER_ERROR,
             Self::InvalidMetadataType => StatusCode::BAD_REQUEST,
+            Self::InvalidPoolOrder => StatusCode::BAD_REQUEST,
             Self::InvalidSort => StatusCode::BAD_REQUEST,
             Self::InvalidTime(_) => StatusCode::BAD_REQUEST,
             Self::InvalidUserRank => StatusCode::BAD_REQUEST,
             Self::Image(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::JobAlreadyRunning => StatusCode::CONFLICT,
             Self::JsonSerialization(err) => match err.classify() {
                 Category::Io | Category::Eof => StatusCode::INTERNAL_SERVER_ERROR,
                 Category::Syntax | Category::Data => StatusCode::BAD_REQUEST,
@@ -172,19 +240,26 @@ impl Error {
             Self::MissingContentType => StatusCode::BAD_REQUEST,
             Self::MissingFormData => StatusCode::BAD_REQUEST,
             Self::MissingMetadata => StatusCode::BAD_REQUEST,
-            Self::MissingSmtpInfo => StatusCode::INTERNAL_SERVER_ERROR,
-            Self::NoEmail => StatusCode::BAD_REQUEST,
+            Self::MovedPermanently(_) => StatusCode::MOVED_PERMANENTLY,
+            Self::NoExif => StatusCode::NOT_FOUND,
             Self::NoNamesGiven(_) => StatusCode::BAD_REQUEST,
             Self::NotAnInteger(_) => StatusCode::BAD_REQUEST,
             Self::NotFound(_) => StatusCode::NOT_FOUND,
-            Self::NotLoggedIn => StatusCode::FORBIDDEN,
+            Self::NotLoggedIn => StatusCode::UNAUTHORIZED,
+            Self::PayloadTooLarge => StatusCode::PAYLOAD_TOO_LARGE,
+            Self::PostLocked => StatusCode::FORBIDDEN,
             Self::Request(_) => StatusCode::BAD_REQUEST,
-            Self::ResourceModified => StatusCode::CONFLICT,
+            Self::ResourceModified { .. } => StatusCode::CONFLICT,
+            Self::SelfFavorite => StatusCode::FORBIDDEN,
             Self::SelfMerge(_) => StatusCode::BAD_REQUEST,
             Self::StdIo(_) => StatusCode::INTERNAL_SERVER_ERROR,
             Self::SwfDecoding(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            Self::TagNameTaken(_) => StatusCode::CONFLICT,
+            Self::TooManyResources(..) => StatusCode::BAD_REQUEST,
+            Self::UnauthorizedEmailVerification => StatusCode::UNAUTHORIZED,
             Self::UnauthorizedPasswordReset => StatusCode::UNAUTHORIZED,
             Self::Utf8Conversion(_) => StatusCode::BAD_REQUEST,
+            Self::ValidationError(_) => StatusCode::BAD_REQUEST,
             Self::VideoDecoding(_) => StatusCode::BAD_REQUEST,
             Self::Warp(_) => StatusCode::BAD_REQUEST,
         }
@@ -196,8 +271,10 @@ impl Error {
             Self::BadHash(_) => "Bad Hash",
             Self::BadIncomingHeader(_) => "Bad Incomding Header",
             Self::BadResponseHeader(_) => "Bad Response Header",
+            Self::ContentTokenExpired => "Content Token Expired",
             Self::ContentTypeMismatch(..) => "Content Type Mismatch",
             Self::CyclicDependency(_) => "Cyclic Dependency",
+            Self::DatabaseUnavailable(_) => "Database Unavailable",
             Self::DeleteDefault(_) => "Delete Default",
             Self::EmptySwf => "Empty SWF",
             Self::EmptyVideo => "Empty Video",
@@ -207,43 +284,78 @@ impl Error {
             Self::FailedEmailTransport(_) => "Failed Email Transport",
             Self::FailedQuery(_) => "Failed Query",
             Self::FromStr(_) => "FromStr Error",
+            Self::ImplicationCycle(_) => "Implication Cycle",
+            Self::IncorrectPassword => "Incorrect Password",
             Self::InsufficientPrivileges => "Insufficient Privileges",
             Self::InvalidEmailAddress(_) => "Invalid Email Address",
             Self::InvalidEmail(_) => "Invalid Email",
+            Self::InvalidFileSize(_) => "Invalid File Size",
             Self::InvalidHeader(_) => "Invalid Header",
             Self::InvalidMetadataType => "Invalid Metadata Type",
+            Self::InvalidPoolOrder => "Invalid Pool Order",
             Self::InvalidSort => "Invalid Sort",
             Self::InvalidTime(_) => "Invalid Time",
             Self::InvalidUserRank => "Invalid User Rank",
             Self::Image(_) => "Image Error",
+            Self::JobAlreadyRunning => "Job Already Running",
             Self::JsonSerialization(_) => "JSON Serialization Error",
             Self::MissingContent(_) => "Missing Content",
             Self::MissingContentType => "Missing Content Type",
             Self::MissingFormData => "Missing Form Data",
             Self::MissingMetadata => "Missing Metadata",
-            Self::MissingSmtpInfo => "Missing SMTP Info",
-            Self::NoEmail => "No Email",
+            Self::MovedPermanently(_) => "Moved Permanently",
+            Self::NoExif => "No EXIF",
             Self::NoNamesGiven(_) => "No Names Given",
             Self::NotAnInteger(_) => "Parse Int Error",
             Self::NotFound(_) => "Resource Not Found",
             Self::NotLoggedIn => "Not Logged In",
+            Self::PayloadTooLarge => "Payload Too Large",
+            Self::PostLocked => "Post Locked",
             Self::Request(_) => "Request Error",
-            Self::ResourceModified => "Resource Modified",
+            Self::ResourceModified { .. } => "Resource Modified",
+            Self::SelfFavorite => "Self Favorite",
             Self::SelfMerge(_) => "Self Merge",
             Self::StdIo(_) => "IO Error",
             Self::SwfDecoding(_) => "SWF Decoding Error",
+            Self::TagNameTaken(_) => "Tag Name Taken",
+            Self::TooManyResources(..) => "Too Many Resources",
+            Self::UnauthorizedEmailVerification => "Unauthorized Email Verification",
             Self::UnauthorizedPasswordReset => "Unauthorized Password Reset",
             Self::Utf8Conversion(_) => "Utf8 Conversion Error",
+            Self::ValidationError(_) => "Validation Error",
             Self::VideoDecoding(_) => "Video Decoding Error",
             Self::Warp(_) => "Warp Error",
         }
     }
 
     fn response(&self) -> ErrorResponse {
+        let (client_version, current_version) = match self {
+            Self::ResourceModified {
+                client_version,
+                current_version,
+            } => (Some(*client_version), Some(*current_version)),
+            _ => (None, None),
+        };
+        let errors = match self {
+            Self::ValidationError(errors) => Some(errors.clone()),
+            _ => None,
+        };
         ErrorResponse {
             name: self.kind(),
             title: self.category(),
             description: self.to_string(),
+            client_version,
+            current_version,
+            errors,
+        }
+    }
+}
+
+impl From<DatabaseError> for Error {
+    fn from(value: DatabaseError) -> Self {
+        match value {
+            DatabaseError::Connection(err) => Self::FailedConnection(err),
+            DatabaseError::Query(err) => Self::FailedQuery(err),
         }
     }
 }
@@ -256,6 +368,22 @@ pub fn verify_privilege(client: Client, required_rank: UserRank) -> ApiResult<()
         .ok_or(Error::InsufficientPrivileges)
 }
 
+/// Checks if the `client` is at least `own_privilege` when acting on a resource owned by
+/// `owner_id` that belongs to them, or at least `any_privilege` otherwise.
+/// Resources without an owner never belong to the `client`.
+pub fn verify_self_or_any_privilege(
+    client: Client,
+    owner_id: Option<i64>,
+    own_privilege: UserRank,
+    any_privilege: UserRank,
+) -> ApiResult<()> {
+    let required_rank = match owner_id.is_some() && client.id == owner_id {
+        true => own_privilege,
+        false => any_privilege,
+    };
+    verify_privilege(client, required_rank)
+}
+
 /// Checks if `haystack` matches regex `regex_type`.
 /// Returns error if it does not match on the regex.
 pub fn verify_matches_regex(haystack: &str, regex_type: RegexType) -> ApiResult<()> {
@@ -274,18 +402,47 @@ pub fn verify_valid_email(email: Option<&str>) -> Result<(), lettre::address::Ad
     }
 }
 
+/// Runs every field check in `checks` instead of stopping at the first failure.
+/// Returns a [Error::ValidationError] listing each failed field if any check fails.
+pub fn verify_fields(checks: impl IntoIterator<Item = (&'static str, ApiResult<()>)>) -> ApiResult<()> {
+    let errors: Vec<_> = checks
+        .into_iter()
+        .filter_map(|(field, result)| result.err().map(|err| (field, err)))
+        .map(|(field, err)| FieldError {
+            field,
+            message: err.to_string(),
+        })
+        .collect();
+    match errors.is_empty() {
+        true => Ok(()),
+        false => Err(Error::ValidationError(errors)),
+    }
+}
+
 /// Returns all possible routes for the application.
 pub fn routes() -> impl Filter<Extract = impl warp::Reply, Error = Infallible> + Clone {
     let catch_all = warp::any().map(|| {
         eprintln!("No endpoint for request!");
-        warp::reply::with_status("Bad Request", StatusCode::BAD_REQUEST)
+        let response = ErrorResponse {
+            title: "Bad Request",
+            name: "NoEndpoint",
+            description: String::from("No endpoint for request"),
+            client_version: None,
+            current_version: None,
+            errors: None,
+        };
+        warp::reply::with_status(warp::reply::json(&response), StatusCode::BAD_REQUEST)
     });
     let log = warp::filters::log::custom(|info| {
         println!("{} {} [{}]", info.method(), info.path(), info.status());
     });
 
-    info::routes()
+    let routes = info::routes()
+        .or(admin::routes())
+        .or(health::routes())
         .or(comment::routes())
+        .or(note::routes())
+        .or(openapi::routes())
         .or(password_reset::routes())
         .or(pool_category::routes())
         .or(pool::routes())
@@ -295,14 +452,78 @@ pub fn routes() -> impl Filter<Extract = impl warp::Reply, Error = Infallible> +
         .or(upload::routes())
         .or(user_token::routes())
         .or(user::routes())
-        .or(catch_all)
+        .recover(recover_payload_too_large)
+        .or(catch_all);
+    warp::any()
+        .map(ActiveRequest::new)
+        .and(with_security_headers(routes))
+        .map(|_request: ActiveRequest, response: Response| response)
         .with(log)
 }
 
+static ACTIVE_REQUESTS: AtomicUsize = AtomicUsize::new(0);
+
+/// Returns the number of requests currently being handled.
+pub fn active_requests() -> usize {
+    ACTIVE_REQUESTS.load(Ordering::SeqCst)
+}
+
+/// Counts towards [active_requests] for as long as it is alive.
+struct ActiveRequest;
+
+impl ActiveRequest {
+    fn new() -> Self {
+        ACTIVE_REQUESTS.fetch_add(1, Ordering::SeqCst);
+        Self
+    }
+}
+
+impl Drop for ActiveRequest {
+    fn drop(&mut self) {
+        ACTIVE_REQUESTS.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Headers sent with every reply. Built once, as they only depend on the config.
+static SECURITY_HEADERS: LazyLock<HeaderMap> = LazyLock::new(|| {
+    // Validity of the policy is checked when the config is loaded
+    let content_security_policy = HeaderValue::from_str(&config::security().content_security_policy).unwrap();
+
+    let mut headers = HeaderMap::new();
+    headers.insert(CONTENT_SECURITY_POLICY, content_security_policy);
+    headers.insert(X_CONTENT_TYPE_OPTIONS, HeaderValue::from_static("nosniff"));
+    headers.insert(X_FRAME_OPTIONS, HeaderValue::from_static("DENY"));
+    headers.insert(REFERRER_POLICY, HeaderValue::from_static("no-referrer"));
+    let uses_https = config::get()
+        .domain
+        .as_deref()
+        .is_some_and(|domain| domain.starts_with("https://"));
+    if uses_https {
+        headers.insert(STRICT_TRANSPORT_SECURITY, HeaderValue::from_static("max-age=63072000"));
+    }
+    headers
+});
+
+/// Adds [SECURITY_HEADERS] to every reply of `filter`. Replies that already set one of
+/// these headers keep their own value, so routes can loosen the content security policy.
+fn with_security_headers<F, R>(filter: F) -> impl Filter<Extract = (Response,), Error = F::Error> + Clone
+where
+    F: Filter<Extract = (R,)> + Clone,
+    R: warp::Reply,
+{
+    filter.map(|reply: R| {
+        let mut response = reply.into_response();
+        for (name, value) in SECURITY_HEADERS.iter() {
+            response.headers_mut().entry(name).or_insert_with(|| value.clone());
+        }
+        response
+    })
+}
+
 type AuthResult = Result<Client, AuthenticationError>;
 
 /// Represents body of a request to apply/change a score.
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 #[serde(deny_unknown_fields)]
 struct RatingBody {
     score: Rating,
@@ -316,7 +537,7 @@ impl Deref for RatingBody {
 }
 
 /// Represents body of a request to delete a resource.
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 #[serde(deny_unknown_fields)]
 struct DeleteBody {
     version: DateTime,
@@ -330,7 +551,7 @@ impl Deref for DeleteBody {
 }
 
 /// Represents body of a request to merge two resources.
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 struct MergeBody<T> {
     remove: T,
@@ -340,10 +561,14 @@ struct MergeBody<T> {
 }
 
 /// Represents parameters of a request to retrieve one or more resources.
-#[derive(Deserialize)]
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 struct ResourceParams {
+    /// Search query, see the API documentation for syntax.
     query: Option<String>,
+    /// Comma-separated list of fields to include in the response.
     fields: Option<String>,
+    /// Updates the last login time of the client.
     #[serde(rename = "bump-login")]
     bump_login: Option<bool>,
 }
@@ -363,14 +588,37 @@ impl ResourceParams {
             _ => Ok(()),
         }
     }
+
+    /// Encodes the parameters back into a query string, which starts with `?` unless it is empty.
+    fn to_query_string(&self) -> String {
+        let mut serializer = url::form_urlencoded::Serializer::new(String::new());
+        if let Some(query) = self.query.as_deref() {
+            serializer.append_pair("query", query);
+        }
+        if let Some(fields) = self.fields.as_deref() {
+            serializer.append_pair("fields", fields);
+        }
+        if let Some(bump_login) = self.bump_login {
+            serializer.append_pair("bump-login", &bump_login.to_string());
+        }
+        let query_string = serializer.finish();
+        match query_string.is_empty() {
+            true => query_string,
+            false => format!("?{query_string}"),
+        }
+    }
 }
 
 /// Represents parameters of a request to retrieve multiple resources, paged.
-#[derive(Deserialize)]
+/// The flattened [ResourceParams] must be listed separately in OpenAPI path parameters.
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
 struct PageParams {
     offset: Option<i64>,
-    limit: NonZero<i64>,
+    #[param(value_type = Option<i64>, minimum = 1)]
+    limit: Option<NonZero<i64>>,
     #[serde(flatten)]
+    #[param(ignore)]
     params: ResourceParams,
 }
 
@@ -387,6 +635,11 @@ impl PageParams {
         self.params.bump_login(user)
     }
 
+    /// Returns the requested page size, or `default` if none was given, capped at `max`.
+    fn limit(&self, default: i64, max: i64) -> i64 {
+        std::cmp::min(self.limit.map(NonZero::get).unwrap_or(default), max)
+    }
+
     fn into_query(self) -> Option<String> {
         self.params.query
     }
@@ -394,28 +647,79 @@ impl PageParams {
 
 /// Represents a response to a request to retrieve multiple resources.
 /// Used for resources which are not paged.
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 struct UnpagedResponse<T> {
     results: Vec<T>,
 }
 
 /// Represents a response to a request to retrieve multiple resources.
 /// Used for resources which are paged.
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
 struct PagedResponse<T> {
     query: Option<String>,
     offset: i64,
     limit: i64,
+    /// Total number of results, or -1 if the server is configured to skip counting them.
     total: i64,
+    has_more: bool,
     results: Vec<T>,
 }
 
+/// Represents a page of resource ids loaded by [load_page].
+struct Page {
+    total: i64,
+    has_more: bool,
+    ids: Vec<i64>,
+}
+
+/// Loads the page of `query` results that starts at `offset` and has at most `limit` results.
+/// If `skip_total_count` is set in the pagination config, the results aren't counted and the total
+/// is -1. Whether there are more results is then determined by loading one extra result.
+fn load_page<Conn>(conn: &mut Conn, query: &mut impl Paginate<Conn>, offset: i64, limit: i64) -> ApiResult<Page> {
+    if config::pagination().skip_total_count {
+        let mut ids = query.page(conn, offset, limit.saturating_add(1))?;
+        let page_size = usize::try_from(limit).unwrap_or(usize::MAX);
+        let has_more = ids.len() > page_size;
+        ids.truncate(page_size);
+        return Ok(Page {
+            total: -1,
+            has_more,
+            ids,
+        });
+    }
+
+    let total = query.total(conn)?;
+    let ids = query.page(conn, offset, limit)?;
+    let page_end = offset.saturating_add(i64::try_from(ids.len()).unwrap_or(i64::MAX));
+    Ok(Page {
+        total,
+        has_more: page_end < total,
+        ids,
+    })
+}
+
 /// Represents a response if an error occured.
-#[derive(Serialize)]
+#[skip_serializing_none]
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
 struct ErrorResponse {
     title: &'static str,
     name: &'static str,
     description: String,
+    /// Version of the resource the client attempted to modify. Only present on `ResourceModified` errors.
+    client_version: Option<DateTime>,
+    /// Version of the resource currently stored on the server. Only present on `ResourceModified` errors.
+    current_version: Option<DateTime>,
+    /// Every invalid field of the request. Only present on `ValidationError` errors.
+    errors: Option<Vec<FieldError>>,
+}
+
+/// Describes why a single field of a request is invalid.
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct FieldError {
+    field: &'static str,
+    message: String,
 }
 
 /// Checks if `current_version` matches `client_version`.
@@ -426,7 +730,10 @@ fn verify_version(current_version: DateTime, client_version: DateTime) -> ApiRes
     } else {
         (current_version == client_version)
             .then_some(())
-            .ok_or(Error::ResourceModified)
+            .ok_or(Error::ResourceModified {
+                client_version,
+                current_version,
+            })
     }
 }
 
@@ -438,6 +745,45 @@ fn auth() -> impl Filter<Extract = (AuthResult,), Error = Rejection> + Clone {
     })
 }
 
+/// Deserializes a JSON request body that is at most as large as the configured limit.
+fn json_body<T: DeserializeOwned + Send>() -> impl Filter<Extract = (T,), Error = Rejection> + Copy {
+    warp::body::content_length_limit(config::body_limits().json).and(warp::body::json())
+}
+
+/// Extracts a raw request body that is at most `limit` bytes large. The body is streamed,
+/// so it is only received once the handler calls [`read_body`].
+fn raw_body(
+    limit: u64,
+) -> impl Filter<Extract = (impl Stream<Item = Result<impl Buf, warp::Error>> + Send + 'static,), Error = Rejection> + Copy
+{
+    warp::body::content_length_limit(limit).and(warp::body::stream())
+}
+
+/// Receives the entire contents of a body extracted by [`raw_body`].
+async fn read_body(body: impl Stream<Item = Result<impl Buf, warp::Error>>) -> ApiResult<Vec<u8>> {
+    let mut body = std::pin::pin!(body);
+    let mut contents = Vec::new();
+    while let Some(mut chunk) = body.try_next().await? {
+        contents.extend_from_slice(&chunk.copy_to_bytes(chunk.remaining()));
+    }
+    Ok(contents)
+}
+
+/// Extracts a multipart/form-data request body that is at most as large as the configured upload limit.
+fn multipart_body() -> impl Filter<Extract = (FormData,), Error = Rejection> + Clone {
+    let limit = config::body_limits().upload;
+    warp::body::content_length_limit(limit).and(warp::multipart::form().max_length(limit))
+}
+
+/// Turns rejections caused by an oversized body into an error reply.
+/// All other rejections are passed on to the next filter.
+async fn recover_payload_too_large(rejection: Rejection) -> Result<Reply, Rejection> {
+    match rejection.find::<PayloadTooLarge>() {
+        Some(_) => Ok(Reply::from(ApiResult::<()>::Err(Error::PayloadTooLarge))),
+        None => Err(rejection),
+    }
+}
+
 /// Optionally serializes a resource query.
 fn resource_query() -> impl Filter<Extract = (ResourceParams,), Error = Infallible> + Clone {
     warp::query::<ResourceParams>().or_else(async |_| {
@@ -457,3 +803,200 @@ where
 {
     Deserialize::deserialize(deserializer).map(Some)
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::Value;
+    use time::Month;
+
+    async fn reply_of(err: Error) -> (StatusCode, Value) {
+        let response = warp::Reply::into_response(Reply::from(ApiResult::<()>::Err(err)));
+        let status = response.status();
+        let body = warp::hyper::body::to_bytes(response.into_body()).await.unwrap();
+        (status, serde_json::from_slice(&body).unwrap())
+    }
+
+    async fn verify_error(err: Error, expected_status: StatusCode, expected_name: &str) {
+        let description = err.to_string();
+        let (status, body) = reply_of(err).await;
+        assert_eq!(status, expected_status);
+        assert_eq!(body["name"], expected_name);
+        assert_eq!(body["description"], description.as_str());
+    }
+
+    /// Query over the ids in a vector, for testing pagination without a database connection.
+    struct IdList(Vec<i64>);
+
+    impl Paginate<()> for IdList {
+        fn total(&mut self, _: &mut ()) -> ApiResult<i64> {
+            Ok(self.0.len() as i64)
+        }
+
+        fn page(&mut self, _: &mut (), offset: i64, limit: i64) -> ApiResult<Vec<i64>> {
+            Ok(self
+                .0
+                .iter()
+                .copied()
+                .skip(offset as usize)
+                .take(limit as usize)
+                .collect())
+        }
+    }
+
+    #[test]
+    fn paging() -> ApiResult<()> {
+        let mut query = IdList((1..=5).collect());
+        let expected_total = match config::pagination().skip_total_count {
+            true => -1,
+            false => 5,
+        };
+
+        let first_page = load_page(&mut (), &mut query, 0, 2)?;
+        assert_eq!(first_page.ids, [1, 2]);
+        assert_eq!(first_page.total, expected_total);
+        assert!(first_page.has_more);
+
+        let last_page = load_page(&mut (), &mut query, 3, 2)?;
+        assert_eq!(last_page.ids, [4, 5]);
+        assert!(!last_page.has_more);
+
+        let full_page = load_page(&mut (), &mut query, 0, 10)?;
+        assert_eq!(full_page.ids.len(), 5);
+        assert!(!full_page.has_more);
+        Ok(())
+    }
+
+    #[test]
+    fn self_or_any_privilege() {
+        let owner = Client::new(Some(1), UserRank::Regular);
+        let other = Client::new(Some(2), UserRank::Regular);
+        let anonymous = Client::new(None, UserRank::Anonymous);
+        let (regular, moderator) = (UserRank::Regular, UserRank::Moderator);
+
+        // Owners only need the lower privilege
+        assert!(verify_self_or_any_privilege(owner, Some(1), regular, moderator).is_ok());
+        assert!(verify_self_or_any_privilege(owner, Some(1), moderator, regular).is_err());
+
+        // Everyone else needs the higher privilege
+        assert!(verify_self_or_any_privilege(other, Some(1), regular, moderator).is_err());
+        assert!(verify_self_or_any_privilege(other, Some(1), moderator, regular).is_ok());
+
+        // Resources without an owner don't belong to anonymous users
+        assert!(verify_self_or_any_privilege(anonymous, None, UserRank::Anonymous, regular).is_err());
+    }
+
+    #[tokio::test]
+    async fn status_codes() {
+        verify_error(Error::NotFound(ResourceType::Post), StatusCode::NOT_FOUND, "NotFound").await;
+        verify_error(Error::InsufficientPrivileges, StatusCode::FORBIDDEN, "InsufficientPrivileges").await;
+        verify_error(Error::NotLoggedIn, StatusCode::UNAUTHORIZED, "NotLoggedIn").await;
+        verify_error(Error::ContentTokenExpired, StatusCode::GONE, "ContentTokenExpired").await;
+
+        let query_error = diesel::result::Error::RollbackTransaction;
+        let query_error_name = query_error.kind();
+        verify_error(Error::from(query_error), StatusCode::INTERNAL_SERVER_ERROR, query_error_name).await;
+
+        let parse_error = "abc".parse::<i64>().unwrap_err();
+        let parse_error_name = parse_error.kind().kind();
+        verify_error(Error::from(parse_error), StatusCode::BAD_REQUEST, parse_error_name).await;
+    }
+
+    #[tokio::test]
+    async fn moved_permanently() {
+        let response = warp::Reply::into_response(Reply::from(ApiResult::<()>::Err(Error::MovedPermanently(
+            String::from("/post/3"),
+        ))));
+        assert_eq!(response.status(), StatusCode::MOVED_PERMANENTLY);
+        let location = response.headers()[LOCATION].to_str().unwrap();
+        assert!(location.ends_with("/api/post/3"));
+    }
+
+    #[tokio::test]
+    async fn payload_too_large() {
+        let body = vec![b' '; config::body_limits().json as usize + 1];
+        let reply = warp::test::request()
+            .method("POST")
+            .path("/comments")
+            .body(body)
+            .reply(&routes())
+            .await;
+        assert_eq!(reply.status(), StatusCode::PAYLOAD_TOO_LARGE);
+
+        let body: Value = serde_json::from_slice(reply.body()).unwrap();
+        assert_eq!(body["name"], "PayloadTooLarge");
+    }
+
+    #[tokio::test]
+    async fn validation_errors() {
+        assert!(verify_fields([("name", Ok(())), ("email", Ok(()))]).is_ok());
+
+        let err = verify_fields([
+            ("name", Ok(())),
+            ("password", Err(Error::InvalidSort)),
+            ("email", Err(Error::NoExif)),
+        ])
+        .unwrap_err();
+        let (status, body) = reply_of(err).await;
+        assert_eq!(status, StatusCode::BAD_REQUEST);
+        assert_eq!(body["name"], "ValidationError");
+        assert_eq!(
+            body["errors"],
+            serde_json::json!([
+                { "field": "password", "message": "Invalid sort token" },
+                { "field": "email", "message": "Post has no EXIF data" },
+            ])
+        );
+    }
+
+    #[tokio::test]
+    async fn resource_modified() {
+        let client_version = DateTime::from_date(2020, Month::January, 1).unwrap();
+        let current_version = DateTime::from_date(2021, Month::February, 2).unwrap();
+        let resource_modified = || Error::ResourceModified {
+            client_version,
+            current_version,
+        };
+        verify_error(resource_modified(), StatusCode::CONFLICT, "ResourceModified").await;
+
+        let (_, body) = reply_of(resource_modified()).await;
+        assert_eq!(body["clientVersion"], serde_json::to_value(client_version).unwrap());
+        assert_eq!(body["currentVersion"], serde_json::to_value(current_version).unwrap());
+
+        let (_, body) = reply_of(Error::NotLoggedIn).await;
+        assert!(body.get("clientVersion").is_none());
+        assert!(body.get("currentVersion").is_none());
+    }
+
+    #[tokio::test]
+    async fn no_endpoint() {
+        let reply = warp::test::request()
+            .path("/this/does/not/exist")
+            .reply(&routes())
+            .await;
+        let body: Value = serde_json::from_slice(reply.body()).unwrap();
+        assert_eq!(reply.status(), StatusCode::BAD_REQUEST);
+        assert_eq!(body["name"], "NoEndpoint");
+    }
+
+    #[tokio::test]
+    async fn security_headers() {
+        let reply = warp::test::request()
+            .path("/this/does/not/exist")
+            .reply(&routes())
+            .await;
+        let headers = reply.headers();
+        let content_security_policy: &str = &config::security().content_security_policy;
+        assert_eq!(headers[CONTENT_SECURITY_POLICY], content_security_policy);
+        assert_eq!(headers[X_CONTENT_TYPE_OPTIONS], "nosniff");
+        assert_eq!(headers[X_FRAME_OPTIONS], "DENY");
+        assert_eq!(headers[REFERRER_POLICY], "no-referrer");
+        assert!(!headers.contains_key(STRICT_TRANSPORT_SECURITY));
+
+        // Routes which set their own policy keep it
+        let reply = warp::test::request().path("/api-docs/").reply(&routes()).await;
+        assert_eq!(reply.status(), StatusCode::OK);
+        assert_ne!(reply.headers()[CONTENT_SECURITY_POLICY], content_security_policy);
+        assert_eq!(reply.headers()[X_FRAME_OPTIONS], "DENY");
+    }
+}
@@ -0,0 +1,304 @@
+use crate::api::{ApiResult, AuthResult, DeleteBody, ResourceParams, UnpagedResponse};
+use crate::auth::header::Client;
+use crate::model::enums::{PostFlags, PostSafety, ResourceType};
+use crate::model::post::PostNote;
+use crate::resource::post::Note;
+use crate::schema::{post, post_note};
+use crate::search::post::is_visible;
+use crate::time::DateTime;
+use crate::{api, config, db, update};
+use diesel::prelude::*;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+use warp::{Filter, Rejection, Reply};
+
+pub fn routes() -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    let list = warp::get()
+        .and(api::auth())
+        .and(warp::path!("post" / i64 / "notes"))
+        .and(api::resource_query())
+        .map(list)
+        .map(api::Reply::from);
+    let create = warp::post()
+        .and(api::auth())
+        .and(warp::path!("post" / i64 / "notes"))
+        .and(api::resource_query())
+        .and(api::json_body())
+        .map(create)
+        .map(api::Reply::from);
+    let update = warp::put()
+        .and(api::auth())
+        .and(warp::path!("post" / i64 / "notes" / i64))
+        .and(api::resource_query())
+        .and(api::json_body())
+        .map(update)
+        .map(api::Reply::from);
+    let delete = warp::delete()
+        .and(api::auth())
+        .and(warp::path!("post" / i64 / "notes" / i64))
+        .and(api::json_body())
+        .map(delete)
+        .map(api::Reply::from);
+
+    list.or(create).or(update).or(delete)
+}
+
+#[derive(Serialize, ToSchema)]
+struct NoteInfo {
+    id: i64,
+    #[serde(flatten)]
+    note: Note,
+}
+
+impl NoteInfo {
+    fn new(note: PostNote) -> Self {
+        Self {
+            id: note.id,
+            note: Note::new(note),
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    path = "/post/{id}/notes",
+    summary = "Lists the notes of a post",
+    params(("id" = i64, Path)),
+    responses((status = 200, body = UnpagedResponse<NoteInfo>))
+)]
+fn list(auth: AuthResult, post_id: i64, params: ResourceParams) -> ApiResult<UnpagedResponse<NoteInfo>> {
+    let client = auth?;
+    params.bump_login(client)?;
+    api::verify_privilege(client, config::privileges().post_view)?;
+
+    db::get_connection()?.transaction(|conn| {
+        get_post(conn, client, post_id)?;
+        let notes: Vec<PostNote> = post_note::table
+            .select(PostNote::as_select())
+            .filter(post_note::post_id.eq(post_id))
+            .order_by(post_note::id)
+            .load(conn)?;
+        Ok(UnpagedResponse {
+            results: notes.into_iter().map(NoteInfo::new).collect(),
+        })
+    })
+}
+
+#[utoipa::path(
+    post,
+    path = "/post/{id}/notes",
+    summary = "Creates a post note",
+    params(("id" = i64, Path)),
+    request_body = Note,
+    responses((status = 200, body = NoteInfo))
+)]
+fn create(auth: AuthResult, post_id: i64, params: ResourceParams, body: Note) -> ApiResult<NoteInfo> {
+    let client = auth?;
+    params.bump_login(client)?;
+    api::verify_privilege(client, config::privileges().post_edit_note)?;
+
+    db::get_connection()?.transaction(|conn| {
+        get_editable_post_version(conn, client, post_id)?;
+        let note = diesel::insert_into(post_note::table)
+            .values(body.to_new_post_note(post_id))
+            .returning(PostNote::as_returning())
+            .get_result(conn)?;
+        update::post::last_edit_time(conn, post_id)?;
+        Ok(NoteInfo::new(note))
+    })
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+#[schema(as = NoteUpdateBody)]
+struct UpdateBody {
+    version: DateTime,
+    polygon: Option<Vec<[f32; 2]>>,
+    text: Option<String>,
+}
+
+#[utoipa::path(
+    put,
+    path = "/post/{id}/notes/{note_id}",
+    summary = "Updates a post note",
+    params(("id" = i64, Path), ("note_id" = i64, Path)),
+    request_body = UpdateBody,
+    responses((status = 200, body = NoteInfo))
+)]
+fn update(
+    auth: AuthResult,
+    post_id: i64,
+    note_id: i64,
+    params: ResourceParams,
+    body: UpdateBody,
+) -> ApiResult<NoteInfo> {
+    let client = auth?;
+    params.bump_login(client)?;
+    api::verify_privilege(client, config::privileges().post_edit_note)?;
+
+    db::get_connection()?.transaction(|conn| {
+        let post_version = get_editable_post_version(conn, client, post_id)?;
+        api::verify_version(post_version, body.version)?;
+        verify_note_exists(conn, post_id, note_id)?;
+
+        if let Some(polygon) = body.polygon {
+            diesel::update(post_note::table.find(note_id))
+                .set(post_note::polygon.eq(polygon.as_flattened()))
+                .execute(conn)?;
+        }
+        if let Some(text) = body.text {
+            diesel::update(post_note::table.find(note_id))
+                .set(post_note::text.eq(text))
+                .execute(conn)?;
+        }
+        update::post::last_edit_time(conn, post_id)?;
+
+        let note = post_note::table
+            .find(note_id)
+            .select(PostNote::as_select())
+            .first(conn)?;
+        Ok(NoteInfo::new(note))
+    })
+}
+
+#[utoipa::path(
+    delete,
+    path = "/post/{id}/notes/{note_id}",
+    summary = "Deletes a post note",
+    params(("id" = i64, Path), ("note_id" = i64, Path)),
+    request_body = DeleteBody,
+    responses((status = 200, description = "Note was deleted"))
+)]
+fn delete(auth: AuthResult, post_id: i64, note_id: i64, client_version: DeleteBody) -> ApiResult<()> {
+    let client = auth?;
+    api::verify_privilege(client, config::privileges().post_edit_note)?;
+
+    db::get_connection()?.transaction(|conn| {
+        let post_version = get_editable_post_version(conn, client, post_id)?;
+        api::verify_version(post_version, *client_version)?;
+        verify_note_exists(conn, post_id, note_id)?;
+
+        diesel::delete(post_note::table.find(note_id)).execute(conn)?;
+        update::post::last_edit_time(conn, post_id)
+    })
+}
+
+/// Returns the version and flags of the post with id `post_id`. The version of a post is also
+/// used as the version of its notes. Posts that `client` can't see are treated as if they don't exist.
+fn get_post(conn: &mut PgConnection, client: Client, post_id: i64) -> ApiResult<(DateTime, PostFlags)> {
+    post::table
+        .find(post_id)
+        .select((post::last_edit_time, post::flags, post::safety))
+        .first::<(DateTime, PostFlags, PostSafety)>(conn)
+        .optional()?
+        .filter(|&(_, flags, safety)| is_visible(client, flags, safety))
+        .map(|(last_edit_time, flags, _)| (last_edit_time, flags))
+        .ok_or(api::Error::NotFound(ResourceType::Post))
+}
+
+/// Returns the version of the post with id `post_id` after checking that `client` may edit its notes.
+fn get_editable_post_version(conn: &mut PgConnection, client: Client, post_id: i64) -> ApiResult<DateTime> {
+    let (post_version, flags) = get_post(conn, client, post_id)?;
+    api::post::verify_unlocked(client, flags)?;
+    Ok(post_version)
+}
+
+/// Checks that the note with id `note_id` belongs to the post with id `post_id`.
+fn verify_note_exists(conn: &mut PgConnection, post_id: i64, note_id: i64) -> ApiResult<()> {
+    let note_exists: bool =
+        diesel::select(diesel::dsl::exists(post_note::table.find(note_id).filter(post_note::post_id.eq(post_id))))
+            .get_result(conn)?;
+    note_exists
+        .then_some(())
+        .ok_or(api::Error::NotFound(ResourceType::PostNote))
+}
+
+#[cfg(test)]
+mod test {
+    use crate::api::{self, ApiResult, ResourceParams};
+    use crate::auth::header::Client;
+    use crate::model::enums::{PostFlag, PostFlags, UserRank};
+    use crate::resource::post::Note;
+    use crate::schema::{post, post_note, post_statistics};
+    use crate::test::*;
+    use crate::time::DateTime;
+    use diesel::prelude::*;
+    use serial_test::{parallel, serial};
+
+    #[tokio::test]
+    #[parallel]
+    async fn list() -> ApiResult<()> {
+        verify_query("GET /post/3/notes", "note/list.json").await?;
+        verify_query("GET /post/4/notes", "note/list_none.json").await
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn create_update_delete() -> ApiResult<()> {
+        const POST_ID: i64 = 4;
+        let get_post_info = |conn: &mut PgConnection| -> QueryResult<(i64, DateTime)> {
+            post::table
+                .inner_join(post_statistics::table)
+                .select((post_statistics::note_count, post::last_edit_time))
+                .filter(post::id.eq(POST_ID))
+                .first(conn)
+        };
+
+        let mut conn = get_connection()?;
+        let (note_count, last_edit_time) = get_post_info(&mut conn)?;
+
+        verify_query(&format!("POST /post/{POST_ID}/notes"), "note/create.json").await?;
+
+        let note_id: i64 = post_note::table
+            .select(post_note::id)
+            .filter(post_note::post_id.eq(POST_ID))
+            .first(&mut conn)?;
+        let (new_note_count, new_last_edit_time) = get_post_info(&mut conn)?;
+        assert_eq!(new_note_count, note_count + 1);
+        assert!(new_last_edit_time > last_edit_time);
+
+        verify_query(&format!("PUT /post/{POST_ID}/notes/{note_id}"), "note/update.json").await?;
+
+        let note_text: String = post_note::table
+            .select(post_note::text)
+            .filter(post_note::id.eq(note_id))
+            .first(&mut conn)?;
+        let (_, last_edit_time) = get_post_info(&mut conn)?;
+        assert_eq!(note_text, "Updated note");
+        assert!(last_edit_time > new_last_edit_time);
+
+        verify_query(&format!("DELETE /post/{POST_ID}/notes/{note_id}"), "delete.json").await?;
+
+        let (new_note_count, _) = get_post_info(&mut conn)?;
+        assert_eq!(new_note_count, note_count);
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn locked() -> ApiResult<()> {
+        const POST_ID: i64 = 4;
+        let mut conn = get_connection()?;
+        let flags: PostFlags = post::table.find(POST_ID).select(post::flags).first(&mut conn)?;
+        diesel::update(post::table.find(POST_ID))
+            .set(post::flags.eq(flags | PostFlag::Locked))
+            .execute(&mut conn)?;
+
+        // Notes of locked posts can only be edited by users with the lock privilege
+        let note = || -> ApiResult<Note> {
+            Ok(serde_json::from_str(&std::fs::read_to_string(body_path("note/create.json"))?)?)
+        };
+        let params = || ResourceParams {
+            query: None,
+            fields: None,
+            bump_login: None,
+        };
+        let regular_user = Client::new(Some(2), UserRank::Regular);
+        let result = super::create(Ok(regular_user), POST_ID, params(), note()?);
+        assert!(matches!(result, Err(api::Error::PostLocked)));
+
+        let moderator = Client::new(Some(4), UserRank::Moderator);
+        super::create(Ok(moderator), POST_ID, params(), note()?)?;
+        Ok(reset_database())
+    }
+}
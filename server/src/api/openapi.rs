@@ -0,0 +1,234 @@
+use crate::api::{
+    ErrorResponse, admin, comment, health, info, note, password_reset, pool, pool_category, post, tag, tag_category,
+    upload, user, user_token,
+};
+use std::sync::{Arc, LazyLock};
+use utoipa::openapi::{ContentBuilder, OpenApi as Document, Ref, RefOr, ResponseBuilder};
+use utoipa::{Modify, OpenApi, ToSchema};
+use utoipa_swagger_ui::Config;
+use warp::http::StatusCode;
+use warp::http::header::{CONTENT_SECURITY_POLICY, CONTENT_TYPE};
+use warp::path::{FullPath, Tail};
+use warp::{Filter, Rejection, Reply};
+
+pub fn routes() -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
+    let config = Arc::new(Config::from("openapi.json"));
+    let document = warp::get()
+        .and(warp::path!("api-docs" / "openapi.json"))
+        .map(|| warp::reply::json(&*DOCUMENT));
+    let swagger_ui = warp::get()
+        .and(warp::path("api-docs"))
+        .and(warp::path::full())
+        .and(warp::path::tail())
+        .map(move |full_path, tail| serve_swagger_ui(full_path, tail, config.clone()));
+
+    document.or(swagger_ui)
+}
+
+/// Description of every route served by the API, generated from the `utoipa::path` annotations on the handlers.
+/// New handlers must be listed in `paths` to show up in the document.
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "oxibooru"),
+    servers((url = "/api")),
+    paths(
+        admin::refresh_statistics,
+        admin::recompute_signatures,
+        admin::recompute_signatures_progress,
+        comment::list,
+        comment::get,
+        comment::create,
+        comment::update,
+        comment::rate,
+        comment::pin,
+        comment::unpin,
+        comment::delete,
+        health::liveness,
+        health::readiness,
+        info::get,
+        note::list,
+        note::create,
+        note::update,
+        note::delete,
+        password_reset::request_token,
+        password_reset::confirm_token,
+        pool_category::list,
+        pool_category::get,
+        pool_category::create,
+        pool_category::update,
+        pool_category::set_default,
+        pool_category::delete,
+        pool::list,
+        pool::get,
+        pool::create,
+        pool::merge,
+        pool::update,
+        pool::reorder,
+        pool::delete,
+        post::list,
+        post::get,
+        post::get_batch,
+        post::get_by_checksum,
+        post::get_safety_breakdown,
+        post::get_neighbors,
+        post::get_pool_neighbors,
+        post::get_context,
+        post::get_exif,
+        post::list_pools,
+        post::get_featured,
+        post::feature,
+        post::reverse_search,
+        post::reverse_search_direct,
+        post::create,
+        post::merge,
+        post::favorite,
+        post::batch_favorite,
+        post::view,
+        post::rate,
+        post::lock,
+        post::unlock,
+        post::update,
+        post::delete,
+        post::unfavorite,
+        post::batch_unfavorite,
+        tag_category::list,
+        tag_category::get,
+        tag_category::create,
+        tag_category::update,
+        tag_category::set_default,
+        tag_category::delete,
+        tag::list,
+        tag::suggest,
+        tag::get,
+        tag::get_siblings,
+        tag::create,
+        tag::merge,
+        tag::update,
+        tag::add_implication,
+        tag::remove_implication,
+        tag::add_suggestion,
+        tag::remove_suggestion,
+        tag::delete,
+        upload::upload_url,
+        user_token::list,
+        user_token::get,
+        user_token::create,
+        user_token::update,
+        user_token::delete,
+        user::list,
+        user::get,
+        user::list_favorites,
+        user::list_uploads,
+        user::list_liked,
+        user::list_disliked,
+        user::create,
+        user::update,
+        user::change_password,
+        user::update_rank,
+        user::verify_email,
+        user::delete,
+    ),
+    components(schemas(ErrorResponse)),
+    modifiers(&ErrorResponses)
+)]
+struct ApiDoc;
+
+static DOCUMENT: LazyLock<Document> = LazyLock::new(ApiDoc::openapi);
+
+/// Documents the error reply that every operation can return alongside its successful response.
+struct ErrorResponses;
+
+impl Modify for ErrorResponses {
+    fn modify(&self, openapi: &mut Document) {
+        let schema = RefOr::Ref(Ref::from_schema_name(ErrorResponse::name()));
+        let response = ResponseBuilder::new()
+            .description("Error")
+            .content("application/json", ContentBuilder::new().schema(Some(schema)).build())
+            .build();
+        let operations = openapi.paths.paths.values_mut().flat_map(|item| {
+            [&mut item.get, &mut item.put, &mut item.post, &mut item.delete]
+                .into_iter()
+                .flatten()
+        });
+        for operation in operations {
+            operation
+                .responses
+                .responses
+                .insert(String::from("default"), RefOr::T(response.clone()));
+        }
+    }
+}
+
+/// Swagger UI loads its own scripts and styles, which the default policy of the API forbids.
+const SWAGGER_UI_CONTENT_SECURITY_POLICY: &str =
+    "default-src 'self'; img-src 'self' data:; style-src 'self' 'unsafe-inline'";
+
+/// Serves the Swagger UI bundle, redirecting `/api-docs` to `/api-docs/` so that relative asset paths resolve.
+fn serve_swagger_ui(full_path: FullPath, tail: Tail, config: Arc<Config<'static>>) -> warp::reply::Response {
+    if !full_path.as_str().ends_with('/') && tail.as_str().is_empty() {
+        return warp::reply::with_header(StatusCode::FOUND, "location", "api-docs/").into_response();
+    }
+    match utoipa_swagger_ui::serve(tail.as_str(), config) {
+        Ok(Some(file)) => {
+            let reply = warp::reply::with_header(file.bytes.into_owned(), CONTENT_TYPE, file.content_type);
+            warp::reply::with_header(reply, CONTENT_SECURITY_POLICY, SWAGGER_UI_CONTENT_SECURITY_POLICY).into_response()
+        }
+        Ok(None) => StatusCode::NOT_FOUND.into_response(),
+        Err(err) => warp::reply::with_status(err.to_string(), StatusCode::INTERNAL_SERVER_ERROR).into_response(),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use serde_json::Value;
+
+    /// Collects the names of all schemas referenced within `value`.
+    fn collect_references<'a>(value: &'a Value, references: &mut Vec<&'a str>) {
+        match value {
+            Value::Object(map) => {
+                if let Some(Value::String(reference)) = map.get("$ref") {
+                    references.push(reference.strip_prefix("#/components/schemas/").unwrap());
+                }
+                map.values().for_each(|value| collect_references(value, references));
+            }
+            Value::Array(values) => values.iter().for_each(|value| collect_references(value, references)),
+            _ => (),
+        }
+    }
+
+    #[test]
+    fn references_resolve() {
+        let document = serde_json::to_value(&*DOCUMENT).unwrap();
+        let mut references = Vec::new();
+        collect_references(&document, &mut references);
+
+        let schemas = &document["components"]["schemas"];
+        for reference in references {
+            assert!(schemas.get(reference).is_some(), "Schema {reference} is not defined");
+        }
+    }
+
+    #[test]
+    fn operations_complete() {
+        let document = serde_json::to_value(&*DOCUMENT).unwrap();
+        for (path, item) in document["paths"].as_object().unwrap() {
+            let templated = path
+                .split('/')
+                .filter_map(|segment| segment.strip_prefix('{')?.strip_suffix('}'));
+            for (method, operation) in item.as_object().unwrap() {
+                let parameters = operation["parameters"]
+                    .as_array()
+                    .map(Vec::as_slice)
+                    .unwrap_or_default();
+                for name in templated.clone() {
+                    let documented = parameters
+                        .iter()
+                        .any(|parameter| parameter["in"] == "path" && parameter["name"] == name);
+                    assert!(documented, "Parameter {name} of {method} {path} is not documented");
+                }
+                assert!(operation["responses"].get("default").is_some(), "{method} {path} has no error response");
+            }
+        }
+    }
+}
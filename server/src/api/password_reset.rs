@@ -1,113 +1,211 @@
 use crate::api::ApiResult;
 use crate::auth::password;
+use crate::config::RegexType;
 use crate::content::hash;
-use crate::schema::user;
+use crate::model::user::NewPasswordResetToken;
+use crate::schema::{password_reset_token, user};
 use crate::string::SmallString;
-use crate::{api, config, db};
+use crate::time::DateTime;
+use crate::{api, config, db, mail};
 use argon2::password_hash::SaltString;
 use argon2::password_hash::rand_core::{OsRng, RngCore};
+use base64::engine::general_purpose::URL_SAFE_NO_PAD;
+use base64::prelude::*;
 use diesel::prelude::*;
-use lettre::message::Mailbox;
-use lettre::message::header::ContentType;
-use lettre::transport::smtp::authentication::Credentials;
-use lettre::{Message, SmtpTransport, Transport};
-use serde::{Deserialize, Serialize};
+use serde::Deserialize;
+use time::Duration;
+use utoipa::ToSchema;
 use warp::{Filter, Rejection, Reply};
 
 pub fn routes() -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
-    let request_reset = warp::get()
-        .and(warp::path!("password-reset" / String))
-        .map(request_reset)
+    let request_token = warp::post()
+        .and(warp::path!("password-reset"))
+        .and(api::json_body())
+        .map(request_token)
         .map(api::Reply::from);
-    let reset_password = warp::post()
-        .and(warp::path!("password-reset" / String))
-        .and(warp::body::json())
-        .map(reset_password)
+    let confirm_token = warp::post()
+        .and(warp::path!("password-reset" / "confirm"))
+        .and(api::json_body())
+        .map(confirm_token)
         .map(api::Reply::from);
 
-    request_reset.or(reset_password)
+    request_token.or(confirm_token)
 }
 
-fn get_user_info(
-    conn: &mut PgConnection,
-    identifier: &str,
-) -> ApiResult<(i64, SmallString, Option<SmallString>, String)> {
+/// How long a token sent by [request_token] can be used to reset a password.
+const RESET_TOKEN_LIFETIME: Duration = Duration::hours(1);
+
+fn get_user_info(conn: &mut PgConnection, identifier: &str) -> ApiResult<(i64, SmallString, Option<SmallString>)> {
     user::table
-        .select((user::id, user::name, user::email, user::password_salt))
+        .select((user::id, user::name, user::email))
         .filter(user::name.eq(identifier).or(user::email.eq(identifier)))
         .first(conn)
         .map_err(api::Error::from)
 }
 
-fn request_reset(identifier: String) -> ApiResult<()> {
-    let smtp_info = config::smtp().ok_or(api::Error::MissingSmtpInfo)?;
-    let identifier = percent_encoding::percent_decode_str(&identifier).decode_utf8()?;
+#[derive(Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+struct TokenRequest {
+    /// Name or email of the user
+    identifier: String,
+}
 
+#[utoipa::path(
+    post,
+    path = "/password-reset",
+    summary = "Requests a password reset token",
+    request_body = TokenRequest,
+    responses((status = 200, description = "Password reset email was sent, if the user exists and has an email"))
+)]
+fn request_token(request: TokenRequest) -> ApiResult<()> {
     let mut conn = db::get_connection()?;
-    let (_id, username, user_email, password_salt) = get_user_info(&mut conn, &identifier)?;
-    let user_email_address = user_email.ok_or(api::Error::NoEmail)?;
-    let user_mailbox: Mailbox = format!("User <{user_email_address}>").parse()?;
 
-    let reset_token = hash::compute_url_safe_hash(&password_salt);
+    // Reply the same way whether or not the user exists, so that users can't be enumerated
+    let (user_id, username, user_email) = match get_user_info(&mut conn, &request.identifier) {
+        Ok(user_info) => user_info,
+        Err(api::Error::FailedQuery(diesel::result::Error::NotFound)) => return Ok(()),
+        Err(err) => return Err(err),
+    };
+    let Some(user_email_address) = user_email else {
+        return Ok(());
+    };
+
+    let mut token_bytes = [0; 32];
+    OsRng.fill_bytes(&mut token_bytes);
+    let reset_token = URL_SAFE_NO_PAD.encode(token_bytes);
+    let token_hash = hash::compute_url_safe_hash(&reset_token);
+    let new_token = NewPasswordResetToken {
+        user_id,
+        token_hash: &token_hash,
+        expiration_time: (*DateTime::now() + RESET_TOKEN_LIFETIME).into(),
+    };
+    conn.transaction(|conn| {
+        // Requesting a new token invalidates the previous one
+        diesel::delete(password_reset_token::table.find(user_id)).execute(conn)?;
+        diesel::insert_into(password_reset_token::table)
+            .values(new_token)
+            .execute(conn)
+    })?;
+
     let domain = config::get().domain.as_deref().unwrap_or("");
-    let url = format!("{domain}/password-reset/{username}/?token={reset_token}");
-
-    let email = Message::builder()
-        .from(smtp_info.from.clone())
-        .to(user_mailbox)
-        .subject("Password Reset Request")
-        .header(ContentType::TEXT_PLAIN)
-        .body(format!(
-            "Hello,
-        
-             You (or someone else) requested to reset your password on {}.\n
-             If you wish to proceed, click this link: {url}\n
-             Otherwise, please ignore this email.",
-            config::get().public_info.name
-        ))?;
-    let credentials = Credentials::new(smtp_info.username.to_string(), smtp_info.password.to_string());
-
-    // Open a remote connection to gmail
-    let mailer = SmtpTransport::relay("smtp.gmail.com")
-        .unwrap()
-        .credentials(credentials)
-        .build();
-
-    // Send the email
-    mailer.send(&email).map(|_| ()).map_err(api::Error::from)
+    // Matches the client route that asks for the new password and sends it to confirm_token
+    let url = format!("{domain}/password-reset/confirm/token={reset_token}");
+    let body = format!(
+        "Hello,
+
+         You (or someone else) requested to reset your password on {}.\n
+         If you wish to proceed, click this link within the next hour: {url}\n
+         Otherwise, please ignore this email.",
+        config::get().public_info.name
+    );
+    if let Err(err) = mail::send_or_log(&user_email_address, "Password Reset Request", body) {
+        eprintln!("Failed to send password reset email to {username}: {err}");
+    }
+    Ok(())
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 #[serde(deny_unknown_fields)]
-struct ResetToken {
+struct TokenConfirmation {
     token: String,
+    password: SmallString,
 }
 
-#[derive(Serialize)]
-struct NewPassword {
-    password: String,
-}
-
-fn reset_password(identifier: String, confirmation: ResetToken) -> ApiResult<NewPassword> {
-    let identifier = percent_encoding::percent_decode_str(&identifier).decode_utf8()?;
-
+#[utoipa::path(
+    post,
+    path = "/password-reset/confirm",
+    summary = "Resets a password with a token",
+    request_body = TokenConfirmation,
+    responses((status = 200, description = "Password was changed"))
+)]
+fn confirm_token(confirmation: TokenConfirmation) -> ApiResult<()> {
+    api::verify_matches_regex(&confirmation.password, RegexType::Password)?;
+
+    let token_hash = hash::compute_url_safe_hash(&confirmation.token);
     db::get_connection()?.transaction(|conn| {
-        let (user_id, _name, _email, password_salt) = get_user_info(conn, &identifier)?;
-        if confirmation.token != hash::compute_url_safe_hash(&password_salt) {
-            return Err(api::Error::UnauthorizedPasswordReset);
-        }
-
-        // TODO: Create random alphanumeric password instead of numeric
-        let temporary_password = OsRng.next_u64().to_string();
+        // Tokens are deleted on use, so each one can only be used once
+        let reset_token: Option<(i64, DateTime)> =
+            diesel::delete(password_reset_token::table.filter(password_reset_token::token_hash.eq(token_hash)))
+                .returning((password_reset_token::user_id, password_reset_token::expiration_time))
+                .get_result(conn)
+                .optional()?;
+        let user_id = match reset_token {
+            Some((user_id, expiration_time)) if expiration_time > DateTime::now() => user_id,
+            _ => return Err(api::Error::UnauthorizedPasswordReset),
+        };
 
         let salt = SaltString::generate(&mut OsRng);
-        let hash = password::hash_password(&temporary_password, &salt)?;
+        let hash = password::hash_password(&confirmation.password, &salt)?;
         diesel::update(user::table.find(user_id))
             .set((user::password_salt.eq(salt.as_str()), user::password_hash.eq(hash)))
             .execute(conn)?;
-
-        Ok(NewPassword {
-            password: temporary_password,
-        })
+        Ok(())
     })
 }
+
+#[cfg(test)]
+mod test {
+    use crate::api::ApiResult;
+    use crate::content::hash;
+    use crate::model::user::NewPasswordResetToken;
+    use crate::schema::{password_reset_token, user};
+    use crate::test::*;
+    use crate::time::DateTime;
+    use diesel::dsl::exists;
+    use diesel::prelude::*;
+    use serial_test::serial;
+    use time::Duration;
+    use warp::http::StatusCode;
+
+    #[tokio::test]
+    #[serial]
+    async fn reset_with_token() -> ApiResult<()> {
+        const NAME: &str = "regular_user";
+        const CONFIRM: &str = "POST /password-reset/confirm";
+
+        let mut conn = get_connection()?;
+        let (user_id, password_hash, password_salt): (i64, String, String) = user::table
+            .select((user::id, user::password_hash, user::password_salt))
+            .filter(user::name.eq(NAME))
+            .first(&mut conn)?;
+        let has_token = |conn: &mut PgConnection| -> QueryResult<bool> {
+            diesel::select(exists(password_reset_token::table.find(user_id))).get_result(conn)
+        };
+        let insert_token = |conn: &mut PgConnection, expiration_time: DateTime| -> QueryResult<usize> {
+            let token_hash = hash::compute_url_safe_hash("test_reset_token");
+            diesel::delete(password_reset_token::table.find(user_id)).execute(conn)?;
+            diesel::insert_into(password_reset_token::table)
+                .values(NewPasswordResetToken {
+                    user_id,
+                    token_hash: &token_hash,
+                    expiration_time,
+                })
+                .execute(conn)
+        };
+
+        // Unknown users get the same reply as known ones
+        verify_query("POST /password-reset", "password_reset/request_unknown.json").await?;
+        verify_query("POST /password-reset", "password_reset/request.json").await?;
+        assert!(has_token(&mut conn)?);
+
+        insert_token(&mut conn, (*DateTime::now() + Duration::hours(1)).into())?;
+        verify_query(CONFIRM, "password_reset/confirm.json").await?;
+        assert!(!has_token(&mut conn)?);
+
+        let new_password_hash: String = user::table.select(user::password_hash).find(user_id).first(&mut conn)?;
+        assert_ne!(new_password_hash, password_hash);
+
+        // Tokens can only be used once
+        verify_status_with_body(CONFIRM, "password_reset/confirm.json", StatusCode::UNAUTHORIZED).await?;
+
+        // Expired tokens are rejected
+        insert_token(&mut conn, (*DateTime::now() - Duration::minutes(1)).into())?;
+        verify_status_with_body(CONFIRM, "password_reset/confirm.json", StatusCode::UNAUTHORIZED).await?;
+
+        diesel::delete(password_reset_token::table.find(user_id)).execute(&mut conn)?;
+        diesel::update(user::table.find(user_id))
+            .set((user::password_hash.eq(password_hash), user::password_salt.eq(password_salt)))
+            .execute(&mut conn)?;
+        Ok(())
+    }
+}
@@ -10,6 +10,7 @@ use crate::{api, config, db, resource, update};
 use diesel::dsl::{exists, max};
 use diesel::prelude::*;
 use serde::Deserialize;
+use utoipa::ToSchema;
 use warp::{Filter, Rejection, Reply};
 
 pub fn routes() -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
@@ -29,60 +30,79 @@ pub fn routes() -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone
         .and(api::auth())
         .and(warp::path!("pool"))
         .and(api::resource_query())
-        .and(warp::body::json())
+        .and(api::json_body())
         .map(create)
         .map(api::Reply::from);
     let merge = warp::post()
         .and(api::auth())
         .and(warp::path!("pool-merge"))
         .and(api::resource_query())
-        .and(warp::body::json())
+        .and(api::json_body())
         .map(merge)
         .map(api::Reply::from);
     let update = warp::put()
         .and(api::auth())
         .and(warp::path!("pool" / i64))
         .and(api::resource_query())
-        .and(warp::body::json())
+        .and(api::json_body())
         .map(update)
         .map(api::Reply::from);
+    let reorder = warp::post()
+        .and(api::auth())
+        .and(warp::path!("pool" / i64 / "reorder"))
+        .and(api::resource_query())
+        .and(api::json_body())
+        .map(reorder)
+        .map(api::Reply::from);
     let delete = warp::delete()
         .and(api::auth())
         .and(warp::path!("pool" / String))
-        .and(warp::body::json())
+        .and(api::json_body())
         .map(delete)
         .map(api::Reply::from);
 
-    list.or(get).or(create).or(merge).or(update).or(delete)
+    list.or(get).or(create).or(merge).or(update).or(reorder).or(delete)
 }
 
-const MAX_POOLS_PER_PAGE: i64 = 1000;
+pub(super) const MAX_POOLS_PER_PAGE: i64 = 1000;
 
+#[utoipa::path(
+    get,
+    path = "/pools",
+    summary = "Lists pools",
+    params(PageParams, ResourceParams),
+    responses((status = 200, body = PagedResponse<PoolInfo>))
+)]
 fn list(auth: AuthResult, params: PageParams) -> ApiResult<PagedResponse<PoolInfo>> {
     let client = auth?;
     params.bump_login(client)?;
     api::verify_privilege(client, config::privileges().pool_list)?;
 
     let offset = params.offset.unwrap_or(0);
-    let limit = std::cmp::min(params.limit.get(), MAX_POOLS_PER_PAGE);
+    let limit = params.limit(MAX_POOLS_PER_PAGE, MAX_POOLS_PER_PAGE);
     let fields = resource::create_table(params.fields()).map_err(Box::from)?;
 
     db::get_connection()?.transaction(|conn| {
         let mut query_builder = QueryBuilder::new(params.criteria())?;
-        query_builder.set_offset_and_limit(offset, limit);
-
-        let total = query_builder.count(conn)?;
-        let selected_pools = query_builder.load(conn)?;
+        let page = api::load_page(conn, &mut query_builder, offset, limit)?;
         Ok(PagedResponse {
             query: params.into_query(),
             offset,
             limit,
-            total,
-            results: PoolInfo::new_batch_from_ids(conn, selected_pools, &fields)?,
+            total: page.total,
+            has_more: page.has_more,
+            results: PoolInfo::new_batch_from_ids(conn, client, page.ids, &fields)?,
         })
     })
 }
 
+#[utoipa::path(
+    get,
+    path = "/pool/{id}",
+    summary = "Gets a pool",
+    params(("id" = i64, Path), ResourceParams),
+    responses((status = 200, body = PoolInfo))
+)]
 fn get(auth: AuthResult, pool_id: i64, params: ResourceParams) -> ApiResult<PoolInfo> {
     let client = auth?;
     params.bump_login(client)?;
@@ -94,12 +114,13 @@ fn get(auth: AuthResult, pool_id: i64, params: ResourceParams) -> ApiResult<Pool
         if !pool_exists {
             return Err(api::Error::NotFound(ResourceType::Pool));
         }
-        PoolInfo::new_from_id(conn, pool_id, &fields).map_err(api::Error::from)
+        PoolInfo::new_from_id(conn, client, pool_id, &fields).map_err(api::Error::from)
     })
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 #[serde(deny_unknown_fields)]
+#[schema(as = PoolCreateBody)]
 struct CreateBody {
     names: Vec<SmallString>,
     category: SmallString,
@@ -107,6 +128,14 @@ struct CreateBody {
     posts: Option<Vec<i64>>,
 }
 
+#[utoipa::path(
+    post,
+    path = "/pool",
+    summary = "Creates a pool",
+    params(ResourceParams),
+    request_body = CreateBody,
+    responses((status = 200, body = PoolInfo))
+)]
 fn create(auth: AuthResult, params: ResourceParams, body: CreateBody) -> ApiResult<PoolInfo> {
     let client = auth?;
     params.bump_login(client)?;
@@ -136,9 +165,17 @@ fn create(auth: AuthResult, params: ResourceParams, body: CreateBody) -> ApiResu
         update::pool::add_posts(conn, pool.id, 0, body.posts.unwrap_or_default())?;
         Ok::<_, api::Error>(pool)
     })?;
-    conn.transaction(|conn| PoolInfo::new(conn, pool, &fields).map_err(api::Error::from))
+    conn.transaction(|conn| PoolInfo::new(conn, client, pool, &fields).map_err(api::Error::from))
 }
 
+#[utoipa::path(
+    post,
+    path = "/pool-merge",
+    summary = "Merges two pools",
+    params(ResourceParams),
+    request_body = MergeBody<i64>,
+    responses((status = 200, body = PoolInfo))
+)]
 fn merge(auth: AuthResult, params: ResourceParams, body: MergeBody<i64>) -> ApiResult<PoolInfo> {
     let client = auth?;
     params.bump_login(client)?;
@@ -189,11 +226,12 @@ fn merge(auth: AuthResult, params: ResourceParams, body: MergeBody<i64>) -> ApiR
         diesel::delete(pool::table.find(remove_id)).execute(conn)?;
         update::pool::last_edit_time(conn, merge_to_id)
     })?;
-    conn.transaction(|conn| PoolInfo::new_from_id(conn, merge_to_id, &fields).map_err(api::Error::from))
+    conn.transaction(|conn| PoolInfo::new_from_id(conn, client, merge_to_id, &fields).map_err(api::Error::from))
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 #[serde(deny_unknown_fields)]
+#[schema(as = PoolUpdateBody)]
 struct UpdateBody {
     version: DateTime,
     category: Option<SmallString>,
@@ -202,6 +240,14 @@ struct UpdateBody {
     posts: Option<Vec<i64>>,
 }
 
+#[utoipa::path(
+    put,
+    path = "/pool/{id}",
+    summary = "Updates a pool",
+    params(("id" = i64, Path), ResourceParams),
+    request_body = UpdateBody,
+    responses((status = 200, body = PoolInfo))
+)]
 fn update(auth: AuthResult, pool_id: i64, params: ResourceParams, body: UpdateBody) -> ApiResult<PoolInfo> {
     let client = auth?;
     params.bump_login(client)?;
@@ -246,9 +292,50 @@ fn update(auth: AuthResult, pool_id: i64, params: ResourceParams, body: UpdateBo
         }
         update::pool::last_edit_time(conn, pool_id)
     })?;
-    conn.transaction(|conn| PoolInfo::new_from_id(conn, pool_id, &fields).map_err(api::Error::from))
+    conn.transaction(|conn| PoolInfo::new_from_id(conn, client, pool_id, &fields).map_err(api::Error::from))
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+struct ReorderBody {
+    version: DateTime,
+    /// Posts to move to the front of the pool, in their new order
+    posts: Vec<i64>,
+}
+
+#[utoipa::path(
+    post,
+    path = "/pool/{id}/reorder",
+    summary = "Reorders the posts of a pool",
+    params(("id" = i64, Path), ResourceParams),
+    request_body = ReorderBody,
+    responses((status = 200, body = PoolInfo))
+)]
+fn reorder(auth: AuthResult, pool_id: i64, params: ResourceParams, body: ReorderBody) -> ApiResult<PoolInfo> {
+    let client = auth?;
+    params.bump_login(client)?;
+    api::verify_privilege(client, config::privileges().pool_edit_post)?;
+    let fields = resource::create_table(params.fields()).map_err(Box::from)?;
+
+    let mut conn = db::get_connection()?;
+    conn.transaction(|conn| {
+        let pool_version: DateTime = pool::table.find(pool_id).select(pool::last_edit_time).first(conn)?;
+        api::verify_version(pool_version, body.version)?;
+
+        update::pool::reorder_posts(conn, pool_id, body.posts)?;
+        update::pool::last_edit_time(conn, pool_id)
+    })?;
+    conn.transaction(|conn| PoolInfo::new_from_id(conn, client, pool_id, &fields).map_err(api::Error::from))
 }
 
+#[utoipa::path(
+    delete,
+    path = "/pool/{id}",
+    summary = "Deletes a pool",
+    params(("id" = String, Path, description = "Name of the pool")),
+    request_body = DeleteBody,
+    responses((status = 200, description = "Pool was deleted"))
+)]
 fn delete(auth: AuthResult, name: String, client_version: DeleteBody) -> ApiResult<()> {
     let client = auth?;
     api::verify_privilege(client, config::privileges().pool_delete)?;
@@ -271,12 +358,13 @@ fn delete(auth: AuthResult, name: String, client_version: DeleteBody) -> ApiResu
 mod test {
     use crate::api::ApiResult;
     use crate::model::pool::Pool;
-    use crate::schema::{database_statistics, pool, pool_name, pool_statistics};
+    use crate::schema::{database_statistics, pool, pool_name, pool_post, pool_statistics};
     use crate::test::*;
     use crate::time::DateTime;
     use diesel::dsl::exists;
     use diesel::prelude::*;
     use serial_test::{parallel, serial};
+    use warp::http::StatusCode;
 
     // Exclude fields that involve creation_time or last_edit_time
     const FIELDS: &str = "&fields=id,description,category,names,posts,postCount";
@@ -289,7 +377,12 @@ mod test {
         verify_query(&format!("{QUERY}={SORT}{FIELDS}"), "pool/list.json").await?;
         verify_query(&format!("{QUERY}=sort:post-count&limit=1{FIELDS}"), "pool/list_most_posts.json").await?;
         verify_query(&format!("{QUERY}=category:Setting {SORT}{FIELDS}"), "pool/list_category_setting.json").await?;
-        verify_query(&format!("{QUERY}=name:*punk* {SORT}{FIELDS}"), "pool/list_name_punk.json").await
+        verify_query(&format!("{QUERY}=name:*punk* {SORT}{FIELDS}"), "pool/list_name_punk.json").await?;
+
+        const NAME_FIELDS: &str = "&fields=id,names";
+        verify_query(&format!("{QUERY}=post-count:1 {SORT}{NAME_FIELDS}"), "pool/list_post-count_filtered.json")
+            .await?;
+        verify_query(&format!("{QUERY}=-sort:name&limit=40{NAME_FIELDS}"), "pool/list_name_sorted.json").await
     }
 
     #[tokio::test]
@@ -308,6 +401,14 @@ mod test {
 
         verify_query(&format!("GET /pool/{POOL_ID}/?{FIELDS}"), "pool/get.json").await?;
 
+        // Posts above the restricted safety cap are left out of the pool's posts
+        verify_query_with_user(
+            "restricted_user",
+            &format!("GET /pool/{POOL_ID}/?{FIELDS}"),
+            "pool/get_restricted.json",
+        )
+        .await?;
+
         let new_last_edit_time = get_last_edit_time(&mut conn)?;
         assert_eq!(new_last_edit_time, last_edit_time);
         Ok(())
@@ -379,6 +480,24 @@ mod test {
         Ok(reset_database())
     }
 
+    #[tokio::test]
+    #[serial]
+    async fn merge_shared_posts() -> ApiResult<()> {
+        const MERGE_TO_ID: i64 = 6;
+        const QUERY: &str = "POST /pool-merge/?fields=id";
+        verify_status_with_body(QUERY, "pool/merge_shared_posts.json", StatusCode::OK).await?;
+
+        // Post 5 is in both pools, so it should only appear once at its original position
+        let mut conn = get_connection()?;
+        let post_ids: Vec<i64> = pool_post::table
+            .select(pool_post::post_id)
+            .filter(pool_post::pool_id.eq(MERGE_TO_ID))
+            .order_by(pool_post::order)
+            .load(&mut conn)?;
+        assert_eq!(post_ids, [5, 1, 2]);
+        Ok(reset_database())
+    }
+
     #[tokio::test]
     #[serial]
     async fn update() -> ApiResult<()> {
@@ -413,4 +532,31 @@ mod test {
         assert_eq!(new_post_count, post_count);
         Ok(())
     }
+
+    #[tokio::test]
+    #[serial]
+    async fn reorder() -> ApiResult<()> {
+        const POOL_ID: i64 = 2;
+        let get_pool_posts = |conn: &mut PgConnection| -> QueryResult<Vec<i64>> {
+            pool_post::table
+                .select(pool_post::post_id)
+                .filter(pool_post::pool_id.eq(POOL_ID))
+                .order_by(pool_post::order)
+                .load(conn)
+        };
+
+        let mut conn = get_connection()?;
+        let pool_posts = get_pool_posts(&mut conn)?;
+
+        let query = format!("POST /pool/{POOL_ID}/reorder/?{FIELDS}");
+        verify_query(&query, "pool/reorder.json").await?;
+        assert_eq!(get_pool_posts(&mut conn)?, [5, 1, 2]);
+
+        // Posts outside of the pool can't be reordered
+        verify_status_with_body(&query, "pool/reorder_invalid.json", StatusCode::BAD_REQUEST).await?;
+
+        verify_query(&query, "pool/reorder_restore.json").await?;
+        assert_eq!(get_pool_posts(&mut conn)?, pool_posts);
+        Ok(())
+    }
 }
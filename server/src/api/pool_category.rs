@@ -9,6 +9,7 @@ use crate::time::DateTime;
 use crate::{api, config, db, resource};
 use diesel::prelude::*;
 use serde::Deserialize;
+use utoipa::ToSchema;
 use warp::{Filter, Rejection, Reply};
 
 pub fn routes() -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
@@ -28,14 +29,14 @@ pub fn routes() -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone
         .and(api::auth())
         .and(warp::path!("pool-categories"))
         .and(api::resource_query())
-        .and(warp::body::json())
+        .and(api::json_body())
         .map(create)
         .map(api::Reply::from);
     let update = warp::put()
         .and(api::auth())
         .and(warp::path!("pool-category" / String))
         .and(api::resource_query())
-        .and(warp::body::json())
+        .and(api::json_body())
         .map(update)
         .map(api::Reply::from);
     let set_default = warp::put()
@@ -47,13 +48,20 @@ pub fn routes() -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone
     let delete = warp::delete()
         .and(api::auth())
         .and(warp::path!("pool-category" / String))
-        .and(warp::body::json())
+        .and(api::json_body())
         .map(delete)
         .map(api::Reply::from);
 
     list.or(get).or(create).or(update).or(set_default).or(delete)
 }
 
+#[utoipa::path(
+    get,
+    path = "/pool-categories",
+    summary = "Lists pool categories",
+    params(ResourceParams),
+    responses((status = 200, body = UnpagedResponse<PoolCategoryInfo>))
+)]
 fn list(auth: AuthResult, params: ResourceParams) -> ApiResult<UnpagedResponse<PoolCategoryInfo>> {
     let client = auth?;
     api::verify_privilege(client, config::privileges().pool_category_list)?;
@@ -66,6 +74,13 @@ fn list(auth: AuthResult, params: ResourceParams) -> ApiResult<UnpagedResponse<P
     })
 }
 
+#[utoipa::path(
+    get,
+    path = "/pool-category/{name}",
+    summary = "Gets a pool category",
+    params(("name" = String, Path), ResourceParams),
+    responses((status = 200, body = PoolCategoryInfo))
+)]
 fn get(auth: AuthResult, name: String, params: ResourceParams) -> ApiResult<PoolCategoryInfo> {
     let client = auth?;
     api::verify_privilege(client, config::privileges().pool_category_view)?;
@@ -82,13 +97,22 @@ fn get(auth: AuthResult, name: String, params: ResourceParams) -> ApiResult<Pool
     })
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 #[serde(deny_unknown_fields)]
+#[schema(as = PoolCategoryCreateBody)]
 struct CreateBody {
     name: SmallString,
     color: SmallString,
 }
 
+#[utoipa::path(
+    post,
+    path = "/pool-categories",
+    summary = "Creates a pool category",
+    params(ResourceParams),
+    request_body = CreateBody,
+    responses((status = 200, body = PoolCategoryInfo))
+)]
 fn create(auth: AuthResult, params: ResourceParams, body: CreateBody) -> ApiResult<PoolCategoryInfo> {
     let client = auth?;
     api::verify_privilege(client, config::privileges().pool_category_create)?;
@@ -108,14 +132,23 @@ fn create(auth: AuthResult, params: ResourceParams, body: CreateBody) -> ApiResu
     conn.transaction(|conn| PoolCategoryInfo::new(conn, category, &fields).map_err(api::Error::from))
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 #[serde(deny_unknown_fields)]
+#[schema(as = PoolCategoryUpdateBody)]
 struct UpdateBody {
     version: DateTime,
     name: Option<SmallString>,
     color: Option<SmallString>,
 }
 
+#[utoipa::path(
+    put,
+    path = "/pool-category/{name}",
+    summary = "Updates a pool category",
+    params(("name" = String, Path), ResourceParams),
+    request_body = UpdateBody,
+    responses((status = 200, body = PoolCategoryInfo))
+)]
 fn update(auth: AuthResult, name: String, params: ResourceParams, body: UpdateBody) -> ApiResult<PoolCategoryInfo> {
     let client = auth?;
     let fields = resource::create_table(params.fields()).map_err(Box::from)?;
@@ -150,6 +183,13 @@ fn update(auth: AuthResult, name: String, params: ResourceParams, body: UpdateBo
     conn.transaction(|conn| PoolCategoryInfo::new_from_id(conn, category_id, &fields).map_err(api::Error::from))
 }
 
+#[utoipa::path(
+    put,
+    path = "/pool-category/{name}/default",
+    summary = "Sets the default pool category",
+    params(("name" = String, Path), ResourceParams),
+    responses((status = 200, body = PoolCategoryInfo))
+)]
 fn set_default(auth: AuthResult, name: String, params: ResourceParams) -> ApiResult<PoolCategoryInfo> {
     let client = auth?;
     api::verify_privilege(client, config::privileges().pool_category_set_default)?;
@@ -196,6 +236,14 @@ fn set_default(auth: AuthResult, name: String, params: ResourceParams) -> ApiRes
     conn.transaction(|conn| PoolCategoryInfo::new(conn, new_default_category, &fields).map_err(api::Error::from))
 }
 
+#[utoipa::path(
+    delete,
+    path = "/pool-category/{name}",
+    summary = "Deletes a pool category",
+    params(("name" = String, Path)),
+    request_body = DeleteBody,
+    responses((status = 200, description = "Pool category was deleted"))
+)]
 fn delete(auth: AuthResult, name: String, client_version: DeleteBody) -> ApiResult<()> {
     let client = auth?;
     api::verify_privilege(client, config::privileges().pool_category_delete)?;
@@ -1,32 +1,47 @@
-use crate::api::{ApiResult, AuthResult, DeleteBody, MergeBody, PageParams, PagedResponse, RatingBody, ResourceParams};
-use crate::content::hash::PostHash;
+use crate::api::{
+    ApiResult, AuthResult, DeleteBody, MergeBody, PageParams, PagedResponse, RatingBody, ResourceParams,
+    UnpagedResponse,
+};
+use crate::auth::header::Client;
+use crate::content::hash::{self, PostHash};
+use crate::content::signature::COMPRESSED_SIGNATURE_LEN;
 use crate::content::thumbnail::{ThumbnailCategory, ThumbnailType};
-use crate::content::upload::{MAX_UPLOAD_SIZE, PartName};
-use crate::content::{Content, FileContents, signature, upload};
+use crate::content::upload::PartName;
+use crate::content::{Content, FileContents, decode, signature, upload};
 use crate::filesystem::Directory;
 use crate::model::comment::NewComment;
-use crate::model::enums::{PostFlag, PostFlags, PostSafety, PostType, ResourceType, Score};
+use crate::model::enums::{
+    ChecksumAlgorithm, MimeType, PostFlag, PostFlags, PostSafety, PostType, ResourceType, Score,
+};
 use crate::model::pool::PoolPost;
 use crate::model::post::{
-    CompressedSignature, NewPost, NewPostFeature, NewPostSignature, Post, PostFavorite, PostRelation, PostScore,
-    PostSignature, PostTag, SignatureIndexes,
+    Checksum, CompressedSignature, NewPost, NewPostFeature, NewPostRedirect, NewPostSignature, Post, PostFavorite,
+    PostRelation, PostScore, PostSignature, PostTag, SignatureIndexes,
 };
-use crate::resource::post::{Note, PostInfo};
+use crate::resource::pool::PoolInfo;
+use crate::resource::post::{FieldSelection, MicroPost, Note, NoteUpdate, PostInfo};
 use crate::schema::{
-    comment, pool_post, post, post_favorite, post_feature, post_relation, post_score, post_signature, post_statistics,
-    post_tag,
+    comment, pool, pool_post, post, post_favorite, post_feature, post_redirect, post_relation, post_score,
+    post_signature, post_statistics, post_tag,
 };
-use crate::search::post::QueryBuilder;
+use crate::search::Paginate;
+use crate::search::post::{QueryBuilder, can_view_any_safety, can_view_hidden, is_not_hidden, is_visible};
 use crate::string::SmallString;
 use crate::time::DateTime;
 use crate::{api, config, db, filesystem, resource, update};
-use diesel::dsl::exists;
+use diesel::dsl::count_star;
 use diesel::prelude::*;
+use futures::Stream;
 use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::BufReader;
+use std::str::FromStr;
 use std::sync::LazyLock;
 use tokio::sync::Mutex as AsyncMutex;
 use url::Url;
+use utoipa::{IntoParams, ToSchema};
+use warp::hyper::body::Buf;
 use warp::multipart::FormData;
 use warp::{Filter, Rejection, Reply};
 
@@ -43,12 +58,52 @@ pub fn routes() -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone
         .and(api::resource_query())
         .map(get)
         .map(api::Reply::from);
+    let get_batch = warp::get()
+        .and(api::auth())
+        .and(warp::path!("posts" / "batch"))
+        .and(warp::query())
+        .map(get_batch)
+        .map(api::Reply::from);
+    let get_by_checksum = warp::get()
+        .and(api::auth())
+        .and(warp::path!("posts" / "by-checksum" / String))
+        .and(api::resource_query())
+        .map(get_by_checksum)
+        .map(api::Reply::from);
+    let get_safety_breakdown = warp::get()
+        .and(api::auth())
+        .and(warp::path!("posts" / "safety-breakdown"))
+        .map(get_safety_breakdown)
+        .map(api::Reply::from);
     let get_neighbors = warp::get()
         .and(api::auth())
         .and(warp::path!("post" / i64 / "around"))
         .and(api::resource_query())
         .map(get_neighbors)
         .map(api::Reply::from);
+    let get_pool_neighbors = warp::get()
+        .and(api::auth())
+        .and(warp::path!("post" / i64 / "pool" / i64 / "neighbors"))
+        .and(api::resource_query())
+        .map(get_pool_neighbors)
+        .map(api::Reply::from);
+    let get_context = warp::get()
+        .and(api::auth())
+        .and(warp::path!("post" / i64 / "context"))
+        .and(warp::query())
+        .map(get_context)
+        .map(api::Reply::from);
+    let get_exif = warp::get()
+        .and(api::auth())
+        .and(warp::path!("post" / i64 / "exif"))
+        .map(get_exif)
+        .map(api::Reply::from);
+    let list_pools = warp::get()
+        .and(api::auth())
+        .and(warp::path!("post" / i64 / "pools"))
+        .and(warp::query())
+        .map(list_pools)
+        .map(api::Reply::from);
     let get_featured = warp::get()
         .and(api::auth())
         .and(warp::path!("featured-post"))
@@ -59,42 +114,50 @@ pub fn routes() -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone
         .and(api::auth())
         .and(warp::path!("featured-post"))
         .and(api::resource_query())
-        .and(warp::body::json())
+        .and(api::json_body())
         .map(feature)
         .map(api::Reply::from);
     let reverse_search = warp::post()
         .and(api::auth())
         .and(warp::path!("posts" / "reverse-search"))
         .and(api::resource_query())
-        .and(warp::body::json())
+        .and(api::json_body())
         .then(reverse_search)
         .map(api::Reply::from);
     let reverse_search_multipart = warp::post()
         .and(api::auth())
         .and(warp::path!("posts" / "reverse-search"))
         .and(api::resource_query())
-        .and(warp::filters::multipart::form().max_length(MAX_UPLOAD_SIZE))
+        .and(api::multipart_body())
         .then(reverse_search_multipart)
         .map(api::Reply::from);
+    let reverse_search_direct = warp::post()
+        .and(api::auth())
+        .and(warp::path!("posts" / "reverse-search-direct"))
+        .and(api::resource_query())
+        .and(warp::header::optional("content-type"))
+        .and(api::raw_body(config::body_limits().reverse_search))
+        .then(reverse_search_direct)
+        .map(api::Reply::from);
     let create = warp::post()
         .and(api::auth())
         .and(warp::path!("posts"))
         .and(api::resource_query())
-        .and(warp::body::json())
+        .and(api::json_body())
         .then(create)
         .map(api::Reply::from);
     let create_multipart = warp::post()
         .and(api::auth())
         .and(warp::path!("posts"))
         .and(api::resource_query())
-        .and(warp::filters::multipart::form().max_length(MAX_UPLOAD_SIZE))
+        .and(api::multipart_body())
         .then(create_multipart)
         .map(api::Reply::from);
     let merge = warp::post()
         .and(api::auth())
         .and(warp::path!("post-merge"))
         .and(api::resource_query())
-        .and(warp::body::json())
+        .and(api::json_body())
         .then(merge)
         .map(api::Reply::from);
     let favorite = warp::post()
@@ -103,31 +166,55 @@ pub fn routes() -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone
         .and(api::resource_query())
         .map(favorite)
         .map(api::Reply::from);
+    let batch_favorite = warp::post()
+        .and(api::auth())
+        .and(warp::path!("posts" / "batch-favorite"))
+        .and(api::resource_query())
+        .and(api::json_body())
+        .map(batch_favorite)
+        .map(api::Reply::from);
+    let view = warp::post()
+        .and(api::auth())
+        .and(warp::path!("post" / i64 / "view"))
+        .map(view)
+        .map(api::Reply::from);
     let rate = warp::put()
         .and(api::auth())
         .and(warp::path!("post" / i64 / "score"))
         .and(api::resource_query())
-        .and(warp::body::json())
+        .and(api::json_body())
         .map(rate)
         .map(api::Reply::from);
+    let lock = warp::post()
+        .and(api::auth())
+        .and(warp::path!("post" / i64 / "lock"))
+        .and(api::resource_query())
+        .map(lock)
+        .map(api::Reply::from);
+    let unlock = warp::post()
+        .and(api::auth())
+        .and(warp::path!("post" / i64 / "unlock"))
+        .and(api::resource_query())
+        .map(unlock)
+        .map(api::Reply::from);
     let update = warp::put()
         .and(api::auth())
         .and(warp::path!("post" / i64))
         .and(api::resource_query())
-        .and(warp::body::json())
+        .and(api::json_body())
         .then(update)
         .map(api::Reply::from);
     let update_multipart = warp::put()
         .and(api::auth())
         .and(warp::path!("post" / i64))
         .and(api::resource_query())
-        .and(warp::filters::multipart::form().max_length(MAX_UPLOAD_SIZE))
+        .and(api::multipart_body())
         .then(update_multipart)
         .map(api::Reply::from);
     let delete = warp::delete()
         .and(api::auth())
         .and(warp::path!("post" / i64))
-        .and(warp::body::json())
+        .and(api::json_body())
         .then(delete)
         .map(api::Reply::from);
     let unfavorite = warp::delete()
@@ -136,28 +223,74 @@ pub fn routes() -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone
         .and(api::resource_query())
         .map(unfavorite)
         .map(api::Reply::from);
+    let batch_unfavorite = warp::delete()
+        .and(api::auth())
+        .and(warp::path!("posts" / "batch-favorite"))
+        .and(api::resource_query())
+        .and(api::json_body())
+        .map(batch_unfavorite)
+        .map(api::Reply::from);
 
     list.or(get)
+        .or(get_batch)
+        .or(get_by_checksum)
+        .or(get_safety_breakdown)
         .or(get_neighbors)
+        .or(get_pool_neighbors)
+        .or(get_context)
+        .or(get_exif)
+        .or(list_pools)
         .or(get_featured)
         .or(feature)
         .or(reverse_search)
         .or(reverse_search_multipart)
+        .or(reverse_search_direct)
         .or(create)
         .or(create_multipart)
         .or(merge)
         .or(favorite)
+        .or(batch_favorite)
+        .or(view)
         .or(rate)
+        .or(lock)
+        .or(unlock)
         .or(update)
         .or(update_multipart)
         .or(delete)
         .or(unfavorite)
+        .or(batch_unfavorite)
 }
 
-const MAX_POSTS_PER_PAGE: i64 = 1000;
-
 static POST_TAG_MUTEX: LazyLock<AsyncMutex<()>> = LazyLock::new(|| AsyncMutex::new(()));
 
+/// Post relation cascade deletion can cause deadlocks when deleting related posts in quick succession.
+/// Deleting a post and merging a post into another both cascade, so they lock this mutex whenever
+/// the posts involved have any relations. This only serializes requests within one server process,
+/// so both also retry their transactions if they deadlock anyway.
+static POST_RELATION_MUTEX: LazyLock<AsyncMutex<()>> = LazyLock::new(|| AsyncMutex::new(()));
+
+/// Checks that `client` may edit a post with the given `flags`.
+/// Locked posts can only be edited by users with the `post_lock` privilege.
+pub(super) fn verify_unlocked(client: Client, flags: PostFlags) -> ApiResult<()> {
+    match flags.contains(PostFlag::Locked) {
+        true => api::verify_privilege(client, config::privileges().post_lock).map_err(|_| api::Error::PostLocked),
+        false => Ok(()),
+    }
+}
+
+/// Checks that the post with id `post_id` exists and can be seen by `client`.
+fn verify_visible(conn: &mut PgConnection, client: Client, post_id: i64) -> ApiResult<()> {
+    let visibility: Option<(PostFlags, PostSafety)> = post::table
+        .find(post_id)
+        .select((post::flags, post::safety))
+        .first(conn)
+        .optional()?;
+    match visibility {
+        Some((flags, safety)) if is_visible(client, flags, safety) => Ok(()),
+        _ => Err(api::Error::NotFound(ResourceType::Post)),
+    }
+}
+
 /// Runs an `update` that may add `tags` to a post as a transaction.
 ///
 /// Tagging multiple posts simultaneously can cause issues if two updates share tags.
@@ -180,31 +313,44 @@ where
     db::get_connection()?.transaction(update)
 }
 
+#[utoipa::path(
+    get,
+    path = "/posts",
+    summary = "Lists posts",
+    params(PageParams, ResourceParams),
+    responses((status = 200, body = PagedResponse<PostInfo>))
+)]
 fn list(auth: AuthResult, params: PageParams) -> ApiResult<PagedResponse<PostInfo>> {
     let client = auth?;
     params.bump_login(client)?;
     api::verify_privilege(client, config::privileges().post_list)?;
 
     let offset = params.offset.unwrap_or(0);
-    let limit = std::cmp::min(params.limit.get(), MAX_POSTS_PER_PAGE);
+    let pagination = config::pagination();
+    let limit = params.limit(pagination.default_post_page_size, pagination.max_post_page_size);
     let fields = resource::create_table(params.fields()).map_err(Box::from)?;
 
     db::get_connection()?.transaction(|conn| {
         let mut query_builder = QueryBuilder::new(client, params.criteria())?;
-        query_builder.set_offset_and_limit(offset, limit);
-
-        let total = query_builder.count(conn)?;
-        let selected_posts = query_builder.load(conn)?;
+        let page = api::load_page(conn, &mut query_builder, offset, limit)?;
         Ok(PagedResponse {
             query: params.into_query(),
             offset,
             limit,
-            total,
-            results: PostInfo::new_batch_from_ids(conn, client, selected_posts, &fields)?,
+            total: page.total,
+            has_more: page.has_more,
+            results: PostInfo::new_batch_from_ids(conn, client, page.ids, &fields)?,
         })
     })
 }
 
+#[utoipa::path(
+    get,
+    path = "/post/{id}",
+    summary = "Gets a post",
+    params(("id" = i64, Path), ResourceParams),
+    responses((status = 200, body = PostInfo))
+)]
 fn get(auth: AuthResult, post_id: i64, params: ResourceParams) -> ApiResult<PostInfo> {
     let client = auth?;
     params.bump_login(client)?;
@@ -212,20 +358,176 @@ fn get(auth: AuthResult, post_id: i64, params: ResourceParams) -> ApiResult<Post
 
     let fields = resource::create_table(params.fields()).map_err(Box::from)?;
     db::get_connection()?.transaction(|conn| {
-        let post_exists: bool = diesel::select(exists(post::table.find(post_id))).get_result(conn)?;
-        if !post_exists {
-            return Err(api::Error::NotFound(ResourceType::Post));
+        let visibility: Option<(PostFlags, PostSafety)> = post::table
+            .find(post_id)
+            .select((post::flags, post::safety))
+            .first(conn)
+            .optional()?;
+        match visibility {
+            Some((flags, safety)) if is_visible(client, flags, safety) => (),
+            Some(_) => return Err(api::Error::NotFound(ResourceType::Post)),
+            None => {
+                return Err(match merged_into(conn, post_id)? {
+                    Some(target_id) => {
+                        let query_string = params.to_query_string();
+                        api::Error::MovedPermanently(format!("/post/{target_id}{query_string}"))
+                    }
+                    None => api::Error::NotFound(ResourceType::Post),
+                });
+            }
         }
         PostInfo::new_from_id(conn, client, post_id, &fields).map_err(api::Error::from)
     })
 }
 
-#[derive(Serialize)]
+/// Returns the id of the post that the removed post with id `post_id` was merged into, if any.
+fn merged_into(conn: &mut PgConnection, post_id: i64) -> QueryResult<Option<i64>> {
+    post_redirect::table
+        .find(post_id)
+        .select(post_redirect::target_id)
+        .first(conn)
+        .optional()
+}
+
+/// Parameters of a request for several posts by id.
+/// The flattened [ResourceParams] must be listed separately in OpenAPI path parameters.
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct BatchParams {
+    /// Comma-separated list of post ids.
+    ids: String,
+    #[serde(flatten)]
+    #[param(ignore)]
+    params: ResourceParams,
+}
+
+/// Gets several posts at once. Posts are returned in the order their ids were requested,
+/// leaving out posts that don't exist or that the client can't see.
+#[utoipa::path(
+    get,
+    path = "/posts/batch",
+    summary = "Gets several posts by id",
+    params(BatchParams, ResourceParams),
+    responses((status = 200, body = UnpagedResponse<PostInfo>))
+)]
+fn get_batch(auth: AuthResult, params: BatchParams) -> ApiResult<UnpagedResponse<PostInfo>> {
+    let client = auth?;
+    params.params.bump_login(client)?;
+    api::verify_privilege(client, config::privileges().post_view)?;
+
+    let post_ids = params.ids.split(',').map(str::parse).collect::<Result<Vec<i64>, _>>()?;
+    let max_post_count = config::pagination().max_post_page_size;
+    if i64::try_from(post_ids.len()).unwrap_or(i64::MAX) > max_post_count {
+        return Err(api::Error::TooManyResources(ResourceType::Post, max_post_count));
+    }
+
+    let fields = resource::create_table(params.params.fields()).map_err(Box::from)?;
+    db::get_connection()?.transaction(|conn| {
+        let mut posts: Vec<Post> = post::table.filter(post::id.eq_any(&post_ids)).load(conn)?;
+        posts.retain(|post| is_visible(client, post.flags, post.safety));
+
+        // Collected in reverse so that the first occurrence of a duplicated id determines its position
+        let positions: HashMap<i64, usize> = post_ids
+            .iter()
+            .enumerate()
+            .rev()
+            .map(|(index, &post_id)| (post_id, index))
+            .collect();
+        posts.sort_by_key(|post| positions[&post.id]);
+        Ok(UnpagedResponse {
+            results: PostInfo::new_batch(conn, client, posts, &fields)?,
+        })
+    })
+}
+
+/// Finds the post with the given `checksum`, which can either be the full checksum
+/// or the MD5 checksum of the post content.
+#[utoipa::path(
+    get,
+    path = "/posts/by-checksum/{checksum}",
+    summary = "Gets a post by checksum",
+    params(("checksum" = String, Path), ResourceParams),
+    responses((status = 200, body = PostInfo))
+)]
+fn get_by_checksum(auth: AuthResult, checksum: String, params: ResourceParams) -> ApiResult<PostInfo> {
+    let client = auth?;
+    params.bump_login(client)?;
+    api::verify_privilege(client, config::privileges().post_view)?;
+
+    let checksum: Checksum = checksum.parse().map_err(Box::from)?;
+    let fields = resource::create_table(params.fields()).map_err(Box::from)?;
+    db::get_connection()?.transaction(|conn| {
+        let post: Post = post::table
+            .filter(post::checksum.eq(&checksum).or(post::checksum_md5.eq(&checksum)))
+            .first(conn)
+            .optional()?
+            .filter(|post: &Post| is_visible(client, post.flags, post.safety))
+            .ok_or(api::Error::NotFound(ResourceType::Post))?;
+        PostInfo::new(conn, client, post, &fields).map_err(api::Error::from)
+    })
+}
+
+/// Number of posts of each safety. Safeties the client isn't allowed to see are `null`.
+#[derive(Serialize, ToSchema)]
+struct SafetyBreakdown {
+    safe: Option<i64>,
+    sketchy: Option<i64>,
+    #[serde(rename = "unsafe")]
+    unsafe_: Option<i64>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/posts/safety-breakdown",
+    summary = "Counts posts by safety",
+    responses((status = 200, body = SafetyBreakdown))
+)]
+fn get_safety_breakdown(auth: AuthResult) -> ApiResult<SafetyBreakdown> {
+    let client = auth?;
+    api::verify_privilege(client, config::privileges().post_list)?;
+
+    let max_safety = match can_view_any_safety(client) {
+        true => PostSafety::Unsafe,
+        false => config::get().max_restricted_safety,
+    };
+    let mut query = post::table
+        .group_by(post::safety)
+        .select((post::safety, count_star()))
+        .filter(post::safety.le(max_safety))
+        .into_boxed();
+    if !can_view_hidden(client) {
+        query = query.filter(is_not_hidden());
+    }
+    let counts: Vec<(PostSafety, i64)> = query.load(&mut db::get_connection()?)?;
+
+    let count = |safety: PostSafety| {
+        (safety <= max_safety).then(|| {
+            counts
+                .iter()
+                .find(|&&(counted_safety, _)| counted_safety == safety)
+                .map_or(0, |&(_, count)| count)
+        })
+    };
+    Ok(SafetyBreakdown {
+        safe: count(PostSafety::Safe),
+        sketchy: count(PostSafety::Sketchy),
+        unsafe_: count(PostSafety::Unsafe),
+    })
+}
+
+#[derive(Serialize, ToSchema)]
 struct PostNeighbors {
     prev: Option<PostInfo>,
     next: Option<PostInfo>,
 }
 
+#[utoipa::path(
+    get,
+    path = "/post/{id}/around",
+    summary = "Gets the posts before and after a post",
+    params(("id" = i64, Path), ResourceParams),
+    responses((status = 200, body = PostNeighbors))
+)]
 fn get_neighbors(auth: AuthResult, post_id: i64, params: ResourceParams) -> ApiResult<PostNeighbors> {
     let client = auth?;
     params.bump_login(client)?;
@@ -245,63 +547,15 @@ fn get_neighbors(auth: AuthResult, post_id: i64, params: ResourceParams) -> ApiR
     let fields = resource::create_table(params.fields()).map_err(Box::from)?;
     let mut query_builder = QueryBuilder::new(client, params.criteria())?;
     db::get_connection()?.transaction(|conn| {
-        const INITIAL_LIMIT: i64 = 100;
-        const LIMIT_GROWTH: i64 = 8;
-
-        // Handle special cases first
-        if query_builder.criteria().has_random_sort() {
-            query_builder.set_offset_and_limit(0, 2);
-            let post_ids = query_builder.load_with(conn, |query| query.filter(post::id.ne(post_id)))?;
-            let post_infos = PostInfo::new_batch_from_ids(conn, client, post_ids, &fields)?;
-            return Ok(create_post_neighbors(post_infos, true));
-        }
-        if !query_builder.criteria().has_filter() && !query_builder.criteria().has_sort() {
-            // Optimized neighbor retrieval for simplest use case
-            let previous_post = post::table
-                .select(Post::as_select())
-                .filter(post::id.gt(post_id))
-                .order_by(post::id.asc())
-                .first(conn)
-                .optional()?;
-            let next_post = post::table
-                .select(Post::as_select())
-                .filter(post::id.lt(post_id))
-                .order_by(post::id.desc())
-                .first(conn)
-                .optional()?;
-
-            let has_previous_post = previous_post.is_some();
-            let posts = previous_post.into_iter().chain(next_post).collect();
-            let post_infos = PostInfo::new_batch(conn, client, posts, &fields)?;
-            return Ok(create_post_neighbors(post_infos, has_previous_post));
+        if let Some(target_id) = merged_into(conn, post_id)? {
+            let query_string = params.to_query_string();
+            return Err(api::Error::MovedPermanently(format!("/post/{target_id}/around{query_string}")));
         }
+        verify_visible(conn, client, post_id)?;
 
-        // Search for neighbors using exponentially increasing limit
-        let mut offset = 0;
-        let mut limit = INITIAL_LIMIT;
-        let (prev_post_id, next_post_id) = loop {
-            query_builder.set_offset_and_limit(offset, limit);
-            let post_id_batch = query_builder.load(conn)?;
-
-            let post_index = post_id_batch.iter().position(|&id| id == post_id);
-            if post_id_batch.len() < usize::try_from(limit).unwrap_or(usize::MAX)
-                || post_id_batch.len() == usize::MAX
-                || (post_index.is_some() && post_index != Some(post_id_batch.len().saturating_sub(1)))
-            {
-                let prev_post_id = post_index
-                    .and_then(|index| index.checked_sub(1))
-                    .and_then(|prev_index| post_id_batch.get(prev_index))
-                    .copied();
-                let next_post_id = post_index
-                    .and_then(|index| index.checked_add(1))
-                    .and_then(|next_index| post_id_batch.get(next_index))
-                    .copied();
-                break (prev_post_id, next_post_id);
-            }
-
-            offset += limit - 2;
-            limit = limit.saturating_mul(LIMIT_GROWTH);
-        };
+        let (prev_post_ids, next_post_ids) = get_neighbor_ids(conn, &mut query_builder, post_id, 1)?;
+        let prev_post_id = prev_post_ids.first().copied();
+        let next_post_id = next_post_ids.first().copied();
 
         let post_ids = prev_post_id.into_iter().chain(next_post_id).collect();
         let post_infos = PostInfo::new_batch_from_ids(conn, client, post_ids, &fields)?;
@@ -309,6 +563,298 @@ fn get_neighbors(auth: AuthResult, post_id: i64, params: ResourceParams) -> ApiR
     })
 }
 
+/// Gets the posts before and after a post in the ordering of the pool with id `pool_id`.
+/// Hidden posts are skipped over for clients that can't see them.
+#[utoipa::path(
+    get,
+    path = "/post/{id}/pool/{pool_id}/neighbors",
+    summary = "Gets the posts before and after a post in a pool",
+    params(("id" = i64, Path), ("pool_id" = i64, Path), ResourceParams),
+    responses((status = 200, body = PostNeighbors))
+)]
+fn get_pool_neighbors(
+    auth: AuthResult,
+    post_id: i64,
+    pool_id: i64,
+    params: ResourceParams,
+) -> ApiResult<PostNeighbors> {
+    let client = auth?;
+    params.bump_login(client)?;
+    api::verify_privilege(client, config::privileges().post_list)?;
+
+    let fields = resource::create_table(params.fields()).map_err(Box::from)?;
+    db::get_connection()?.transaction(|conn| {
+        let order = pool_post::table
+            .inner_join(post::table)
+            .select((pool_post::order, post::flags, post::safety))
+            .filter(pool_post::pool_id.eq(pool_id))
+            .filter(pool_post::post_id.eq(post_id))
+            .first::<(i64, PostFlags, PostSafety)>(conn)
+            .optional()?
+            .filter(|&(_, flags, safety)| is_visible(client, flags, safety))
+            .map(|(order, _, _)| order)
+            .ok_or(api::Error::NotFound(ResourceType::Post))?;
+
+        let pool_posts = || {
+            let query = pool_post::table
+                .inner_join(post::table)
+                .select(pool_post::post_id)
+                .filter(pool_post::pool_id.eq(pool_id))
+                .into_boxed();
+            let query = match can_view_hidden(client) {
+                true => query,
+                false => query.filter(is_not_hidden()),
+            };
+            match can_view_any_safety(client) {
+                true => query,
+                false => query.filter(post::safety.le(config::get().max_restricted_safety)),
+            }
+        };
+        let prev_post_id: Option<i64> = pool_posts()
+            .filter(pool_post::order.lt(order))
+            .order_by(pool_post::order.desc())
+            .first(conn)
+            .optional()?;
+        let next_post_id: Option<i64> = pool_posts()
+            .filter(pool_post::order.gt(order))
+            .order_by(pool_post::order)
+            .first(conn)
+            .optional()?;
+
+        let mut post_info = |post_id| PostInfo::new_from_id(conn, client, post_id, &fields);
+        Ok(PostNeighbors {
+            prev: prev_post_id.map(&mut post_info).transpose()?,
+            next: next_post_id.map(&mut post_info).transpose()?,
+        })
+    })
+}
+
+// Must be less than half of the initial neighbor search limit
+const MAX_CONTEXT_RADIUS: usize = 25;
+
+#[derive(Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+struct ContextParams {
+    /// Number of posts to return on each side
+    radius: usize,
+    #[serde(flatten)]
+    #[param(ignore)]
+    params: ResourceParams,
+}
+
+#[derive(Serialize, ToSchema)]
+struct PostContext {
+    prev: Vec<MicroPost>,
+    next: Vec<MicroPost>,
+}
+
+#[utoipa::path(
+    get,
+    path = "/post/{id}/context",
+    summary = "Gets the posts surrounding a post",
+    params(("id" = i64, Path), ContextParams, ResourceParams),
+    responses((status = 200, body = PostContext))
+)]
+fn get_context(auth: AuthResult, post_id: i64, params: ContextParams) -> ApiResult<PostContext> {
+    let client = auth?;
+    params.params.bump_login(client)?;
+    api::verify_privilege(client, config::privileges().post_list)?;
+
+    let to_micro_posts =
+        |post_ids: Vec<i64>| -> Vec<MicroPost> { post_ids.into_iter().map(|id| MicroPost::new(id, None)).collect() };
+
+    let radius = std::cmp::min(params.radius, MAX_CONTEXT_RADIUS);
+    let mut query_builder = QueryBuilder::new(client, params.params.criteria())?;
+    db::get_connection()?.transaction(|conn| {
+        verify_visible(conn, client, post_id)?;
+
+        let (prev_post_ids, next_post_ids) = get_neighbor_ids(conn, &mut query_builder, post_id, radius)?;
+        Ok(PostContext {
+            prev: to_micro_posts(prev_post_ids),
+            next: to_micro_posts(next_post_ids),
+        })
+    })
+}
+
+/// Reads every EXIF tag from the content of a post. EXIF data isn't stored in the database,
+/// so it is read from the post's file on demand.
+#[utoipa::path(
+    get,
+    path = "/post/{id}/exif",
+    summary = "Gets the EXIF metadata of a post",
+    params(("id" = i64, Path)),
+    responses((status = 200, body = HashMap<String, String>))
+)]
+fn get_exif(auth: AuthResult, post_id: i64) -> ApiResult<HashMap<String, String>> {
+    let client = auth?;
+    api::verify_privilege(client, config::privileges().post_view_exif)?;
+
+    let mime_type = db::get_connection()?.transaction(|conn| {
+        let post_properties: Option<(MimeType, PostFlags, PostSafety)> = post::table
+            .find(post_id)
+            .select((post::mime_type, post::flags, post::safety))
+            .first(conn)
+            .optional()?;
+        match post_properties {
+            Some((mime_type, flags, safety)) if is_visible(client, flags, safety) => Ok(mime_type),
+            _ => Err(api::Error::NotFound(ResourceType::Post)),
+        }
+    })?;
+
+    let file = File::open(PostHash::new(post_id).content_path(mime_type))?;
+    let exif = match exif::Reader::new().read_from_container(&mut BufReader::new(file)) {
+        Ok(exif) => exif,
+        Err(exif::Error::Io(err)) => return Err(api::Error::from(err)),
+        Err(_) => return Err(api::Error::NoExif),
+    };
+
+    // Tags in the primary image take precedence over tags in the embedded thumbnail
+    let mut tags = HashMap::new();
+    for field in exif.fields() {
+        let value = field.display_value().with_unit(&exif).to_string();
+        if field.ifd_num == exif::In::PRIMARY {
+            tags.insert(field.tag.to_string(), value);
+        } else {
+            tags.entry(field.tag.to_string()).or_insert(value);
+        }
+    }
+    Ok(tags)
+}
+
+/// Returns the ids of up to `radius` posts on either side of the post with id `post_id`
+/// in the ordering defined by `query_builder`. Both lists of ids are in search order.
+///
+/// Neighbors are searched for using an exponentially increasing limit. Consecutive batches
+/// overlap by `2 * radius` so that every neighbor of a post is contained in the same batch.
+fn get_neighbor_ids<'a>(
+    conn: &mut PgConnection,
+    query_builder: &'a mut QueryBuilder<'a>,
+    post_id: i64,
+    radius: usize,
+) -> ApiResult<(Vec<i64>, Vec<i64>)> {
+    const INITIAL_LIMIT: i64 = 100;
+    const LIMIT_GROWTH: i64 = 8;
+
+    let radius_limit = i64::try_from(radius).unwrap_or(i64::MAX);
+
+    // Handle special cases first
+    if query_builder.criteria().has_random_sort() {
+        query_builder.set_offset_and_limit(0, radius_limit.saturating_mul(2));
+        let mut prev_post_ids = query_builder.load_with(conn, |query| query.filter(post::id.ne(post_id)))?;
+        let next_post_ids = prev_post_ids.split_off(std::cmp::min(radius, prev_post_ids.len()));
+        return Ok((prev_post_ids, next_post_ids));
+    }
+    let criteria = query_builder.criteria();
+    if !criteria.has_filter() && !criteria.has_sort() && query_builder.includes_hidden() {
+        // Optimized neighbor retrieval for simplest use case
+        let mut prev_post_ids: Vec<i64> = post::table
+            .select(post::id)
+            .filter(post::id.gt(post_id))
+            .order_by(post::id.asc())
+            .limit(radius_limit)
+            .load(conn)?;
+        prev_post_ids.reverse();
+        let next_post_ids = post::table
+            .select(post::id)
+            .filter(post::id.lt(post_id))
+            .order_by(post::id.desc())
+            .limit(radius_limit)
+            .load(conn)?;
+        return Ok((prev_post_ids, next_post_ids));
+    }
+
+    let mut offset = 0;
+    let mut limit = INITIAL_LIMIT;
+    loop {
+        query_builder.set_offset_and_limit(offset, limit);
+        let post_id_batch = query_builder.load(conn)?;
+
+        let post_index = post_id_batch.iter().position(|&id| id == post_id);
+        if post_id_batch.len() < usize::try_from(limit).unwrap_or(usize::MAX)
+            || post_id_batch.len() == usize::MAX
+            || post_index.is_some_and(|index| index.saturating_add(radius) < post_id_batch.len())
+        {
+            let Some(index) = post_index else {
+                return Ok((Vec::new(), Vec::new()));
+            };
+            let prev_post_ids = post_id_batch[index.saturating_sub(radius)..index].to_vec();
+            let next_end = std::cmp::min(index.saturating_add(radius).saturating_add(1), post_id_batch.len());
+            let next_post_ids = post_id_batch[index + 1..next_end].to_vec();
+            return Ok((prev_post_ids, next_post_ids));
+        }
+
+        offset += limit - 2 * radius_limit;
+        limit = limit.saturating_mul(LIMIT_GROWTH);
+    }
+}
+
+/// Pools containing a post, in the same order as the `pools` field of [PostInfo].
+struct PostPools {
+    post_id: i64,
+}
+
+impl Paginate for PostPools {
+    fn total(&mut self, conn: &mut PgConnection) -> ApiResult<i64> {
+        pool_post::table
+            .filter(pool_post::post_id.eq(self.post_id))
+            .count()
+            .first(conn)
+            .map_err(api::Error::from)
+    }
+
+    fn page(&mut self, conn: &mut PgConnection, offset: i64, limit: i64) -> ApiResult<Vec<i64>> {
+        pool_post::table
+            .inner_join(pool::table)
+            .select(pool::id)
+            .filter(pool_post::post_id.eq(self.post_id))
+            .order((pool::category_id, pool::id))
+            .offset(offset)
+            .limit(limit)
+            .load(conn)
+            .map_err(api::Error::from)
+    }
+}
+
+/// Lists a page of the pools containing a post. Useful for posts that are in too many
+/// pools to retrieve them all at once through the `pools` field.
+#[utoipa::path(
+    get,
+    path = "/post/{id}/pools",
+    summary = "Lists the pools containing a post",
+    params(("id" = i64, Path), PageParams, ResourceParams),
+    responses((status = 200, body = PagedResponse<PoolInfo>))
+)]
+fn list_pools(auth: AuthResult, post_id: i64, params: PageParams) -> ApiResult<PagedResponse<PoolInfo>> {
+    let client = auth?;
+    params.bump_login(client)?;
+    api::verify_privilege(client, config::privileges().post_list)?;
+
+    let offset = params.offset.unwrap_or(0);
+    let limit = params.limit(api::pool::MAX_POOLS_PER_PAGE, api::pool::MAX_POOLS_PER_PAGE);
+    let fields = resource::create_table(params.fields()).map_err(Box::from)?;
+
+    db::get_connection()?.transaction(|conn| {
+        verify_visible(conn, client, post_id)?;
+
+        let page = api::load_page(conn, &mut PostPools { post_id }, offset, limit)?;
+        Ok(PagedResponse {
+            query: params.into_query(),
+            offset,
+            limit,
+            total: page.total,
+            has_more: page.has_more,
+            results: PoolInfo::new_batch_from_ids(conn, client, page.ids, &fields)?,
+        })
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/featured-post",
+    summary = "Gets the featured post",
+    params(ResourceParams),
+    responses((status = 200, body = Option<PostInfo>))
+)]
 fn get_featured(auth: AuthResult, params: ResourceParams) -> ApiResult<Option<PostInfo>> {
     let client = auth?;
     params.bump_login(client)?;
@@ -316,25 +862,36 @@ fn get_featured(auth: AuthResult, params: ResourceParams) -> ApiResult<Option<Po
 
     let fields = resource::create_table(params.fields()).map_err(Box::from)?;
     db::get_connection()?.transaction(|conn| {
-        let featured_post_id: Option<i64> = post_feature::table
-            .select(post_feature::post_id)
+        let featured_post: Option<(i64, PostFlags, PostSafety)> = post_feature::table
+            .inner_join(post::table)
+            .select((post_feature::post_id, post::flags, post::safety))
             .order_by(post_feature::time.desc())
             .first(conn)
             .optional()?;
 
-        featured_post_id
-            .map(|post_id| PostInfo::new_from_id(conn, client, post_id, &fields))
+        // A featured post the client can't see is treated as if nothing were featured
+        featured_post
+            .filter(|&(_, flags, safety)| is_visible(client, flags, safety))
+            .map(|(post_id, _, _)| PostInfo::new_from_id(conn, client, post_id, &fields))
             .transpose()
             .map_err(api::Error::from)
     })
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 #[serde(deny_unknown_fields)]
 struct FeatureBody {
     id: i64,
 }
 
+#[utoipa::path(
+    post,
+    path = "/featured-post",
+    summary = "Features a post",
+    params(ResourceParams),
+    request_body = FeatureBody,
+    responses((status = 200, body = PostInfo))
+)]
 fn feature(auth: AuthResult, params: ResourceParams, body: FeatureBody) -> ApiResult<PostInfo> {
     let client = auth?;
     params.bump_login(client)?;
@@ -349,13 +906,15 @@ fn feature(auth: AuthResult, params: ResourceParams, body: FeatureBody) -> ApiRe
     };
 
     let mut conn = db::get_connection()?;
+    verify_visible(&mut conn, client, body.id)?;
+
     diesel::insert_into(post_feature::table)
         .values(new_post_feature)
         .execute(&mut conn)?;
     conn.transaction(|conn| PostInfo::new_from_id(conn, client, body.id, &fields).map_err(api::Error::from))
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 #[serde(deny_unknown_fields)]
 #[serde(rename_all = "camelCase")]
 struct ReverseSearchBody {
@@ -365,19 +924,27 @@ struct ReverseSearchBody {
     content_url: Option<Url>,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 struct SimilarPost {
     distance: f64,
     post: PostInfo,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 struct ReverseSearchResponse {
     exact_post: Option<PostInfo>,
     similar_posts: Vec<SimilarPost>,
 }
 
+#[utoipa::path(
+    post,
+    path = "/posts/reverse-search",
+    summary = "Searches for similar posts",
+    params(ResourceParams),
+    request_body(content((ReverseSearchBody = "application/json"), ("multipart/form-data"))),
+    responses((status = 200, body = ReverseSearchResponse))
+)]
 async fn reverse_search(
     auth: AuthResult,
     params: ResourceParams,
@@ -391,55 +958,128 @@ async fn reverse_search(
     let fields = resource::create_table(params.fields()).map_err(Box::from)?;
     let content = Content::new(body.content, body.content_token, body.content_url)
         .ok_or(api::Error::MissingContent(ResourceType::Post))?;
+    search_content(client, content, &fields).await
+}
+
+#[utoipa::path(
+    post,
+    path = "/posts/reverse-search-direct",
+    summary = "Searches for similar posts given the raw content",
+    params(ResourceParams),
+    request_body(content = Vec<u8>, content_type = "application/octet-stream"),
+    responses((status = 200, body = ReverseSearchResponse))
+)]
+async fn reverse_search_direct(
+    auth: AuthResult,
+    params: ResourceParams,
+    content_type: Option<String>,
+    body: impl Stream<Item = Result<impl Buf, warp::Error>>,
+) -> ApiResult<ReverseSearchResponse> {
+    let _timer = crate::time::Timer::new("direct reverse search");
+    let client = auth?;
+    params.bump_login(client)?;
+    api::verify_privilege(client, config::privileges().post_reverse_search)?;
+
+    let fields = resource::create_table(params.fields()).map_err(Box::from)?;
+    let mime_type = content_type
+        .ok_or(api::Error::MissingContentType)
+        .and_then(|content_type| {
+            MimeType::from_str(content_type.trim())
+                .map_err(Box::from)
+                .map_err(api::Error::from)
+        })?;
+
+    // The body is only received once the client is known to be allowed to search
+    let file_contents = FileContents {
+        data: api::read_body(body).await?,
+        mime_type,
+    };
+
+    // Videos and flash can only be decoded from a file, so they go through a temporary upload
+    let Some(image_format) = mime_type.to_image_format() else {
+        return search_content(client, Content::DirectUpload(file_contents), &fields).await;
+    };
+
+    let image = decode::image(&file_contents.data, image_format)?;
+    let (checksum, checksum_algorithm) = hash::compute_checksum(&file_contents.data);
+    let signature = signature::compute(&image);
+    db::get_connection()?
+        .transaction(|conn| find_matches(conn, client, &checksum, checksum_algorithm, &signature, &fields))
+}
+
+/// Finds the post with the same checksum as `content` if one exists, or the posts that are
+/// similar to it otherwise.
+async fn search_content(client: Client, content: Content, fields: &FieldSelection) -> ApiResult<ReverseSearchResponse> {
     let content_properties = content.compute_properties().await?;
     db::get_connection()?.transaction(|conn| {
-        // Check for exact match
-        let exact_post = post::table
-            .filter(post::checksum.eq(content_properties.checksum))
-            .first(conn)
-            .optional()?;
-        if exact_post.is_some() {
-            return Ok(ReverseSearchResponse {
-                exact_post: exact_post
-                    .map(|post_id| PostInfo::new(conn, client, post_id, &fields))
-                    .transpose()?,
-                similar_posts: Vec::new(),
-            });
-        }
+        find_matches(
+            conn,
+            client,
+            &content_properties.checksum,
+            content_properties.checksum_algorithm,
+            &content_properties.signature,
+            fields,
+        )
+    })
+}
 
-        // Search for similar images candidates
-        let similar_signature_candidates =
-            PostSignature::find_similar_candidates(conn, signature::generate_indexes(&content_properties.signature))?;
-        println!("Found {} similar signatures", similar_signature_candidates.len());
+/// Finds the post with the given `checksum` if one exists, or the posts that are similar to
+/// content with the given `signature` otherwise.
+fn find_matches(
+    conn: &mut PgConnection,
+    client: Client,
+    checksum: &[u8],
+    checksum_algorithm: ChecksumAlgorithm,
+    signature: &[i64; COMPRESSED_SIGNATURE_LEN],
+    fields: &FieldSelection,
+) -> ApiResult<ReverseSearchResponse> {
+    // Check for exact match
+    let exact_post = post::table
+        .filter(post::checksum.eq(checksum))
+        .filter(post::checksum_algorithm.eq(checksum_algorithm))
+        .first(conn)
+        .optional()?
+        .filter(|post: &Post| is_visible(client, post.flags, post.safety));
+    if exact_post.is_some() {
+        return Ok(ReverseSearchResponse {
+            exact_post: exact_post
+                .map(|post_id| PostInfo::new(conn, client, post_id, fields))
+                .transpose()?,
+            similar_posts: Vec::new(),
+        });
+    }
 
-        // Filter candidates based on similarity score
-        let content_signature_cache = signature::cache(&content_properties.signature);
-        let mut similar_signatures: Vec<_> = similar_signature_candidates
-            .into_iter()
-            .filter_map(|post_signature| {
-                let distance = signature::distance(&content_signature_cache, &post_signature.signature);
-                let distance_threshold = 1.0 - config::get().post_similarity_threshold;
-                (distance < distance_threshold).then_some((post_signature.post_id, distance))
-            })
-            .collect();
-        if similar_signatures.is_empty() {
-            return Ok(ReverseSearchResponse {
-                exact_post: None,
-                similar_posts: Vec::new(),
-            });
+    // Search for similar images candidates
+    let indexes = signature::generate_indexes(signature);
+    let similar_signature_candidates = PostSignature::find_similar_candidates(conn, &indexes)?;
+    if cfg!(debug_assertions) {
+        let mut candidates_per_word = [0; signature::NUM_WORDS];
+        for candidate in similar_signature_candidates.iter() {
+            let candidate_indexes = signature::generate_indexes(&candidate.signature);
+            std::iter::zip(indexes, candidate_indexes)
+                .zip(candidates_per_word.iter_mut())
+                .filter(|((word, candidate_word), _)| word == candidate_word)
+                .for_each(|(_, count)| *count += 1);
         }
+        println!(
+            "DEBUG: Found {} similar signature candidates. Candidates per word: {candidates_per_word:?}",
+            similar_signature_candidates.len()
+        );
+    }
 
-        similar_signatures.sort_unstable_by(|(_, dist_a), (_, dist_b)| dist_a.partial_cmp(dist_b).unwrap());
-
-        let (post_ids, distances): (Vec<_>, Vec<_>) = similar_signatures.into_iter().unzip();
-        Ok(ReverseSearchResponse {
-            exact_post: None,
-            similar_posts: PostInfo::new_batch_from_ids(conn, client, post_ids, &fields)?
-                .into_iter()
-                .zip(distances)
-                .map(|(post, distance)| SimilarPost { distance, post })
-                .collect(),
+    // Filter candidates based on similarity score
+    let content_signature_cache = signature::cache(signature);
+    let distances: HashMap<i64, f64> = similar_signature_candidates
+        .into_iter()
+        .filter_map(|post_signature| {
+            let distance = signature::distance(&content_signature_cache, &post_signature.signature);
+            let distance_threshold = 1.0 - config::get().post_similarity_threshold;
+            (distance < distance_threshold).then_some((post_signature.post_id, distance))
         })
+        .collect();
+    Ok(ReverseSearchResponse {
+        exact_post: None,
+        similar_posts: similar_posts(conn, client, &distances, fields)?,
     })
 }
 
@@ -463,9 +1103,39 @@ async fn reverse_search_multipart(
     reverse_search(auth, params, reverse_search_body).await
 }
 
-#[derive(Deserialize)]
+/// Retrieves the posts in `distances`, ordered from most to least similar.
+/// Distances are looked up by post id, so posts that no longer exist can't shift them onto other posts.
+fn similar_posts(
+    conn: &mut PgConnection,
+    client: Client,
+    distances: &HashMap<i64, f64>,
+    fields: &FieldSelection,
+) -> QueryResult<Vec<SimilarPost>> {
+    if distances.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let post_ids: Vec<i64> = distances.keys().copied().collect();
+    let mut posts: Vec<Post> = post::table.filter(post::id.eq_any(&post_ids)).load(conn)?;
+    posts.retain(|post| is_visible(client, post.flags, post.safety));
+    posts.sort_unstable_by(|post_a, post_b| {
+        distances[&post_a.id]
+            .total_cmp(&distances[&post_b.id])
+            .then(post_a.id.cmp(&post_b.id))
+    });
+
+    let post_distances: Vec<f64> = posts.iter().map(|post| distances[&post.id]).collect();
+    Ok(PostInfo::new_batch(conn, client, posts, fields)?
+        .into_iter()
+        .zip(post_distances)
+        .map(|(post, distance)| SimilarPost { distance, post })
+        .collect())
+}
+
+#[derive(Deserialize, ToSchema)]
 #[serde(deny_unknown_fields)]
 #[serde(rename_all = "camelCase")]
+#[schema(as = PostCreateBody)]
 struct CreateBody {
     safety: PostSafety,
     #[serde(skip_deserializing)]
@@ -485,9 +1155,24 @@ struct CreateBody {
     flags: Option<Vec<PostFlag>>,
 }
 
+/// Returns the id of the user a new post is attributed to. Anonymous uploads aren't attributed
+/// to anyone, even if the client is logged in.
+fn uploader_id(client: Client, anonymous: bool) -> Option<i64> {
+    client.id.filter(|_| !anonymous)
+}
+
+#[utoipa::path(
+    post,
+    path = "/posts",
+    summary = "Creates a post",
+    params(ResourceParams),
+    request_body(content((CreateBody = "application/json"), ("multipart/form-data"))),
+    responses((status = 200, body = PostInfo))
+)]
 async fn create(auth: AuthResult, params: ResourceParams, body: CreateBody) -> ApiResult<PostInfo> {
     let client = auth?;
-    let required_rank = match body.anonymous.unwrap_or(false) {
+    let anonymous = body.anonymous.unwrap_or(false);
+    let required_rank = match anonymous {
         true => config::privileges().post_create_anonymous,
         false => config::privileges().post_create_identified,
     };
@@ -504,9 +1189,15 @@ async fn create(auth: AuthResult, params: ResourceParams, body: CreateBody) -> A
         None => None,
     };
     let flags = content_properties.flags | PostFlags::from_slice(&body.flags.unwrap_or_default());
+    if flags.contains(PostFlag::Hidden) {
+        api::verify_privilege(client, config::privileges().post_edit_flag_hidden)?;
+    }
+    if flags.contains(PostFlag::Locked) {
+        api::verify_privilege(client, config::privileges().post_lock)?;
+    }
 
     let new_post = NewPost {
-        user_id: client.id,
+        user_id: uploader_id(client, anonymous),
         file_size: content_properties.file_size as i64,
         width: content_properties.width as i32,
         height: content_properties.height as i32,
@@ -518,6 +1209,7 @@ async fn create(auth: AuthResult, params: ResourceParams, body: CreateBody) -> A
         flags,
         source: body.source.as_deref().unwrap_or(""),
         description: body.description.as_deref().unwrap_or(""),
+        checksum_algorithm: content_properties.checksum_algorithm,
     };
 
     let post_id = tagging_update(body.tags.as_deref(), |conn| {
@@ -525,7 +1217,7 @@ async fn create(auth: AuthResult, params: ResourceParams, body: CreateBody) -> A
         let tag_ids = body
             .tags
             .as_deref()
-            .map(|names| update::tag::get_or_create_tag_ids(conn, client, names, false))
+            .map(|names| update::tag::get_or_create_tag_ids(conn, client, names, "tags", false))
             .transpose()?;
 
         let post_id = diesel::insert_into(post::table)
@@ -587,7 +1279,7 @@ async fn create_multipart(auth: AuthResult, params: ResourceParams, form_data: F
     create(auth, params, new_post).await
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 #[serde(deny_unknown_fields)]
 #[serde(rename_all = "camelCase")]
 struct PostMergeBody {
@@ -596,6 +1288,14 @@ struct PostMergeBody {
     replace_content: bool,
 }
 
+#[utoipa::path(
+    post,
+    path = "/post-merge",
+    summary = "Merges two posts",
+    params(ResourceParams),
+    request_body = PostMergeBody,
+    responses((status = 200, body = PostInfo))
+)]
 async fn merge(auth: AuthResult, params: ResourceParams, body: PostMergeBody) -> ApiResult<PostInfo> {
     let client = auth?;
     params.bump_login(client)?;
@@ -609,12 +1309,27 @@ async fn merge(auth: AuthResult, params: ResourceParams, body: PostMergeBody) ->
     let remove_hash = PostHash::new(remove_id);
     let merge_to_hash = PostHash::new(merge_to_id);
 
+    let relation_count: i64 = post_statistics::table
+        .filter(post_statistics::post_id.eq_any([remove_id, merge_to_id]))
+        .select(post_statistics::relation_count)
+        .load(&mut db::get_connection()?)?
+        .into_iter()
+        .sum();
+    let _lock;
+    if relation_count > 0 {
+        _lock = POST_RELATION_MUTEX.lock().await;
+    }
+
     let fields = resource::create_table(params.fields()).map_err(Box::from)?;
-    let merged_post = tagging_update(Some(&[]), |conn| {
+    let _tag_lock = POST_TAG_MUTEX.lock().await;
+    let mut swapped_content = false;
+    let (merged_post, remove_mime_type) = db::deadlock_prone_transaction(&mut db::get_connection()?, |conn| {
         let remove_post: Post = post::table.find(remove_id).first(conn)?;
         let mut merge_to_post: Post = post::table.find(merge_to_id).first(conn)?;
         api::verify_version(remove_post.last_edit_time, body.post_info.remove_version)?;
         api::verify_version(merge_to_post.last_edit_time, body.post_info.merge_to_version)?;
+        verify_unlocked(client, remove_post.flags)?;
+        verify_unlocked(client, merge_to_post.flags)?;
 
         // Merge relations
         let involved_relations: Vec<PostRelation> = post_relation::table
@@ -766,11 +1481,26 @@ async fn merge(auth: AuthResult, params: ResourceParams, body: PostMergeBody) ->
                 .execute(conn)?;
         }
 
+        // Redirect links to the removed post, including ones to posts previously merged into it.
+        // This needs to be done before deletion because redirects to the removed post cascade
+        diesel::update(post_redirect::table.filter(post_redirect::target_id.eq(remove_id)))
+            .set(post_redirect::target_id.eq(merge_to_id))
+            .execute(conn)?;
+        let new_redirect = NewPostRedirect {
+            removed_id: remove_id,
+            target_id: merge_to_id,
+        };
+        diesel::insert_into(post_redirect::table)
+            .values(new_redirect)
+            .execute(conn)?;
+
         diesel::delete(post::table.find(remove_id)).execute(conn)?;
 
         let remove_mime_type = if body.replace_content {
-            if !cfg!(test) {
+            // Content stays swapped if the transaction is retried, so it is only swapped once
+            if !cfg!(test) && !swapped_content {
                 filesystem::swap_posts(&remove_hash, remove_post.mime_type, &merge_to_hash, merge_to_post.mime_type)?;
+                swapped_content = true;
             }
 
             let old_mime_type = merge_to_post.mime_type;
@@ -782,6 +1512,7 @@ async fn merge(auth: AuthResult, params: ResourceParams, body: PostMergeBody) ->
             merge_to_post.type_ = remove_post.type_;
             merge_to_post.mime_type = remove_post.mime_type;
             merge_to_post.checksum = remove_post.checksum;
+            merge_to_post.checksum_algorithm = remove_post.checksum_algorithm;
             merge_to_post.checksum_md5 = remove_post.checksum_md5;
             merge_to_post.flags = remove_post.flags;
             merge_to_post.source = remove_post.source;
@@ -795,20 +1526,50 @@ async fn merge(auth: AuthResult, params: ResourceParams, body: PostMergeBody) ->
             remove_post.mime_type
         };
 
-        if config::get().delete_source_files && !cfg!(test) {
-            filesystem::delete_post(&remove_hash, remove_mime_type)?;
-        }
-        update::post::last_edit_time(conn, merge_to_id).map(|_| merge_to_post)
-    })
-    .await?;
+        update::post::last_edit_time(conn, merge_to_id)?;
+        Ok((merge_to_post, remove_mime_type))
+    })?;
+    if config::get().delete_source_files && !cfg!(test) {
+        filesystem::delete_post(&remove_hash, remove_mime_type)?;
+    }
     db::get_connection()?
         .transaction(|conn| PostInfo::new(conn, client, merged_post, &fields).map_err(api::Error::from))
 }
 
-fn favorite(auth: AuthResult, post_id: i64, params: ResourceParams) -> ApiResult<PostInfo> {
-    let client = auth?;
-    params.bump_login(client)?;
-    api::verify_privilege(client, config::privileges().post_favorite)?;
+/// Returns an error if `prevent_self_favorite` is set and any of the posts with the
+/// given `post_ids` were uploaded by the user with id `user_id`.
+fn verify_not_self_favorite(
+    conn: &mut PgConnection,
+    user_id: i64,
+    post_ids: &[i64],
+    prevent_self_favorite: bool,
+) -> ApiResult<()> {
+    if !prevent_self_favorite {
+        return Ok(());
+    }
+
+    let own_post_count: i64 = post::table
+        .filter(post::id.eq_any(post_ids))
+        .filter(post::user_id.eq(user_id))
+        .count()
+        .first(conn)?;
+    match own_post_count {
+        0 => Ok(()),
+        _ => Err(api::Error::SelfFavorite),
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/post/{id}/favorite",
+    summary = "Adds a post to favorites",
+    params(("id" = i64, Path), ResourceParams),
+    responses((status = 200, body = PostInfo))
+)]
+fn favorite(auth: AuthResult, post_id: i64, params: ResourceParams) -> ApiResult<PostInfo> {
+    let client = auth?;
+    params.bump_login(client)?;
+    api::verify_privilege(client, config::privileges().post_favorite)?;
 
     let fields = resource::create_table(params.fields()).map_err(Box::from)?;
     let user_id = client.id.ok_or(api::Error::NotLoggedIn)?;
@@ -820,15 +1581,114 @@ fn favorite(auth: AuthResult, post_id: i64, params: ResourceParams) -> ApiResult
 
     let mut conn = db::get_connection()?;
     conn.transaction(|conn| {
-        diesel::delete(post_favorite::table.find((post_id, user_id))).execute(conn)?;
+        verify_visible(conn, client, post_id)?;
+        verify_not_self_favorite(conn, user_id, &[post_id], config::get().prevent_self_favorite)?;
         diesel::insert_into(post_favorite::table)
             .values(new_post_favorite)
+            .on_conflict_do_nothing()
             .execute(conn)
             .map_err(api::Error::from)
     })?;
     conn.transaction(|conn| PostInfo::new_from_id(conn, client, post_id, &fields).map_err(api::Error::from))
 }
 
+/// Represents body of a request to add or remove several posts from favorites.
+#[derive(Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+struct BatchFavoriteBody {
+    ids: Vec<i64>,
+}
+
+impl BatchFavoriteBody {
+    /// Returns the requested post ids with repeated ids removed, keeping the order they were given in.
+    /// Fails if any of the posts don't exist or can't be seen by `client`.
+    fn post_ids(&self, conn: &mut PgConnection, client: Client) -> ApiResult<Vec<i64>> {
+        let mut seen = HashSet::new();
+        let post_ids: Vec<i64> = self.ids.iter().copied().filter(|&id| seen.insert(id)).collect();
+        let max_post_count = config::pagination().max_post_page_size;
+        if i64::try_from(post_ids.len()).unwrap_or(i64::MAX) > max_post_count {
+            return Err(api::Error::TooManyResources(ResourceType::Post, max_post_count));
+        }
+
+        let visibilities: Vec<(PostFlags, PostSafety)> = post::table
+            .select((post::flags, post::safety))
+            .filter(post::id.eq_any(&post_ids))
+            .load(conn)?;
+        let visible_post_count = visibilities
+            .into_iter()
+            .filter(|&(flags, safety)| is_visible(client, flags, safety))
+            .count();
+        if visible_post_count != post_ids.len() {
+            return Err(api::Error::NotFound(ResourceType::Post));
+        }
+        Ok(post_ids)
+    }
+}
+
+#[utoipa::path(
+    post,
+    path = "/posts/batch-favorite",
+    summary = "Adds several posts to favorites",
+    params(ResourceParams),
+    request_body = BatchFavoriteBody,
+    responses((status = 200, body = UnpagedResponse<PostInfo>))
+)]
+fn batch_favorite(
+    auth: AuthResult,
+    params: ResourceParams,
+    body: BatchFavoriteBody,
+) -> ApiResult<UnpagedResponse<PostInfo>> {
+    let client = auth?;
+    params.bump_login(client)?;
+    api::verify_privilege(client, config::privileges().post_favorite)?;
+
+    let fields = resource::create_table(params.fields()).map_err(Box::from)?;
+    let user_id = client.id.ok_or(api::Error::NotLoggedIn)?;
+
+    db::get_connection()?.transaction(|conn| {
+        let post_ids = body.post_ids(conn, client)?;
+        verify_not_self_favorite(conn, user_id, &post_ids, config::get().prevent_self_favorite)?;
+        let time = DateTime::now();
+        let new_post_favorites: Vec<_> = post_ids
+            .iter()
+            .map(|&post_id| PostFavorite { post_id, user_id, time })
+            .collect();
+        diesel::insert_into(post_favorite::table)
+            .values(new_post_favorites)
+            .on_conflict_do_nothing()
+            .execute(conn)?;
+        Ok(UnpagedResponse {
+            results: PostInfo::new_batch_from_ids(conn, client, post_ids, &fields)?,
+        })
+    })
+}
+
+/// Counts a view of a post. Views are written to the database periodically rather than
+/// immediately, so view counts can lag behind by up to the configured flush interval.
+#[utoipa::path(
+    post,
+    path = "/post/{id}/view",
+    summary = "Counts a view of a post",
+    params(("id" = i64, Path)),
+    responses((status = 200))
+)]
+fn view(auth: AuthResult, post_id: i64) -> ApiResult<()> {
+    let client = auth?;
+    api::verify_privilege(client, config::privileges().post_view)?;
+
+    db::get_connection()?.transaction(|conn| verify_visible(conn, client, post_id))?;
+    update::post::record_view(post_id);
+    Ok(())
+}
+
+#[utoipa::path(
+    put,
+    path = "/post/{id}/score",
+    summary = "Rates a post",
+    params(("id" = i64, Path), ResourceParams),
+    request_body = RatingBody,
+    responses((status = 200, body = PostInfo))
+)]
 fn rate(auth: AuthResult, post_id: i64, params: ResourceParams, body: RatingBody) -> ApiResult<PostInfo> {
     let client = auth?;
     params.bump_login(client)?;
@@ -839,27 +1699,81 @@ fn rate(auth: AuthResult, post_id: i64, params: ResourceParams, body: RatingBody
 
     let mut conn = db::get_connection()?;
     conn.transaction(|conn| {
-        diesel::delete(post_score::table.find((post_id, user_id))).execute(conn)?;
-
-        if let Ok(score) = Score::try_from(*body) {
-            let new_post_score = PostScore {
-                post_id,
-                user_id,
-                score,
-                time: DateTime::now(),
-            };
-            diesel::insert_into(post_score::table)
-                .values(new_post_score)
-                .execute(conn)?;
+        verify_visible(conn, client, post_id)?;
+
+        // Upserting keeps repeated ratings from racing each other
+        match Score::try_from(*body) {
+            Ok(score) => {
+                let time = DateTime::now();
+                let new_post_score = PostScore {
+                    post_id,
+                    user_id,
+                    score,
+                    time,
+                };
+                diesel::insert_into(post_score::table)
+                    .values(new_post_score)
+                    .on_conflict((post_score::post_id, post_score::user_id))
+                    .do_update()
+                    .set((post_score::score.eq(score), post_score::time.eq(time)))
+                    .execute(conn)?;
+            }
+            Err(_) => {
+                diesel::delete(post_score::table.find((post_id, user_id))).execute(conn)?;
+            }
         }
         Ok::<_, api::Error>(())
     })?;
     conn.transaction(|conn| PostInfo::new_from_id(conn, client, post_id, &fields).map_err(api::Error::from))
 }
 
-#[derive(Deserialize)]
+#[utoipa::path(
+    post,
+    path = "/post/{id}/lock",
+    summary = "Locks a post against further edits",
+    params(("id" = i64, Path), ResourceParams),
+    responses((status = 200, body = PostInfo))
+)]
+fn lock(auth: AuthResult, post_id: i64, params: ResourceParams) -> ApiResult<PostInfo> {
+    set_locked(auth, post_id, params, true)
+}
+
+#[utoipa::path(
+    post,
+    path = "/post/{id}/unlock",
+    summary = "Unlocks a post",
+    params(("id" = i64, Path), ResourceParams),
+    responses((status = 200, body = PostInfo))
+)]
+fn unlock(auth: AuthResult, post_id: i64, params: ResourceParams) -> ApiResult<PostInfo> {
+    set_locked(auth, post_id, params, false)
+}
+
+/// Sets or clears the locked flag of the post with id `post_id`.
+fn set_locked(auth: AuthResult, post_id: i64, params: ResourceParams, locked: bool) -> ApiResult<PostInfo> {
+    let client = auth?;
+    params.bump_login(client)?;
+    api::verify_privilege(client, config::privileges().post_lock)?;
+
+    let fields = resource::create_table(params.fields()).map_err(Box::from)?;
+    let mut conn = db::get_connection()?;
+    conn.transaction(|conn| {
+        let flags: PostFlags = post::table.find(post_id).select(post::flags).first(conn)?;
+        let updated_flags = match locked {
+            true => flags | PostFlag::Locked,
+            false => flags.without(PostFlag::Locked),
+        };
+        diesel::update(post::table.find(post_id))
+            .set(post::flags.eq(updated_flags))
+            .execute(conn)
+    })?;
+    conn.transaction(|conn| PostInfo::new_from_id(conn, client, post_id, &fields).map_err(api::Error::from))
+}
+
+#[derive(Deserialize, ToSchema)]
 #[serde(deny_unknown_fields)]
 #[serde(rename_all = "camelCase")]
+#[schema(as = PostUpdateBody)]
 struct UpdateBody {
     version: DateTime,
     safety: Option<PostSafety>,
@@ -867,7 +1781,7 @@ struct UpdateBody {
     description: Option<String>,
     relations: Option<Vec<i64>>,
     tags: Option<Vec<SmallString>>,
-    notes: Option<Vec<Note>>,
+    notes: Option<Vec<NoteUpdate>>,
     flags: Option<Vec<PostFlag>>,
     #[serde(skip_deserializing)]
     content: Option<FileContents>,
@@ -879,6 +1793,14 @@ struct UpdateBody {
     thumbnail_url: Option<Url>,
 }
 
+#[utoipa::path(
+    put,
+    path = "/post/{id}",
+    summary = "Updates a post",
+    params(("id" = i64, Path), ResourceParams),
+    request_body(content((UpdateBody = "application/json"), ("multipart/form-data"))),
+    responses((status = 200, body = PostInfo))
+)]
 async fn update(auth: AuthResult, post_id: i64, params: ResourceParams, body: UpdateBody) -> ApiResult<PostInfo> {
     let client = auth?;
     params.bump_login(client)?;
@@ -896,8 +1818,12 @@ async fn update(auth: AuthResult, post_id: i64, params: ResourceParams, body: Up
     };
 
     tagging_update(body.tags.as_deref(), |conn| {
-        let post_version = post::table.find(post_id).select(post::last_edit_time).first(conn)?;
+        let (post_version, current_flags): (DateTime, PostFlags) = post::table
+            .find(post_id)
+            .select((post::last_edit_time, post::flags))
+            .first(conn)?;
         api::verify_version(post_version, body.version)?;
+        verify_unlocked(client, current_flags)?;
 
         if let Some(safety) = body.safety {
             api::verify_privilege(client, config::privileges().post_edit_safety)?;
@@ -929,20 +1855,25 @@ async fn update(auth: AuthResult, post_id: i64, params: ResourceParams, body: Up
         if let Some(tags) = body.tags.as_deref() {
             api::verify_privilege(client, config::privileges().post_edit_tag)?;
 
-            let updated_tag_ids = update::tag::get_or_create_tag_ids(conn, client, tags, false)?;
+            let updated_tag_ids = update::tag::get_or_create_tag_ids(conn, client, tags, "tags", false)?;
             update::post::delete_tags(conn, post_id)?;
             update::post::add_tags(conn, post_id, updated_tag_ids)?;
         }
         if let Some(notes) = body.notes {
             api::verify_privilege(client, config::privileges().post_edit_note)?;
 
-            update::post::delete_notes(conn, post_id)?;
-            update::post::add_notes(conn, post_id, notes)?;
+            update::post::update_notes(conn, post_id, notes)?;
         }
         if let Some(flags) = body.flags {
             api::verify_privilege(client, config::privileges().post_edit_flag)?;
 
             let updated_flags = PostFlags::from_slice(&flags);
+            if updated_flags.contains(PostFlag::Hidden) != current_flags.contains(PostFlag::Hidden) {
+                api::verify_privilege(client, config::privileges().post_edit_flag_hidden)?;
+            }
+            if updated_flags.contains(PostFlag::Locked) != current_flags.contains(PostFlag::Locked) {
+                api::verify_privilege(client, config::privileges().post_lock)?;
+            }
             diesel::update(post::table.find(post_id))
                 .set(post::flags.eq(updated_flags))
                 .execute(conn)?;
@@ -960,6 +1891,7 @@ async fn update(auth: AuthResult, post_id: i64, params: ResourceParams, body: Up
             post.type_ = PostType::from(content_properties.mime_type);
             post.mime_type = content_properties.mime_type;
             post.checksum = content_properties.checksum;
+            post.checksum_algorithm = content_properties.checksum_algorithm;
             post.flags |= content_properties.flags;
             post.save_changes::<Post>(conn)?;
 
@@ -1017,6 +1949,14 @@ async fn update_multipart(
     update(auth, post_id, params, post_update).await
 }
 
+#[utoipa::path(
+    delete,
+    path = "/post/{id}",
+    summary = "Deletes a post",
+    params(("id" = i64, Path)),
+    request_body = DeleteBody,
+    responses((status = 200, description = "Post was deleted"))
+)]
 async fn delete(auth: AuthResult, post_id: i64, client_version: DeleteBody) -> ApiResult<()> {
     let client = auth?;
     api::verify_privilege(client, config::privileges().post_delete)?;
@@ -1026,16 +1966,12 @@ async fn delete(auth: AuthResult, post_id: i64, client_version: DeleteBody) -> A
         .select(post_statistics::relation_count)
         .first(&mut db::get_connection()?)?;
 
-    // Post relation cascade deletion can cause deadlocks when deleting related posts in quick
-    // succession, so we lock an aysnchronous mutex when deleting if the post has any relations.
-    static ANTI_DEADLOCK_MUTEX: LazyLock<AsyncMutex<()>> = LazyLock::new(|| AsyncMutex::new(()));
     let _lock;
     if relation_count > 0 {
-        _lock = ANTI_DEADLOCK_MUTEX.lock().await;
+        _lock = POST_RELATION_MUTEX.lock().await;
     }
 
-    let mut conn = db::get_connection()?;
-    let mime_type = conn.transaction(|conn| {
+    let mime_type = db::deadlock_prone_transaction(&mut db::get_connection()?, |conn| {
         let (mime_type, post_version) = post::table
             .find(post_id)
             .select((post::mime_type, post::last_edit_time))
@@ -1043,7 +1979,7 @@ async fn delete(auth: AuthResult, post_id: i64, client_version: DeleteBody) -> A
         api::verify_version(post_version, *client_version)?;
 
         diesel::delete(post::table.find(post_id)).execute(conn)?;
-        Ok::<_, api::Error>(mime_type)
+        Ok(mime_type)
     })?;
     if config::get().delete_source_files {
         filesystem::delete_post(&PostHash::new(post_id), mime_type)?;
@@ -1051,6 +1987,13 @@ async fn delete(auth: AuthResult, post_id: i64, client_version: DeleteBody) -> A
     Ok(())
 }
 
+#[utoipa::path(
+    delete,
+    path = "/post/{id}/favorite",
+    summary = "Removes a post from favorites",
+    params(("id" = i64, Path), ResourceParams),
+    responses((status = 200, body = PostInfo))
+)]
 fn unfavorite(auth: AuthResult, post_id: i64, params: ResourceParams) -> ApiResult<PostInfo> {
     let client = auth?;
     api::verify_privilege(client, config::privileges().post_favorite)?;
@@ -1063,22 +2006,69 @@ fn unfavorite(auth: AuthResult, post_id: i64, params: ResourceParams) -> ApiResu
     conn.transaction(|conn| PostInfo::new_from_id(conn, client, post_id, &fields).map_err(api::Error::from))
 }
 
+#[utoipa::path(
+    delete,
+    path = "/posts/batch-favorite",
+    summary = "Removes several posts from favorites",
+    params(ResourceParams),
+    request_body = BatchFavoriteBody,
+    responses((status = 200, body = UnpagedResponse<PostInfo>))
+)]
+fn batch_unfavorite(
+    auth: AuthResult,
+    params: ResourceParams,
+    body: BatchFavoriteBody,
+) -> ApiResult<UnpagedResponse<PostInfo>> {
+    let client = auth?;
+    api::verify_privilege(client, config::privileges().post_favorite)?;
+
+    let fields = resource::create_table(params.fields()).map_err(Box::from)?;
+    let user_id = client.id.ok_or(api::Error::NotLoggedIn)?;
+
+    db::get_connection()?.transaction(|conn| {
+        let post_ids = body.post_ids(conn, client)?;
+        diesel::delete(post_favorite::table)
+            .filter(post_favorite::user_id.eq(user_id))
+            .filter(post_favorite::post_id.eq_any(&post_ids))
+            .execute(conn)?;
+        Ok(UnpagedResponse {
+            results: PostInfo::new_batch_from_ids(conn, client, post_ids, &fields)?,
+        })
+    })
+}
+
 #[cfg(test)]
 mod test {
-    use crate::api::ApiResult;
-    use crate::model::post::Post;
-    use crate::schema::{post, post_feature, post_statistics, tag, tag_name, user, user_statistics};
+    use crate::api::{self, ApiResult, ResourceParams};
+    use crate::auth::header::Client;
+    use crate::config;
+    use crate::content::hash::PostHash;
+    use crate::content::signature;
+    use crate::filesystem::{self, Directory};
+    use crate::model::enums::{MimeType, PostFlag, PostFlags, Rating, UserRank};
+    use crate::model::post::{NewPostSignature, Post, PostSignature};
+    use crate::resource;
+    use crate::resource::post::{Field, FieldSelection, PostInfo};
+    use crate::schema::{
+        post, post_feature, post_note, post_redirect, post_relation, post_signature, post_statistics, tag, tag_name,
+        user, user_statistics,
+    };
     use crate::search::post::Token;
     use crate::test::*;
     use crate::time::DateTime;
+    use crate::update;
     use diesel::dsl::exists;
     use diesel::prelude::*;
     use serial_test::{parallel, serial};
+    use std::collections::HashMap;
+    use std::task::Poll;
     use strum::IntoEnumIterator;
+    use warp::http::StatusCode;
+    use warp::hyper::body::Bytes;
 
     // Exclude fields that involve creation_time or last_edit_time
     const FIELDS: &str = "&fields=id,user,fileSize,canvasWidth,canvasHeight,safety,type,mimeType,checksum,checksumMd5,\
-    flags,source,description,contentUrl,thumbnailUrl,tags,relations,pools,notes,score,ownScore,ownFavorite,tagCount,commentCount,\
+    flags,source,description,contentUrl,thumbnail,tags,relations,pools,notes,score,ownScore,ownFavorite,tagCount,commentCount,\
     relationCount,noteCount,favoriteCount,featureCount,favoritedBy,hasCustomThumbnail";
 
     #[tokio::test]
@@ -1091,7 +2081,12 @@ mod test {
         // Test sorts
         for token in Token::iter() {
             match token {
-                Token::Id | Token::ContentChecksum | Token::NoteText | Token::Special => continue,
+                Token::Id
+                | Token::MimeType
+                | Token::ContentChecksum
+                | Token::CommentText
+                | Token::NoteText
+                | Token::Special => continue,
                 _ => (),
             };
             let token_str: &'static str = token.into();
@@ -1100,17 +2095,84 @@ mod test {
             verify_query(&query, &path).await?;
         }
 
+        verify_query(
+            &format!("{QUERY}=sort:last-comment-date {SORT}&fields=id"),
+            "post/list_last-comment-date_sorted.json",
+        )
+        .await?;
+        verify_query(&format!("{QUERY}=-sort:type {SORT}&fields=id"), "post/list_type_ascending_sorted.json").await?;
+
         // Test filters
         verify_query(&format!("{QUERY}=-plant,sky,tagme {SORT}&fields=id"), "post/list_tag_filtered.json").await?;
         verify_query(&format!("{QUERY}=-pool:2 {SORT}&fields=id"), "post/list_pool_filtered.json").await?;
+        verify_query(
+            &format!("{QUERY}=mime-type:jpeg,png,image/gif {SORT}&fields=id"),
+            "post/list_mime-type_filtered.json",
+        )
+        .await?;
+        verify_query(
+            &format!("{QUERY}=-mime-type:bmp,video/mp4 {SORT}&fields=id"),
+            "post/list_mime-type_negated_filtered.json",
+        )
+        .await?;
         verify_query(&format!("{QUERY}=fav:*user* {SORT}&fields=id"), "post/list_fav_filtered.json").await?;
+        verify_query(&format!("{QUERY}=-uploader:regular_user {SORT}&fields=id"), "post/list_uploader_filtered.json")
+            .await?;
+        verify_query(&format!("{QUERY}=uploader:none {SORT}&fields=id"), "post/list_anonymous_filtered.json").await?;
         verify_query(&format!("{QUERY}=-comment:*user* {SORT}&fields=id"), "post/list_comment_filtered.json").await?;
+        verify_query(&format!("{QUERY}=comment-text:*POST* {SORT}&fields=id"), "post/list_comment-text_filtered.json")
+            .await?;
+        verify_query(
+            &format!("{QUERY}=-comment-text:*lorem* {SORT}&fields=id"),
+            "post/list_comment-text_negated_filtered.json",
+        )
+        .await?;
         verify_query(&format!("{QUERY}=note-text:*fav* {SORT}&fields=id"), "post/list_note-text_filtered.json").await?;
+        verify_query(&format!("{QUERY}=file-size:1MB.. {SORT}&fields=id"), "post/list_file-size_filtered.json").await?;
+        verify_query(&format!("{QUERY}=file-size:..5MiB {SORT}&fields=id"), "post/list_file-size_binary_filtered.json")
+            .await?;
+        verify_query(&format!("{QUERY}=tag-count:0 {SORT}&fields=id"), "post/list_tag-count_filtered.json").await?;
+        verify_query(&format!("{QUERY}=source:none {SORT}&fields=id"), "post/list_source_none_filtered.json").await?;
+        verify_query(&format!("{QUERY}=-source:*WIKI* {SORT}&fields=id"), "post/list_source_negated_filtered.json")
+            .await?;
+        verify_query(&format!("{QUERY}=id:5,1,3 {SORT}&fields=id"), "post/list_id_filtered.json").await?;
+        verify_query(&format!("{QUERY}=aspect-ratio:..0.99 {SORT}&fields=id"), "post/list_portrait_filtered.json")
+            .await?;
+        verify_query(
+            &format!("{QUERY}=image-ratio:landscape {SORT}&fields=id"),
+            "post/list_image-ratio_landscape.json",
+        )
+        .await?;
+        verify_query(&format!("{QUERY}=image-ratio:portrait {SORT}&fields=id"), "post/list_image-ratio_portrait.json")
+            .await?;
+        verify_query(&format!("{QUERY}=image-ratio:16:9..2:1 {SORT}&fields=id"), "post/list_image-ratio_fraction.json")
+            .await?;
+        verify_query(
+            &format!("{QUERY}=width:1000..2000 height:1000..1500 {SORT}&fields=id"),
+            "post/list_dimensions_filtered.json",
+        )
+        .await?;
+        verify_query(
+            &format!("{QUERY}=note-count:0 tag-count:1.. {SORT}&fields=id"),
+            "post/list_note-count_filtered.json",
+        )
+        .await?;
         verify_query(&format!("{QUERY}=special:liked {SORT}&fields=id"), "post/list_liked_filtered.json").await?;
         verify_query(&format!("{QUERY}=special:disliked {SORT}&fields=id"), "post/list_disliked_filtered.json").await?;
         verify_query(&format!("{QUERY}=special:fav {SORT}&fields=id"), "post/list_special-fav_filtered.json").await?;
         verify_query(&format!("{QUERY}=special:tumbleweed {SORT}&fields=id"), "post/list_tumbleweed_filtered.json")
-            .await
+            .await?;
+
+        // Test safety restriction
+        const USER: &str = "restricted_user";
+        verify_query_with_user(USER, &format!("{QUERY}={SORT}&fields=id"), "post/list_restricted.json").await?;
+        verify_status_with_user(USER, "GET /post/5", StatusCode::NOT_FOUND).await;
+        verify_query_with_user(
+            USER,
+            &format!("{QUERY}=safety:sketchy,unsafe {SORT}&fields=id"),
+            "post/list_restricted_unsafe.json",
+        )
+        .await
     }
 
     #[tokio::test]
@@ -1128,19 +2190,324 @@ mod test {
         let last_edit_time = get_last_edit_time(&mut conn)?;
 
         verify_query(&format!("GET /post/{POST_ID}/?{FIELDS}"), "post/get.json").await?;
+        verify_query(&format!("GET /post/{POST_ID}/?fields=id,thumbnailUrl"), "post/get_thumbnail_url.json").await?;
 
         let new_last_edit_time = get_last_edit_time(&mut conn)?;
         assert_eq!(new_last_edit_time, last_edit_time);
         Ok(())
     }
 
+    #[tokio::test]
+    #[parallel]
+    async fn get_nested_fields() -> ApiResult<()> {
+        const NESTED_FIELDS: &str = "fields=id,user.name,tags.names,relations.id,favoritedBy.avatarUrl";
+        verify_query(&format!("GET /post/2/?{NESTED_FIELDS}"), "post/get_2_nested_fields.json").await?;
+        verify_query("GET /post/1/?fields=id,comments.text,comments.score", "post/get_1_comment_fields.json").await?;
+        verify_query("GET /posts/?query=-sort:id&limit=40&fields=id,featuredBy.name", "post/list_featured_by.json")
+            .await?;
+
+        let create_table = |fields| resource::create_table::<FieldSelection, _>(Some(fields));
+        assert!(create_table("tags.names,tags.category").is_ok());
+        assert!(create_table("tags.unknown").is_err());
+        assert!(create_table("comments.unknown").is_err());
+        assert!(create_table("id.unknown").is_err());
+        assert!(create_table("unknown.names").is_err());
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[parallel]
+    async fn get_similar_posts() -> ApiResult<()> {
+        // Test posts have no signatures, so no similar posts can be found
+        verify_query("GET /post/2/?fields=id,similarPosts.id", "post/get_2_similar_posts.json").await?;
+
+        let create_table = |fields| resource::create_table::<FieldSelection, _>(fields);
+        assert!(create_table(Some("similarPosts.thumbnailUrl")).is_ok());
+        assert!(!create_table(None).unwrap()[Field::SimilarPosts]);
+        Ok(())
+    }
+
     #[tokio::test]
     #[parallel]
     async fn get_neighbors() -> ApiResult<()> {
         const QUERY: &str = "around/?query=-sort:id";
         verify_query(&format!("GET /post/1/{QUERY}{FIELDS}"), "post/get_1_neighbors.json").await?;
         verify_query(&format!("GET /post/4/{QUERY}{FIELDS}"), "post/get_4_neighbors.json").await?;
-        verify_query(&format!("GET /post/5/{QUERY}{FIELDS}"), "post/get_5_neighbors.json").await
+        verify_query(&format!("GET /post/5/{QUERY}{FIELDS}"), "post/get_5_neighbors.json").await?;
+
+        // Post 5 is first when sorting by file size
+        const SORTED_QUERY: &str = "around/?query=sort:file-size -sort:id";
+        verify_query(&format!("GET /post/5/{SORTED_QUERY}{FIELDS}"), "post/get_5_file-size_neighbors.json").await?;
+
+        verify_status_with_user("administrator", "GET /post/99/around", StatusCode::NOT_FOUND).await;
+        Ok(())
+    }
+
+    #[test]
+    #[parallel]
+    fn anonymous_upload() {
+        let client = Client::new(Some(2), UserRank::Regular);
+        assert_eq!(super::uploader_id(client, false), Some(2));
+        assert_eq!(super::uploader_id(client, true), None);
+        assert_eq!(super::uploader_id(Client::new(None, UserRank::Anonymous), false), None);
+    }
+
+    #[tokio::test]
+    #[parallel]
+    async fn get_pool_neighbors() -> ApiResult<()> {
+        verify_query("GET /post/2/pool/2/neighbors/?fields=id", "post/get_2_pool_neighbors.json").await?;
+        verify_query("GET /post/1/pool/2/neighbors/?fields=id", "post/get_1_pool_neighbors.json").await?;
+        verify_status_with_user("administrator", "GET /post/3/pool/2/neighbors", StatusCode::NOT_FOUND).await;
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn get_by_checksum() -> ApiResult<()> {
+        const POST_ID: i64 = 2;
+        verify_query("GET /posts/by-checksum/3034/?fields=id", "post/get_by_checksum.json").await?;
+
+        // Give the post an MD5 checksum that differs from its full checksum
+        diesel::update(post::table.find(POST_ID))
+            .set(post::checksum_md5.eq(b"md5".as_slice()))
+            .execute(&mut get_connection()?)?;
+        verify_query("GET /posts/by-checksum/6d6435/?fields=id", "post/get_by_checksum_md5.json").await?;
+        Ok(reset_database())
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn get_exif() -> ApiResult<()> {
+        const POST_ID: i64 = 1;
+        let query = format!("GET /post/{POST_ID}/exif");
+
+        // Test posts have no content on disk, so give the post some
+        let post_hash = PostHash::new(POST_ID);
+        let content_path = post_hash.content_path(MimeType::Jpeg);
+        filesystem::create_dir(Directory::Posts)?;
+        std::fs::copy(image_path("starry_night.jpg"), &content_path)?;
+
+        let body = reply_body_with_user("regular_user", &query, StatusCode::OK).await;
+        let tags: HashMap<String, String> = serde_json::from_str(&body).unwrap();
+        assert_eq!(tags["PixelXDimension"], "480");
+        assert_eq!(tags["PixelYDimension"], "374");
+        assert!(tags["Software"].contains("Polarr Photo Editor"));
+
+        // Restricted users are below the post_view_exif rank of the test config
+        verify_status_with_user("restricted_user", &query, StatusCode::FORBIDDEN).await;
+
+        std::fs::copy(image_path("lisa.jpg"), &content_path)?;
+        verify_error(&query, "post/get_exif_missing.json", StatusCode::NOT_FOUND).await?;
+        Ok(filesystem::delete_content(&post_hash, MimeType::Jpeg)?)
+    }
+
+    #[tokio::test]
+    #[parallel]
+    async fn get_safety_breakdown() -> ApiResult<()> {
+        const QUERY: &str = "GET /posts/safety-breakdown";
+        verify_query(QUERY, "post/safety_breakdown.json").await?;
+        verify_query_with_user("restricted_user", QUERY, "post/safety_breakdown_restricted.json").await
+    }
+
+    #[tokio::test]
+    #[parallel]
+    async fn get_batch() -> ApiResult<()> {
+        verify_query("GET /posts/batch/?ids=4,99,2,4&fields=id", "post/get_batch.json").await?;
+        verify_status_with_user("administrator", "GET /posts/batch/?ids=1,two", StatusCode::BAD_REQUEST).await;
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[parallel]
+    async fn list_pools() -> ApiResult<()> {
+        verify_query("GET /post/2/pools/?limit=1&fields=id,names", "post/list_2_pools.json").await?;
+        verify_status_with_user("administrator", "GET /post/99/pools", StatusCode::NOT_FOUND).await;
+        Ok(())
+    }
+
+    #[test]
+    #[parallel]
+    fn find_similar_candidates() -> ApiResult<()> {
+        const POST_ID: i64 = 3;
+        let image = image::open(image_path("png.png"))?;
+        let post_signature = signature::compute(&image);
+
+        let mut conn = get_connection()?;
+        conn.test_transaction(|conn| -> QueryResult<()> {
+            let new_post_signature = NewPostSignature {
+                post_id: POST_ID,
+                signature: post_signature.into(),
+                words: signature::generate_indexes(&post_signature).into(),
+            };
+            diesel::insert_into(post_signature::table)
+                .values(new_post_signature)
+                .execute(conn)?;
+
+            let candidates =
+                PostSignature::find_similar_candidates(conn, &signature::generate_indexes(&post_signature))?;
+            assert!(candidates.iter().any(|candidate| candidate.post_id == POST_ID));
+            Ok(())
+        });
+        Ok(())
+    }
+
+    #[test]
+    #[parallel]
+    fn batch_order() -> ApiResult<()> {
+        const POST_IDS: [i64; 5] = [3, 1, 5, 2, 4];
+        let fields = resource::create_table::<FieldSelection, _>(Some("id,tagCount")).unwrap();
+        let client = Client::new(None, UserRank::Administrator);
+
+        let mut conn = get_connection()?;
+        let post_infos = PostInfo::new_batch_from_ids(&mut conn, client, POST_IDS.to_vec(), &fields)?;
+        assert_eq!(post_infos.len(), POST_IDS.len());
+        for (post_info, post_id) in post_infos.into_iter().zip(POST_IDS) {
+            let tag_count: i64 = post_statistics::table
+                .find(post_id)
+                .select(post_statistics::tag_count)
+                .first(&mut conn)?;
+            let post_info = serde_json::to_value(post_info).unwrap();
+            assert_eq!(post_info["id"], post_id);
+            assert_eq!(post_info["tagCount"], tag_count);
+        }
+        Ok(())
+    }
+
+    #[test]
+    #[parallel]
+    fn relation_symmetry() -> ApiResult<()> {
+        let has_relation = |conn: &mut PgConnection, parent_id: i64, child_id: i64| -> QueryResult<bool> {
+            diesel::select(exists(post_relation::table.find((parent_id, child_id)))).get_result(conn)
+        };
+
+        let mut conn = get_connection()?;
+        conn.test_transaction(|conn| -> QueryResult<()> {
+            update::post::create_relations(conn, 2, vec![5, 5, 2])?;
+            assert!(has_relation(conn, 2, 5)?);
+            assert!(has_relation(conn, 5, 2)?);
+            assert!(!has_relation(conn, 2, 2)?);
+
+            update::post::delete_relations(conn, 2)?;
+            assert!(!has_relation(conn, 2, 5)?);
+            assert!(!has_relation(conn, 5, 2)?);
+            Ok(())
+        });
+        Ok(())
+    }
+
+    #[test]
+    #[parallel]
+    fn similar_post_distances() -> ApiResult<()> {
+        // Post 99 doesn't exist and must not shift any of the other distances
+        let distances = HashMap::from([(4, 0.3), (2, 0.1), (99, 0.15), (1, 0.2)]);
+        let fields = resource::create_table::<FieldSelection, _>(Some("id")).unwrap();
+        let client = Client::new(None, UserRank::Administrator);
+
+        let mut conn = get_connection()?;
+        let similar_posts = super::similar_posts(&mut conn, client, &distances, &fields)?;
+        let post_distances: Vec<_> = similar_posts
+            .into_iter()
+            .map(|similar_post| {
+                let post_info = serde_json::to_value(similar_post.post).unwrap();
+                (post_info["id"].as_i64().unwrap(), similar_post.distance)
+            })
+            .collect();
+        assert_eq!(post_distances, [(2, 0.1), (1, 0.2), (4, 0.3)]);
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[parallel]
+    async fn reverse_search_direct() -> ApiResult<()> {
+        let client = Client::new(None, UserRank::Administrator);
+        let params = || ResourceParams {
+            query: None,
+            fields: Some(String::from("id")),
+            bump_login: None,
+        };
+        let content = Bytes::from(std::fs::read(image_path("jpeg.jpg"))?);
+        let body = || futures::stream::iter([Ok::<_, warp::Error>(content.clone())]);
+
+        let content_type = || Some(String::from("image/jpeg"));
+        let response = super::reverse_search_direct(Ok(client), params(), content_type(), body()).await?;
+        assert!(response.exact_post.is_none());
+        assert!(response.similar_posts.is_empty());
+
+        let response = super::reverse_search_direct(Ok(client), params(), None, body()).await;
+        assert!(matches!(response, Err(api::Error::MissingContentType)));
+
+        // Clients without the privilege are rejected before the body is received
+        let unread_body = futures::stream::poll_fn(|_| -> Poll<Option<Result<Bytes, warp::Error>>> {
+            panic!("Body of unprivileged request was read")
+        });
+        let restricted_user = Client::new(Some(1), UserRank::Restricted);
+        let response = super::reverse_search_direct(Ok(restricted_user), params(), content_type(), unread_body).await;
+        assert!(matches!(response, Err(api::Error::InsufficientPrivileges)));
+
+        let oversized_length = config::body_limits().reverse_search + 1;
+        let reply = warp::test::request()
+            .method("POST")
+            .path("/posts/reverse-search-direct")
+            .header("content-length", oversized_length)
+            .reply(&api::routes())
+            .await;
+        assert_eq!(reply.status(), StatusCode::PAYLOAD_TOO_LARGE);
+        Ok(())
+    }
+
+    #[test]
+    #[parallel]
+    fn find_similar_posts() -> ApiResult<()> {
+        let image = image::open(image_path("png.png"))?;
+        let post_signature = signature::compute(&image);
+        let fields = resource::create_table::<FieldSelection, _>(Some("similarPosts.id")).unwrap();
+        let client = Client::new(None, UserRank::Administrator);
+
+        let mut conn = get_connection()?;
+        conn.test_transaction(|conn| -> QueryResult<()> {
+            let new_post_signatures: Vec<_> = [1, 2, 5]
+                .map(|post_id| NewPostSignature {
+                    post_id,
+                    signature: post_signature.into(),
+                    words: signature::generate_indexes(&post_signature).into(),
+                })
+                .into();
+            diesel::insert_into(post_signature::table)
+                .values(new_post_signatures)
+                .execute(conn)?;
+
+            let similar_post_ids = |post_info: PostInfo| -> Vec<i64> {
+                serde_json::to_value(post_info).unwrap()["similarPosts"]
+                    .as_array()
+                    .unwrap()
+                    .iter()
+                    .map(|similar_post| similar_post["post"]["id"].as_i64().unwrap())
+                    .collect()
+            };
+            assert_eq!(similar_post_ids(PostInfo::new_from_id(conn, client, 1, &fields)?), [2, 5]);
+
+            // Similar posts are only searched for images and animations
+            assert!(similar_post_ids(PostInfo::new_from_id(conn, client, 5, &fields)?).is_empty());
+
+            // Similar posts of a batch are found together
+            let batch = PostInfo::new_batch_from_ids(conn, client, vec![2, 1, 5], &fields)?;
+            let batch_similar_post_ids: Vec<_> = batch.into_iter().map(similar_post_ids).collect();
+            assert_eq!(batch_similar_post_ids, [vec![1, 5], vec![2, 5], vec![]]);
+            Ok(())
+        });
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[parallel]
+    async fn get_context() -> ApiResult<()> {
+        verify_query("GET /post/3/context/?radius=2", "post/get_3_context.json").await?;
+
+        const SORTED_QUERY: &str = "context/?radius=2&query=sort:file-size -sort:id";
+        verify_query(&format!("GET /post/3/{SORTED_QUERY}"), "post/get_3_file-size_context.json").await?;
+
+        // Score ties are broken by id, giving an order of 3, 2, 1, 5, 4
+        verify_query("GET /post/1/context/?radius=1&query=sort:score", "post/get_1_score_context.json").await
     }
 
     #[tokio::test]
@@ -1219,6 +2586,107 @@ mod test {
         Ok(reset_database())
     }
 
+    #[tokio::test]
+    #[serial]
+    async fn merge_concurrently() -> ApiResult<()> {
+        const MERGE_TO_ID: i64 = 1;
+        let (merge_result, other_merge_result) = tokio::join!(
+            verify_status_with_body("POST /post-merge/?fields=id", "post/merge_2_into_1.json", StatusCode::OK),
+            verify_status_with_body("POST /post-merge/?fields=id", "post/merge_3_into_1.json", StatusCode::OK),
+        );
+        merge_result?;
+        other_merge_result?;
+
+        let mut conn = get_connection()?;
+        let remaining_posts: i64 = post::table.filter(post::id.eq_any([2, 3])).count().first(&mut conn)?;
+        assert_eq!(remaining_posts, 0);
+
+        let relation_count: i64 = post_statistics::table
+            .find(MERGE_TO_ID)
+            .select(post_statistics::relation_count)
+            .first(&mut conn)?;
+        assert_eq!(relation_count, 0);
+        Ok(reset_database())
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn merge_redirect() -> ApiResult<()> {
+        const QUERY: &str = "POST /post-merge/?fields=id";
+        const USER: &str = "regular_user";
+        verify_status_with_body(QUERY, "post/merge_2_into_1.json", StatusCode::OK).await?;
+        verify_status_with_user(USER, "GET /post/2", StatusCode::MOVED_PERMANENTLY).await;
+
+        // Redirects follow the post through further merges
+        verify_status_with_body(QUERY, "post/merge_1_into_3.json", StatusCode::OK).await?;
+        verify_status_with_user(USER, "GET /post/1", StatusCode::MOVED_PERMANENTLY).await;
+        verify_redirect_with_user(USER, "GET /post/1/?fields=id,safety", "/api/post/3?fields=id%2Csafety").await;
+        verify_redirect_with_user(
+            USER,
+            "GET /post/2/around/?query=-sort:id&fields=id",
+            "/api/post/3/around?query=-sort%3Aid&fields=id",
+        )
+        .await;
+        verify_status_with_user(USER, "GET /post/3", StatusCode::OK).await;
+
+        let mut conn = get_connection()?;
+        let targets: Vec<i64> = post_redirect::table
+            .select(post_redirect::target_id)
+            .filter(post_redirect::removed_id.eq_any([1, 2]))
+            .load(&mut conn)?;
+        assert_eq!(targets, [3, 3]);
+
+        // Redirects are dropped along with the post they point to
+        diesel::delete(post::table.find(3)).execute(&mut conn)?;
+        verify_status_with_user(USER, "GET /post/1", StatusCode::NOT_FOUND).await;
+        Ok(reset_database())
+    }
+
+    #[test]
+    #[parallel]
+    fn restricted_safety() -> ApiResult<()> {
+        // Favoriting and rating require at least the rank needed to see every safety with the test config,
+        // so posts above the cap are checked through the lookup those handlers share
+        let mut conn = get_connection()?;
+        let restricted_user = Client::new(Some(1), UserRank::Restricted);
+        super::verify_visible(&mut conn, restricted_user, 1)?;
+        assert!(matches!(super::verify_visible(&mut conn, restricted_user, 5), Err(api::Error::NotFound(_))));
+        super::verify_visible(&mut conn, Client::new(Some(2), UserRank::Regular), 5)
+    }
+
+    #[test]
+    #[parallel]
+    fn self_favorite() -> ApiResult<()> {
+        const USER_ID: i64 = 2;
+        let mut conn = get_connection()?;
+        assert!(super::verify_not_self_favorite(&mut conn, USER_ID, &[1, 2], false).is_ok());
+        assert!(super::verify_not_self_favorite(&mut conn, USER_ID, &[1, 5], true).is_ok());
+        assert!(matches!(
+            super::verify_not_self_favorite(&mut conn, USER_ID, &[1, 2], true),
+            Err(api::Error::SelfFavorite)
+        ));
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn batch_favorite() -> ApiResult<()> {
+        const QUERY: &str = "posts/batch-favorite/?fields=id,ownFavorite,favoriteCount";
+        verify_query(&format!("POST /{QUERY}"), "post/batch_favorite.json").await?;
+        verify_query(&format!("DELETE /{QUERY}"), "post/batch_unfavorite.json").await?;
+        verify_status_with_body(&format!("POST /{QUERY}"), "post/batch_favorite_missing.json", StatusCode::NOT_FOUND)
+            .await?;
+
+        let mut conn = get_connection()?;
+        let admin_favorite_count: i64 = user::table
+            .inner_join(user_statistics::table)
+            .select(user_statistics::favorite_count)
+            .filter(user::name.eq("administrator"))
+            .first(&mut conn)?;
+        assert_eq!(admin_favorite_count, 0);
+        Ok(reset_database())
+    }
+
     #[tokio::test]
     #[serial]
     async fn favorite() -> ApiResult<()> {
@@ -1256,6 +2724,141 @@ mod test {
         Ok(())
     }
 
+    #[tokio::test]
+    #[serial]
+    async fn concurrent_favorite() -> ApiResult<()> {
+        const POST_ID: i64 = 3;
+        let get_favorite_count = |conn: &mut PgConnection| -> QueryResult<i64> {
+            post_statistics::table
+                .find(POST_ID)
+                .select(post_statistics::favorite_count)
+                .first(conn)
+        };
+
+        let mut conn = get_connection()?;
+        let favorite_count = get_favorite_count(&mut conn)?;
+
+        let query = format!("POST /post/{POST_ID}/favorite");
+        tokio::join!(
+            verify_status_with_user("administrator", &query, StatusCode::OK),
+            verify_status_with_user("administrator", &query, StatusCode::OK),
+        );
+        assert_eq!(get_favorite_count(&mut conn)?, favorite_count + 1);
+
+        verify_status_with_user("administrator", &format!("DELETE /post/{POST_ID}/favorite"), StatusCode::OK).await;
+        assert_eq!(get_favorite_count(&mut conn)?, favorite_count);
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn view() -> ApiResult<()> {
+        const QUERY: &str = "GET /posts/?query=view-count:1.. sort:view-count -sort:id&limit=40&fields=id,viewCount";
+        verify_status_with_user("regular_user", "POST /post/2/view", StatusCode::OK).await;
+        verify_status_with_user("regular_user", "POST /post/4/view", StatusCode::OK).await;
+        verify_status_with_user("restricted_user", "POST /post/4/view", StatusCode::OK).await;
+        verify_status_with_user("regular_user", "POST /post/99/view", StatusCode::NOT_FOUND).await;
+
+        // Views are only counted once they are flushed
+        let mut conn = get_connection()?;
+        let view_count: i64 = post_statistics::table
+            .find(4)
+            .select(post_statistics::view_count)
+            .first(&mut conn)?;
+        assert_eq!(view_count, 0);
+
+        update::post::flush_views(&mut conn)?;
+        verify_query(QUERY, "post/list_viewed.json").await?;
+        Ok(reset_database())
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn lock() -> ApiResult<()> {
+        const POST_ID: i64 = 2;
+        const QUERY: &str = "/?fields=id,flags,locked";
+        verify_status_with_user("regular_user", &format!("POST /post/{POST_ID}/lock"), StatusCode::FORBIDDEN).await;
+        verify_query_with_user("moderator", &format!("POST /post/{POST_ID}/lock{QUERY}"), "post/lock.json").await?;
+
+        // Locked posts can only be edited by users with the lock privilege
+        let update_body = || -> super::UpdateBody {
+            serde_json::from_str(r#"{"version": "2025-01-17T06:25:08.461692853Z", "source": "locked"}"#).unwrap()
+        };
+        let params = || ResourceParams {
+            query: None,
+            fields: Some(String::from("id")),
+            bump_login: None,
+        };
+        let regular_user = Client::new(Some(2), UserRank::Regular);
+        let result = super::update(Ok(regular_user), POST_ID, params(), update_body()).await;
+        assert!(matches!(result, Err(api::Error::PostLocked)));
+
+        let moderator = Client::new(Some(4), UserRank::Moderator);
+        super::update(Ok(moderator), POST_ID, params(), update_body()).await?;
+
+        verify_query_with_user("moderator", &format!("POST /post/{POST_ID}/unlock{QUERY}"), "post/unlock.json").await?;
+        super::update(Ok(regular_user), POST_ID, params(), update_body()).await?;
+        Ok(reset_database())
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn hidden() -> ApiResult<()> {
+        const POST_ID: i64 = 3;
+        const LIST_QUERY: &str = "GET /posts/?query=id:2,3,4&fields=id";
+
+        let mut conn = get_connection()?;
+        let flags: PostFlags = post::table.find(POST_ID).select(post::flags).first(&mut conn)?;
+        diesel::update(post::table.find(POST_ID))
+            .set(post::flags.eq(flags | PostFlag::Hidden))
+            .execute(&mut conn)?;
+
+        // Privileged users can still see hidden posts
+        verify_query_with_user("moderator", &format!("GET /post/{POST_ID}/?fields=id,flags"), "post/get_hidden.json")
+            .await?;
+        verify_query_with_user("moderator", LIST_QUERY, "post/list_hidden_visible.json").await?;
+
+        // Hidden posts behave as if they don't exist for everyone else
+        verify_status_with_user("regular_user", &format!("GET /post/{POST_ID}"), StatusCode::NOT_FOUND).await;
+        verify_status_with_user("regular_user", &format!("GET /post/{POST_ID}/exif"), StatusCode::NOT_FOUND).await;
+        verify_query_with_user("regular_user", LIST_QUERY, "post/list_hidden.json").await?;
+        verify_query_with_user(
+            "regular_user",
+            "GET /post/1/?fields=id,relations.id",
+            "post/get_1_hidden_relations.json",
+        )
+        .await?;
+        verify_query_with_user("regular_user", "GET /post/4/around/?fields=id", "post/get_4_hidden_neighbors.json")
+            .await?;
+        verify_status_with_user("regular_user", &format!("GET /post/{POST_ID}/around"), StatusCode::NOT_FOUND).await;
+        verify_status_with_user(
+            "regular_user",
+            &format!("GET /post/{POST_ID}/context?radius=1"),
+            StatusCode::NOT_FOUND,
+        )
+        .await;
+        verify_status_with_user("regular_user", &format!("GET /post/{POST_ID}/notes"), StatusCode::NOT_FOUND).await;
+        verify_status_with_user("regular_user", &format!("POST /post/{POST_ID}/favorite"), StatusCode::NOT_FOUND).await;
+
+        let params = || ResourceParams {
+            query: None,
+            fields: Some(String::from("id")),
+            bump_login: None,
+        };
+        let regular_user = Client::new(Some(2), UserRank::Regular);
+        let rating = api::RatingBody { score: Rating::Like };
+        let result = super::rate(Ok(regular_user), POST_ID, params(), rating);
+        assert!(matches!(result, Err(api::Error::NotFound(_))));
+        let batch = super::BatchFavoriteBody { ids: vec![1, POST_ID] };
+        let result = super::batch_favorite(Ok(regular_user), params(), batch);
+        assert!(matches!(result, Err(api::Error::NotFound(_))));
+
+        diesel::update(post::table.find(POST_ID))
+            .set(post::flags.eq(flags))
+            .execute(&mut conn)?;
+        Ok(())
+    }
+
     #[tokio::test]
     #[serial]
     async fn rate() -> ApiResult<()> {
@@ -1291,6 +2894,12 @@ mod test {
         Ok(())
     }
 
+    #[tokio::test]
+    #[serial]
+    async fn update_invalid_tags() -> ApiResult<()> {
+        verify_error("PUT /post/5", "post/update_invalid_tags.json", StatusCode::BAD_REQUEST).await
+    }
+
     #[tokio::test]
     #[serial]
     async fn update() -> ApiResult<()> {
@@ -1354,4 +2963,26 @@ mod test {
         assert_eq!(new_relation_count, relation_count);
         Ok(())
     }
+
+    #[tokio::test]
+    #[serial]
+    async fn update_notes() -> ApiResult<()> {
+        const POST_ID: i64 = 3;
+        const NOTE_ID: i64 = 1;
+        let query = format!("PUT /post/{POST_ID}/?fields=id");
+        verify_status_with_body(&query, "post/update_notes_missing.json", StatusCode::NOT_FOUND).await?;
+        verify_status_with_body(&query, "post/update_notes.json", StatusCode::OK).await?;
+
+        // Submitted note ids are kept, while notes without an id are created
+        let mut conn = get_connection()?;
+        let notes: Vec<(i64, String)> = post_note::table
+            .select((post_note::id, post_note::text))
+            .filter(post_note::post_id.eq(POST_ID))
+            .order_by(post_note::id)
+            .load(&mut conn)?;
+        assert_eq!(notes.len(), 2);
+        assert_eq!(notes[0], (NOTE_ID, String::from("Edited note")));
+        assert_eq!(notes[1].1, "New note");
+        Ok(reset_database())
+    }
 }
@@ -1,17 +1,22 @@
-use crate::api::{ApiResult, AuthResult, DeleteBody, MergeBody, PageParams, PagedResponse, ResourceParams};
+use crate::api::{
+    ApiResult, AuthResult, DeleteBody, MergeBody, PageParams, PagedResponse, ResourceParams, UnpagedResponse,
+};
 use crate::model::enums::ResourceType;
 use crate::model::post::PostTag;
 use crate::model::tag::{NewTag, TagImplication, TagSuggestion};
 use crate::resource::tag::TagInfo;
-use crate::schema::{post_tag, tag, tag_category, tag_implication, tag_name, tag_suggestion};
+use crate::schema::{post_tag, tag, tag_category, tag_implication, tag_name, tag_statistics, tag_suggestion};
 use crate::search::tag::QueryBuilder;
 use crate::string::SmallString;
 use crate::time::DateTime;
 use crate::{api, config, db, resource, update};
-use diesel::dsl::{count_star, max};
+use diesel::dsl::{count_star, max, sql};
+use diesel::pg::sql_types::Citext;
 use diesel::prelude::*;
+use diesel::sql_types::{Integer, Text};
 use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
+use utoipa::ToSchema;
 use warp::{Filter, Rejection, Reply};
 
 pub fn routes() -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
@@ -21,6 +26,12 @@ pub fn routes() -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone
         .and(warp::query())
         .map(list)
         .map(api::Reply::from);
+    let suggest = warp::get()
+        .and(api::auth())
+        .and(warp::path!("tags" / "suggest"))
+        .and(warp::query())
+        .map(suggest)
+        .map(api::Reply::from);
     let get = warp::get()
         .and(api::auth())
         .and(warp::path!("tag" / String))
@@ -37,61 +48,154 @@ pub fn routes() -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone
         .and(api::auth())
         .and(warp::path!("tags"))
         .and(api::resource_query())
-        .and(warp::body::json())
+        .and(api::json_body())
         .map(create)
         .map(api::Reply::from);
     let merge = warp::post()
         .and(api::auth())
         .and(warp::path!("tag-merge"))
         .and(api::resource_query())
-        .and(warp::body::json())
+        .and(api::json_body())
         .map(merge)
         .map(api::Reply::from);
     let update = warp::put()
         .and(api::auth())
         .and(warp::path!("tag" / String))
         .and(api::resource_query())
-        .and(warp::body::json())
+        .and(api::json_body())
         .map(update)
         .map(api::Reply::from);
+    let add_implication = warp::post()
+        .and(api::auth())
+        .and(warp::path!("tag" / String / "implications"))
+        .and(api::resource_query())
+        .and(api::json_body())
+        .map(add_implication)
+        .map(api::Reply::from);
+    let remove_implication = warp::delete()
+        .and(api::auth())
+        .and(warp::path!("tag" / String / "implications"))
+        .and(api::resource_query())
+        .and(api::json_body())
+        .map(remove_implication)
+        .map(api::Reply::from);
+    let add_suggestion = warp::post()
+        .and(api::auth())
+        .and(warp::path!("tag" / String / "suggestions"))
+        .and(api::resource_query())
+        .and(api::json_body())
+        .map(add_suggestion)
+        .map(api::Reply::from);
+    let remove_suggestion = warp::delete()
+        .and(api::auth())
+        .and(warp::path!("tag" / String / "suggestions"))
+        .and(api::resource_query())
+        .and(api::json_body())
+        .map(remove_suggestion)
+        .map(api::Reply::from);
     let delete = warp::delete()
         .and(api::auth())
         .and(warp::path!("tag" / String))
-        .and(warp::body::json())
+        .and(api::json_body())
         .map(delete)
         .map(api::Reply::from);
 
-    list.or(get).or(get_siblings).or(create).or(merge).or(update).or(delete)
+    list.or(suggest)
+        .or(get)
+        .or(get_siblings)
+        .or(create)
+        .or(merge)
+        .or(update)
+        .or(add_implication)
+        .or(remove_implication)
+        .or(add_suggestion)
+        .or(remove_suggestion)
+        .or(delete)
 }
 
 const MAX_TAGS_PER_PAGE: i64 = 1000;
 const MAX_TAG_SIBLINGS: i64 = 1000;
-
+const MAX_TAG_SUGGESTIONS: i64 = 30;
+
+#[utoipa::path(
+    get,
+    path = "/tags",
+    summary = "Lists tags",
+    params(PageParams, ResourceParams),
+    responses((status = 200, body = PagedResponse<TagInfo>))
+)]
 fn list(auth: AuthResult, params: PageParams) -> ApiResult<PagedResponse<TagInfo>> {
     let client = auth?;
     params.bump_login(client)?;
     api::verify_privilege(client, config::privileges().tag_list)?;
 
     let offset = params.offset.unwrap_or(0);
-    let limit = std::cmp::min(params.limit.get(), MAX_TAGS_PER_PAGE);
+    let limit = params.limit(MAX_TAGS_PER_PAGE, MAX_TAGS_PER_PAGE);
     let fields = resource::create_table(params.fields()).map_err(Box::from)?;
 
     db::get_connection()?.transaction(|conn| {
         let mut query_builder = QueryBuilder::new(params.criteria())?;
-        query_builder.set_offset_and_limit(offset, limit);
-
-        let total = query_builder.count(conn)?;
-        let selected_tags = query_builder.load(conn)?;
+        let page = api::load_page(conn, &mut query_builder, offset, limit)?;
         Ok(PagedResponse {
             query: params.into_query(),
             offset,
             limit,
-            total,
-            results: TagInfo::new_batch_from_ids(conn, selected_tags, &fields)?,
+            total: page.total,
+            has_more: page.has_more,
+            results: TagInfo::new_batch_from_ids(conn, page.ids, &fields)?,
+        })
+    })
+}
+
+#[utoipa::path(
+    get,
+    path = "/tags/suggest",
+    summary = "Suggests tags matching a partial name",
+    params(PageParams, ResourceParams),
+    responses((status = 200, body = UnpagedResponse<TagInfo>))
+)]
+fn suggest(auth: AuthResult, params: PageParams) -> ApiResult<UnpagedResponse<TagInfo>> {
+    let client = auth?;
+    params.bump_login(client)?;
+    api::verify_privilege(client, config::privileges().tag_list)?;
+
+    let limit = params.limit(MAX_TAG_SUGGESTIONS, MAX_TAG_SUGGESTIONS);
+    let fields = resource::create_table(params.fields()).map_err(Box::from)?;
+    let name = params.criteria();
+    let escaped_name = name.replace('\\', "\\\\").replace('%', "\\%").replace('_', "\\_");
+
+    // Tags named exactly like the query come first, then tags with a name starting with it,
+    // then tags with a name containing it. A tag is ranked by whichever of its names matches best.
+    let tier = sql::<Integer>("(SELECT MIN(CASE WHEN tag_name.name = ")
+        .bind::<Citext, _>(name)
+        .sql(" THEN 0 WHEN tag_name.name LIKE ")
+        .bind::<Text, _>(format!("{escaped_name}%"))
+        .sql(" THEN 1 ELSE 2 END) FROM tag_name WHERE tag_name.tag_id = tag_statistics.tag_id)");
+
+    db::get_connection()?.transaction(|conn| {
+        let matching_tags = tag_name::table
+            .select(tag_name::tag_id)
+            .filter(tag_name::name.like(format!("%{escaped_name}%")))
+            .into_boxed();
+        let tag_ids = tag_statistics::table
+            .select(tag_statistics::tag_id)
+            .filter(tag_statistics::tag_id.eq_any(matching_tags))
+            .order_by((tier.asc(), tag_statistics::usage_count.desc(), tag_statistics::tag_id))
+            .limit(limit)
+            .load(conn)?;
+        Ok(UnpagedResponse {
+            results: TagInfo::new_batch_from_ids(conn, tag_ids, &fields)?,
         })
     })
 }
 
+#[utoipa::path(
+    get,
+    path = "/tag/{name}",
+    summary = "Gets a tag",
+    params(("name" = String, Path), ResourceParams),
+    responses((status = 200, body = TagInfo))
+)]
 fn get(auth: AuthResult, name: String, params: ResourceParams) -> ApiResult<TagInfo> {
     let client = auth?;
     params.bump_login(client)?;
@@ -110,17 +214,24 @@ fn get(auth: AuthResult, name: String, params: ResourceParams) -> ApiResult<TagI
     })
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 struct TagSibling {
     tag: TagInfo,
     occurrences: i64,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 struct TagSiblings {
     results: Vec<TagSibling>,
 }
 
+#[utoipa::path(
+    get,
+    path = "/tag-siblings/{name}",
+    summary = "Gets the siblings of a tag",
+    params(("name" = String, Path), ResourceParams),
+    responses((status = 200, body = TagSiblings))
+)]
 fn get_siblings(auth: AuthResult, name: String, params: ResourceParams) -> ApiResult<TagSiblings> {
     let client = auth?;
     params.bump_login(client)?;
@@ -158,8 +269,9 @@ fn get_siblings(auth: AuthResult, name: String, params: ResourceParams) -> ApiRe
     })
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 #[serde(deny_unknown_fields)]
+#[schema(as = TagCreateBody)]
 struct CreateBody {
     category: String,
     description: Option<String>,
@@ -168,6 +280,14 @@ struct CreateBody {
     suggestions: Option<Vec<SmallString>>,
 }
 
+#[utoipa::path(
+    post,
+    path = "/tags",
+    summary = "Creates a tag",
+    params(ResourceParams),
+    request_body = CreateBody,
+    responses((status = 200, body = TagInfo))
+)]
 fn create(auth: AuthResult, params: ResourceParams, body: CreateBody) -> ApiResult<TagInfo> {
     let client = auth?;
     params.bump_login(client)?;
@@ -195,11 +315,11 @@ fn create(auth: AuthResult, params: ResourceParams, body: CreateBody) -> ApiResu
 
         update::tag::add_names(conn, tag_id, 0, body.names)?;
         if let Some(implications) = body.implications {
-            let implied_ids = update::tag::get_or_create_tag_ids(conn, client, &implications, true)?;
+            let implied_ids = update::tag::get_or_create_tag_ids(conn, client, &implications, "implications", true)?;
             update::tag::add_implications(conn, tag_id, implied_ids)?;
         }
         if let Some(suggestions) = body.suggestions {
-            let suggested_ids = update::tag::get_or_create_tag_ids(conn, client, &suggestions, true)?;
+            let suggested_ids = update::tag::get_or_create_tag_ids(conn, client, &suggestions, "suggestions", true)?;
             update::tag::add_suggestions(conn, tag_id, suggested_ids)?;
         }
         Ok::<_, api::Error>(tag_id)
@@ -207,6 +327,14 @@ fn create(auth: AuthResult, params: ResourceParams, body: CreateBody) -> ApiResu
     conn.transaction(|conn| TagInfo::new_from_id(conn, tag_id, &fields).map_err(api::Error::from))
 }
 
+#[utoipa::path(
+    post,
+    path = "/tag-merge",
+    summary = "Merges two tags",
+    params(ResourceParams),
+    request_body = MergeBody<String>,
+    responses((status = 200, body = TagInfo))
+)]
 fn merge(auth: AuthResult, params: ResourceParams, body: MergeBody<String>) -> ApiResult<TagInfo> {
     let client = auth?;
     params.bump_login(client)?;
@@ -324,8 +452,9 @@ fn merge(auth: AuthResult, params: ResourceParams, body: MergeBody<String>) -> A
     conn.transaction(|conn| TagInfo::new_from_id(conn, merged_tag_id, &fields).map_err(api::Error::from))
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 #[serde(deny_unknown_fields)]
+#[schema(as = TagUpdateBody)]
 struct UpdateBody {
     version: DateTime,
     category: Option<SmallString>,
@@ -335,6 +464,14 @@ struct UpdateBody {
     suggestions: Option<Vec<SmallString>>,
 }
 
+#[utoipa::path(
+    put,
+    path = "/tag/{name}",
+    summary = "Updates a tag",
+    params(("name" = String, Path), ResourceParams),
+    request_body = UpdateBody,
+    responses((status = 200, body = TagInfo))
+)]
 fn update(auth: AuthResult, name: String, params: ResourceParams, body: UpdateBody) -> ApiResult<TagInfo> {
     let client = auth?;
     params.bump_login(client)?;
@@ -380,7 +517,7 @@ fn update(auth: AuthResult, name: String, params: ResourceParams, body: UpdateBo
         if let Some(implications) = body.implications {
             api::verify_privilege(client, config::privileges().tag_edit_implication)?;
 
-            let implied_ids = update::tag::get_or_create_tag_ids(conn, client, &implications, true)?;
+            let implied_ids = update::tag::get_or_create_tag_ids(conn, client, &implications, "implications", true)?;
             diesel::delete(tag_implication::table)
                 .filter(tag_implication::parent_id.eq(tag_id))
                 .execute(conn)?;
@@ -389,7 +526,7 @@ fn update(auth: AuthResult, name: String, params: ResourceParams, body: UpdateBo
         if let Some(suggestions) = body.suggestions {
             api::verify_privilege(client, config::privileges().tag_edit_suggestion)?;
 
-            let suggested_ids = update::tag::get_or_create_tag_ids(conn, client, &suggestions, true)?;
+            let suggested_ids = update::tag::get_or_create_tag_ids(conn, client, &suggestions, "suggestions", true)?;
             diesel::delete(tag_suggestion::table)
                 .filter(tag_suggestion::parent_id.eq(tag_id))
                 .execute(conn)?;
@@ -400,6 +537,150 @@ fn update(auth: AuthResult, name: String, params: ResourceParams, body: UpdateBo
     conn.transaction(|conn| TagInfo::new_from_id(conn, tag_id, &fields).map_err(api::Error::from))
 }
 
+#[derive(Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+#[schema(as = TagRelationBody)]
+struct RelationBody {
+    version: DateTime,
+    /// Name of the implied or suggested tag.
+    tag: SmallString,
+}
+
+#[derive(Clone, Copy)]
+enum Relation {
+    Implication,
+    Suggestion,
+}
+
+#[utoipa::path(
+    post,
+    path = "/tag/{name}/implications",
+    summary = "Adds an implication to a tag",
+    params(("name" = String, Path), ResourceParams),
+    request_body = RelationBody,
+    responses((status = 200, body = TagInfo))
+)]
+fn add_implication(auth: AuthResult, name: String, params: ResourceParams, body: RelationBody) -> ApiResult<TagInfo> {
+    edit_relation(auth, name, params, body, Relation::Implication, true)
+}
+
+#[utoipa::path(
+    delete,
+    path = "/tag/{name}/implications",
+    summary = "Removes an implication from a tag",
+    params(("name" = String, Path), ResourceParams),
+    request_body = RelationBody,
+    responses((status = 200, body = TagInfo))
+)]
+fn remove_implication(
+    auth: AuthResult,
+    name: String,
+    params: ResourceParams,
+    body: RelationBody,
+) -> ApiResult<TagInfo> {
+    edit_relation(auth, name, params, body, Relation::Implication, false)
+}
+
+#[utoipa::path(
+    post,
+    path = "/tag/{name}/suggestions",
+    summary = "Adds a suggestion to a tag",
+    params(("name" = String, Path), ResourceParams),
+    request_body = RelationBody,
+    responses((status = 200, body = TagInfo))
+)]
+fn add_suggestion(auth: AuthResult, name: String, params: ResourceParams, body: RelationBody) -> ApiResult<TagInfo> {
+    edit_relation(auth, name, params, body, Relation::Suggestion, true)
+}
+
+#[utoipa::path(
+    delete,
+    path = "/tag/{name}/suggestions",
+    summary = "Removes a suggestion from a tag",
+    params(("name" = String, Path), ResourceParams),
+    request_body = RelationBody,
+    responses((status = 200, body = TagInfo))
+)]
+fn remove_suggestion(auth: AuthResult, name: String, params: ResourceParams, body: RelationBody) -> ApiResult<TagInfo> {
+    edit_relation(auth, name, params, body, Relation::Suggestion, false)
+}
+
+/// Adds or removes a single implication or suggestion between the tag called `name`
+/// and the tag named in `body`. Adding a relationship that already exists does nothing.
+fn edit_relation(
+    auth: AuthResult,
+    name: String,
+    params: ResourceParams,
+    body: RelationBody,
+    relation: Relation,
+    add: bool,
+) -> ApiResult<TagInfo> {
+    let client = auth?;
+    params.bump_login(client)?;
+    let required_rank = match relation {
+        Relation::Implication => config::privileges().tag_edit_implication,
+        Relation::Suggestion => config::privileges().tag_edit_suggestion,
+    };
+    api::verify_privilege(client, required_rank)?;
+
+    let fields = resource::create_table(params.fields()).map_err(Box::from)?;
+    let name = percent_encoding::percent_decode_str(&name).decode_utf8()?;
+    let mut conn = db::get_connection()?;
+    let tag_id = conn.transaction(|conn| {
+        let (tag_id, tag_version) = tag::table
+            .select((tag::id, tag::last_edit_time))
+            .inner_join(tag_name::table)
+            .filter(tag_name::name.eq(name))
+            .first(conn)?;
+        api::verify_version(tag_version, body.version)?;
+
+        let related_id: i64 = tag_name::table
+            .select(tag_name::tag_id)
+            .filter(tag_name::name.eq(body.tag))
+            .first(conn)?;
+        match (relation, add) {
+            (Relation::Implication, true) => {
+                update::tag::check_implication_cycle(conn, tag_id, related_id)?;
+                diesel::insert_into(tag_implication::table)
+                    .values(TagImplication {
+                        parent_id: tag_id,
+                        child_id: related_id,
+                    })
+                    .on_conflict_do_nothing()
+                    .execute(conn)?;
+            }
+            (Relation::Implication, false) => {
+                diesel::delete(tag_implication::table.find((tag_id, related_id))).execute(conn)?;
+            }
+            (Relation::Suggestion, true) => {
+                if tag_id == related_id {
+                    return Err(api::Error::CyclicDependency(ResourceType::TagSuggestion));
+                }
+                diesel::insert_into(tag_suggestion::table)
+                    .values(TagSuggestion {
+                        parent_id: tag_id,
+                        child_id: related_id,
+                    })
+                    .on_conflict_do_nothing()
+                    .execute(conn)?;
+            }
+            (Relation::Suggestion, false) => {
+                diesel::delete(tag_suggestion::table.find((tag_id, related_id))).execute(conn)?;
+            }
+        }
+        update::tag::last_edit_time(conn, tag_id).map(|_| tag_id)
+    })?;
+    conn.transaction(|conn| TagInfo::new_from_id(conn, tag_id, &fields).map_err(api::Error::from))
+}
+
+#[utoipa::path(
+    delete,
+    path = "/tag/{name}",
+    summary = "Deletes a tag",
+    params(("name" = String, Path)),
+    request_body = DeleteBody,
+    responses((status = 200, description = "Tag was deleted"))
+)]
 fn delete(auth: AuthResult, name: String, client_version: DeleteBody) -> ApiResult<()> {
     let client = auth?;
     api::verify_privilege(client, config::privileges().tag_delete)?;
@@ -422,12 +703,14 @@ fn delete(auth: AuthResult, name: String, client_version: DeleteBody) -> ApiResu
 mod test {
     use crate::api::ApiResult;
     use crate::model::tag::Tag;
-    use crate::schema::{database_statistics, tag, tag_name, tag_statistics};
+    use crate::schema::{database_statistics, tag, tag_implication, tag_name, tag_statistics, tag_suggestion};
     use crate::test::*;
     use crate::time::DateTime;
+    use crate::update;
     use diesel::dsl::exists;
     use diesel::prelude::*;
     use serial_test::{parallel, serial};
+    use warp::http::StatusCode;
 
     // Exclude fields that involve creation_time or last_edit_time
     const FIELDS: &str = "&fields=description,category,names,implications,suggestions,usages";
@@ -445,6 +728,14 @@ mod test {
         Ok(())
     }
 
+    #[tokio::test]
+    #[parallel]
+    async fn suggest() -> ApiResult<()> {
+        const QUERY: &str = "GET /tags/suggest/?query";
+        verify_query(&format!("{QUERY}=sky{FIELDS}"), "tag/suggest_sky.json").await?;
+        verify_query(&format!("{QUERY}=s&limit=3{FIELDS}"), "tag/suggest_s.json").await
+    }
+
     #[tokio::test]
     #[parallel]
     async fn get() -> ApiResult<()> {
@@ -520,6 +811,26 @@ mod test {
         Ok(())
     }
 
+    #[tokio::test]
+    #[serial]
+    async fn create_taken_name() -> ApiResult<()> {
+        const QUERY: &str = "POST /tags";
+        verify_status_with_body(QUERY, "tag/create_taken_name.json", StatusCode::CONFLICT).await?;
+
+        let mut conn = get_connection()?;
+        let has_new_name: bool =
+            diesel::select(exists(tag_name::table.filter(tag_name::name.eq("new_name")))).get_result(&mut conn)?;
+        assert!(!has_new_name);
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn create_invalid_names() -> ApiResult<()> {
+        verify_error("POST /tags", "tag/create_invalid_names.json", StatusCode::BAD_REQUEST).await?;
+        verify_error("POST /tags", "tag/create_invalid_implications.json", StatusCode::BAD_REQUEST).await
+    }
+
     #[tokio::test]
     #[serial]
     async fn merge() -> ApiResult<()> {
@@ -618,4 +929,93 @@ mod test {
         assert_eq!(new_suggestion_count, suggestion_count);
         Ok(())
     }
+
+    #[tokio::test]
+    #[serial]
+    async fn edit_implications() -> ApiResult<()> {
+        let has_implication = |conn: &mut PgConnection, parent: &str, child: &str| -> QueryResult<bool> {
+            let parent_id: i64 = tag_name::table
+                .select(tag_name::tag_id)
+                .filter(tag_name::name.eq(parent))
+                .first(conn)?;
+            let child_id: i64 = tag_name::table
+                .select(tag_name::tag_id)
+                .filter(tag_name::name.eq(child))
+                .first(conn)?;
+            diesel::select(exists(tag_implication::table.find((parent_id, child_id)))).get_result(conn)
+        };
+
+        let mut conn = get_connection()?;
+        assert!(!has_implication(&mut conn, "luke_skywalker", "star_wars")?);
+
+        const QUERY: &str = "/tag/luke_skywalker/implications";
+        verify_status_with_body(&format!("POST {QUERY}"), "tag/implication.json", StatusCode::OK).await?;
+        assert!(has_implication(&mut conn, "luke_skywalker", "star_wars")?);
+
+        // Adding an existing implication is a no-op
+        verify_status_with_body(&format!("POST {QUERY}"), "tag/implication.json", StatusCode::OK).await?;
+
+        // Self-implications and cycles are rejected
+        const REVERSE_QUERY: &str = "POST /tag/star_wars/implications";
+        verify_status_with_body(REVERSE_QUERY, "tag/implication.json", StatusCode::BAD_REQUEST).await?;
+        verify_status_with_body(REVERSE_QUERY, "tag/cyclic_implication.json", StatusCode::BAD_REQUEST).await?;
+        verify_status_with_body("POST /tag/plant/implications", "tag/missing_relation.json", StatusCode::NOT_FOUND)
+            .await?;
+        assert!(!has_implication(&mut conn, "star_wars", "luke_skywalker")?);
+
+        verify_status_with_body(&format!("DELETE {QUERY}"), "tag/implication.json", StatusCode::OK).await?;
+        assert!(!has_implication(&mut conn, "luke_skywalker", "star_wars")?);
+        Ok(reset_database())
+    }
+
+    #[test]
+    #[parallel]
+    fn implication_cycle() -> ApiResult<()> {
+        let mut conn = get_connection()?;
+        let mut get_tag_id = |name: &str| -> QueryResult<i64> {
+            tag_name::table
+                .select(tag_name::tag_id)
+                .filter(tag_name::name.eq(name))
+                .first(&mut conn)
+        };
+        let night_id = get_tag_id("night")?;
+        let night_sky_id = get_tag_id("night_sky")?;
+        let sky_id = get_tag_id("sky")?;
+
+        assert!(update::tag::check_implication_cycle(&mut conn, night_id, sky_id).is_ok());
+        let err = update::tag::check_implication_cycle(&mut conn, night_id, night_sky_id).unwrap_err();
+        assert_eq!(err.to_string(), "Tag implications would form a cycle: night -> night_sky -> night");
+        let err = update::tag::check_implication_cycle(&mut conn, night_id, night_id).unwrap_err();
+        assert_eq!(err.to_string(), "Tag implications would form a cycle: night -> night");
+        Ok(())
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn edit_suggestions() -> ApiResult<()> {
+        let has_suggestion = |conn: &mut PgConnection, parent: &str, child: &str| -> QueryResult<bool> {
+            let parent_id: i64 = tag_name::table
+                .select(tag_name::tag_id)
+                .filter(tag_name::name.eq(parent))
+                .first(conn)?;
+            let child_id: i64 = tag_name::table
+                .select(tag_name::tag_id)
+                .filter(tag_name::name.eq(child))
+                .first(conn)?;
+            diesel::select(exists(tag_suggestion::table.find((parent_id, child_id)))).get_result(conn)
+        };
+
+        let mut conn = get_connection()?;
+        assert!(!has_suggestion(&mut conn, "forest", "river")?);
+
+        const QUERY: &str = "/tag/forest/suggestions";
+        verify_status_with_body(&format!("POST {QUERY}"), "tag/suggestion.json", StatusCode::OK).await?;
+        assert!(has_suggestion(&mut conn, "forest", "river")?);
+
+        verify_status_with_body("POST /tag/river/suggestions", "tag/suggestion.json", StatusCode::BAD_REQUEST).await?;
+
+        verify_status_with_body(&format!("DELETE {QUERY}"), "tag/suggestion.json", StatusCode::OK).await?;
+        assert!(!has_suggestion(&mut conn, "forest", "river")?);
+        Ok(reset_database())
+    }
 }
@@ -9,6 +9,7 @@ use crate::time::DateTime;
 use crate::{api, config, db, resource};
 use diesel::prelude::*;
 use serde::Deserialize;
+use utoipa::ToSchema;
 use warp::{Filter, Rejection, Reply};
 
 pub fn routes() -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone {
@@ -28,14 +29,14 @@ pub fn routes() -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone
         .and(api::auth())
         .and(warp::path!("tag-categories"))
         .and(api::resource_query())
-        .and(warp::body::json())
+        .and(api::json_body())
         .map(create)
         .map(api::Reply::from);
     let update = warp::put()
         .and(api::auth())
         .and(warp::path!("tag-category" / String))
         .and(api::resource_query())
-        .and(warp::body::json())
+        .and(api::json_body())
         .map(update)
         .map(api::Reply::from);
     let set_default = warp::put()
@@ -47,13 +48,20 @@ pub fn routes() -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone
     let delete = warp::delete()
         .and(api::auth())
         .and(warp::path!("tag-category" / String))
-        .and(warp::body::json())
+        .and(api::json_body())
         .map(delete)
         .map(api::Reply::from);
 
     list.or(get).or(create).or(update).or(set_default).or(delete)
 }
 
+#[utoipa::path(
+    get,
+    path = "/tag-categories",
+    summary = "Lists tag categories",
+    params(ResourceParams),
+    responses((status = 200, body = UnpagedResponse<TagCategoryInfo>))
+)]
 fn list(auth: AuthResult, params: ResourceParams) -> ApiResult<UnpagedResponse<TagCategoryInfo>> {
     let client = auth?;
     api::verify_privilege(client, config::privileges().tag_category_list)?;
@@ -66,6 +74,13 @@ fn list(auth: AuthResult, params: ResourceParams) -> ApiResult<UnpagedResponse<T
     })
 }
 
+#[utoipa::path(
+    get,
+    path = "/tag-category/{name}",
+    summary = "Gets a tag category",
+    params(("name" = String, Path), ResourceParams),
+    responses((status = 200, body = TagCategoryInfo))
+)]
 fn get(auth: AuthResult, name: String, params: ResourceParams) -> ApiResult<TagCategoryInfo> {
     let client = auth?;
     api::verify_privilege(client, config::privileges().tag_category_view)?;
@@ -82,14 +97,23 @@ fn get(auth: AuthResult, name: String, params: ResourceParams) -> ApiResult<TagC
     })
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 #[serde(deny_unknown_fields)]
+#[schema(as = TagCategoryCreateBody)]
 struct CreateBody {
     order: i32,
     name: SmallString,
     color: SmallString,
 }
 
+#[utoipa::path(
+    post,
+    path = "/tag-categories",
+    summary = "Creates a tag category",
+    params(ResourceParams),
+    request_body = CreateBody,
+    responses((status = 200, body = TagCategoryInfo))
+)]
 fn create(auth: AuthResult, params: ResourceParams, body: CreateBody) -> ApiResult<TagCategoryInfo> {
     let client = auth?;
     api::verify_privilege(client, config::privileges().tag_category_create)?;
@@ -110,8 +134,9 @@ fn create(auth: AuthResult, params: ResourceParams, body: CreateBody) -> ApiResu
     conn.transaction(|conn| TagCategoryInfo::new(conn, category, &fields).map_err(api::Error::from))
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 #[serde(deny_unknown_fields)]
+#[schema(as = TagCategoryUpdateBody)]
 struct UpdateBody {
     version: DateTime,
     order: Option<SmallString>, // TODO: Client sends order out as string so we convert on server, would be better to do this on client
@@ -119,6 +144,14 @@ struct UpdateBody {
     color: Option<SmallString>,
 }
 
+#[utoipa::path(
+    put,
+    path = "/tag-category/{name}",
+    summary = "Updates a tag category",
+    params(("name" = String, Path), ResourceParams),
+    request_body = UpdateBody,
+    responses((status = 200, body = TagCategoryInfo))
+)]
 fn update(auth: AuthResult, name: String, params: ResourceParams, body: UpdateBody) -> ApiResult<TagCategoryInfo> {
     let client = auth?;
     let fields = resource::create_table(params.fields()).map_err(Box::from)?;
@@ -165,6 +198,13 @@ fn update(auth: AuthResult, name: String, params: ResourceParams, body: UpdateBo
     conn.transaction(|conn| TagCategoryInfo::new_from_id(conn, category_id, &fields).map_err(api::Error::from))
 }
 
+#[utoipa::path(
+    put,
+    path = "/tag-category/{name}/default",
+    summary = "Sets the default tag category",
+    params(("name" = String, Path), ResourceParams),
+    responses((status = 200, body = TagCategoryInfo))
+)]
 fn set_default(auth: AuthResult, name: String, params: ResourceParams) -> ApiResult<TagCategoryInfo> {
     let client = auth?;
     api::verify_privilege(client, config::privileges().tag_category_set_default)?;
@@ -210,6 +250,14 @@ fn set_default(auth: AuthResult, name: String, params: ResourceParams) -> ApiRes
     conn.transaction(|conn| TagCategoryInfo::new(conn, new_default_category, &fields).map_err(api::Error::from))
 }
 
+#[utoipa::path(
+    delete,
+    path = "/tag-category/{name}",
+    summary = "Deletes a tag category",
+    params(("name" = String, Path)),
+    request_body = DeleteBody,
+    responses((status = 200, description = "Tag category was deleted"))
+)]
 fn delete(auth: AuthResult, name: String, client_version: DeleteBody) -> ApiResult<()> {
     let client = auth?;
     api::verify_privilege(client, config::privileges().tag_category_delete)?;
@@ -233,8 +281,8 @@ fn delete(auth: AuthResult, name: String, client_version: DeleteBody) -> ApiResu
 #[cfg(test)]
 mod test {
     use crate::api::ApiResult;
-    use crate::model::tag::TagCategory;
-    use crate::schema::{tag_category, tag_category_statistics};
+    use crate::model::tag::{NewTag, NewTagCategory, TagCategory};
+    use crate::schema::{tag, tag_category, tag_category_statistics};
     use crate::test::*;
     use crate::time::DateTime;
     use diesel::prelude::*;
@@ -322,6 +370,48 @@ mod test {
         verify_query(&format!("PUT /tag-category/{new_name}/?{FIELDS}"), "tag_category/update_restore.json").await
     }
 
+    #[tokio::test]
+    #[serial]
+    async fn delete() -> ApiResult<()> {
+        const NAME: &str = "Obsolete";
+        let get_usage_count = |conn: &mut PgConnection, category_id: i64| -> QueryResult<i64> {
+            tag_category_statistics::table
+                .select(tag_category_statistics::usage_count)
+                .filter(tag_category_statistics::category_id.eq(category_id))
+                .first(conn)
+        };
+
+        let mut conn = get_connection()?;
+        let new_category = NewTagCategory {
+            order: 0,
+            name: NAME,
+            color: "",
+        };
+        let category_id: i64 = diesel::insert_into(tag_category::table)
+            .values(new_category)
+            .returning(tag_category::id)
+            .get_result(&mut conn)?;
+        let new_tag = NewTag {
+            category_id,
+            description: "",
+        };
+        let tag_id: i64 = diesel::insert_into(tag::table)
+            .values(new_tag)
+            .returning(tag::id)
+            .get_result(&mut conn)?;
+        let default_usage_count = get_usage_count(&mut conn, 0)?;
+
+        verify_query(&format!("DELETE /tag-category/{NAME}"), "delete.json").await?;
+
+        // Tags of the deleted category should be moved to the default category
+        let tag_category_id: i64 = tag::table.find(tag_id).select(tag::category_id).first(&mut conn)?;
+        assert_eq!(tag_category_id, 0);
+        assert_eq!(get_usage_count(&mut conn, 0)?, default_usage_count + 1);
+
+        diesel::delete(tag::table.find(tag_id)).execute(&mut conn)?;
+        Ok(())
+    }
+
     #[tokio::test]
     #[serial]
     async fn set_default() -> ApiResult<()> {
@@ -1,9 +1,10 @@
 use crate::api::{ApiResult, AuthResult};
 use crate::content::download;
-use crate::content::upload::{self, MAX_UPLOAD_SIZE, PartName};
+use crate::content::upload::{self, PartName};
 use crate::{api, config};
 use serde::{Deserialize, Serialize};
 use url::Url;
+use utoipa::ToSchema;
 use warp::multipart::FormData;
 use warp::{Filter, Rejection, Reply};
 
@@ -11,31 +12,38 @@ pub fn routes() -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone
     let upload_url = warp::post()
         .and(api::auth())
         .and(warp::path!("uploads"))
-        .and(warp::body::json())
+        .and(api::json_body())
         .then(upload_url)
         .map(api::Reply::from);
     let upload_multipart = warp::post()
         .and(api::auth())
         .and(warp::path!("uploads"))
-        .and(warp::filters::multipart::form().max_length(MAX_UPLOAD_SIZE))
+        .and(api::multipart_body())
         .then(upload_multipart)
         .map(api::Reply::from);
 
     upload_url.or(upload_multipart)
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 #[serde(deny_unknown_fields)]
 #[serde(rename_all = "camelCase")]
 struct UploadBody {
     content_url: Url,
 }
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 struct UploadResponse {
     token: String,
 }
 
+#[utoipa::path(
+    post,
+    path = "/uploads",
+    summary = "Uploads a temporary file",
+    request_body(content((UploadBody = "application/json"), ("multipart/form-data"))),
+    responses((status = 200, body = UploadResponse))
+)]
 async fn upload_url(auth: AuthResult, body: UploadBody) -> ApiResult<UploadResponse> {
     let client = auth?;
     api::verify_privilege(client, config::privileges().upload_create)?;
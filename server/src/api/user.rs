@@ -1,22 +1,27 @@
 use crate::api::{ApiResult, AuthResult, DeleteBody, PageParams, PagedResponse, ResourceParams};
+use crate::auth::header::Client;
 use crate::auth::password;
 use crate::config::RegexType;
 use crate::content::thumbnail::ThumbnailType;
-use crate::content::upload::{MAX_UPLOAD_SIZE, PartName};
+use crate::content::upload::PartName;
 use crate::content::{Content, FileContents, hash, upload};
-use crate::model::enums::{AvatarStyle, ResourceType, UserRank};
+use crate::model::enums::{AvatarStyle, ResourceType, Score, UserRank};
 use crate::model::user::NewUser;
+use crate::resource::post::PostInfo;
 use crate::resource::user::{UserInfo, Visibility};
 use crate::schema::user;
+use crate::search::post::UserPosts;
 use crate::search::user::QueryBuilder;
 use crate::string::SmallString;
 use crate::time::DateTime;
-use crate::{api, config, db, filesystem, resource, update};
+use crate::{api, config, db, filesystem, mail, resource, search, update};
 use argon2::password_hash::SaltString;
 use argon2::password_hash::rand_core::OsRng;
 use diesel::prelude::*;
 use serde::Deserialize;
 use url::Url;
+use utoipa::ToSchema;
+use uuid::Uuid;
 use warp::filters::multipart::FormData;
 use warp::{Filter, Rejection, Reply};
 
@@ -33,76 +38,136 @@ pub fn routes() -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone
         .and(api::resource_query())
         .map(get)
         .map(api::Reply::from);
+    let list_favorites = warp::get()
+        .and(api::auth())
+        .and(warp::path!("user" / String / "favorites"))
+        .and(warp::query())
+        .map(list_favorites)
+        .map(api::Reply::from);
+    let list_uploads = warp::get()
+        .and(api::auth())
+        .and(warp::path!("user" / String / "uploads"))
+        .and(warp::query())
+        .map(list_uploads)
+        .map(api::Reply::from);
+    let list_liked = warp::get()
+        .and(api::auth())
+        .and(warp::path!("user" / String / "liked"))
+        .and(warp::query())
+        .map(list_liked)
+        .map(api::Reply::from);
+    let list_disliked = warp::get()
+        .and(api::auth())
+        .and(warp::path!("user" / String / "disliked"))
+        .and(warp::query())
+        .map(list_disliked)
+        .map(api::Reply::from);
     let create = warp::post()
         .and(api::auth())
         .and(warp::path!("users"))
         .and(api::resource_query())
-        .and(warp::body::json())
+        .and(api::json_body())
         .then(create)
         .map(api::Reply::from);
     let create_multipart = warp::post()
         .and(api::auth())
         .and(warp::path!("users"))
         .and(api::resource_query())
-        .and(warp::filters::multipart::form().max_length(MAX_UPLOAD_SIZE))
+        .and(api::multipart_body())
         .then(create_multipart)
         .map(api::Reply::from);
     let update = warp::put()
         .and(api::auth())
         .and(warp::path!("user" / String))
         .and(api::resource_query())
-        .and(warp::body::json())
+        .and(api::json_body())
         .then(update)
         .map(api::Reply::from);
     let update_multipart = warp::put()
         .and(api::auth())
         .and(warp::path!("user" / String))
         .and(api::resource_query())
-        .and(warp::filters::multipart::form().max_length(MAX_UPLOAD_SIZE))
+        .and(api::multipart_body())
         .then(update_multipart)
         .map(api::Reply::from);
+    let change_password = warp::post()
+        .and(api::auth())
+        .and(warp::path!("user" / String / "password"))
+        .and(api::json_body())
+        .map(change_password)
+        .map(api::Reply::from);
+    let update_rank = warp::put()
+        .and(api::auth())
+        .and(warp::path!("user" / String / "rank"))
+        .and(api::resource_query())
+        .and(api::json_body())
+        .map(update_rank)
+        .map(api::Reply::from);
+    let verify_email = warp::get()
+        .and(warp::path!("user" / String / "verify" / Uuid))
+        .map(verify_email)
+        .map(api::Reply::from);
     let delete = warp::delete()
         .and(api::auth())
         .and(warp::path!("user" / String))
-        .and(warp::body::json())
+        .and(api::json_body())
         .map(delete)
         .map(api::Reply::from);
 
     list.or(get)
+        .or(list_favorites)
+        .or(list_uploads)
+        .or(list_liked)
+        .or(list_disliked)
         .or(create)
         .or(create_multipart)
         .or(update)
         .or(update_multipart)
+        .or(change_password)
+        .or(update_rank)
+        .or(verify_email)
         .or(delete)
 }
 
 const MAX_USERS_PER_PAGE: i64 = 1000;
 
+#[utoipa::path(
+    get,
+    path = "/users",
+    summary = "Lists users",
+    params(PageParams, ResourceParams),
+    responses((status = 200, body = PagedResponse<UserInfo>))
+)]
 fn list(auth: AuthResult, params: PageParams) -> ApiResult<PagedResponse<UserInfo>> {
     let client = auth?;
     params.bump_login(client)?;
     api::verify_privilege(client, config::privileges().user_list)?;
 
     let offset = params.offset.unwrap_or(0);
-    let limit = std::cmp::min(params.limit.get(), MAX_USERS_PER_PAGE);
+    let limit = params.limit(MAX_USERS_PER_PAGE, MAX_USERS_PER_PAGE);
     let fields = resource::create_table(params.fields()).map_err(Box::from)?;
 
     db::get_connection()?.transaction(|conn| {
         let mut query_builder = QueryBuilder::new(params.criteria())?;
-        query_builder.set_offset_and_limit(offset, limit);
-
-        let total = query_builder.count(conn)?;
-        let selected_users = query_builder.load(conn)?;
+        let page = api::load_page(conn, &mut query_builder, offset, limit)?;
         Ok(PagedResponse {
             query: params.into_query(),
             offset,
             limit,
-            total,
-            results: UserInfo::new_batch_from_ids(conn, selected_users, &fields, Visibility::PublicOnly)?,
+            total: page.total,
+            has_more: page.has_more,
+            results: UserInfo::new_batch_from_ids(conn, page.ids, &fields, Visibility::PublicOnly)?,
         })
     })
 }
 
+#[utoipa::path(
+    get,
+    path = "/user/{name}",
+    summary = "Gets a user",
+    params(("name" = String, Path), ResourceParams),
+    responses((status = 200, body = UserInfo))
+)]
 fn get(auth: AuthResult, username: String, params: ResourceParams) -> ApiResult<UserInfo> {
     let client = auth?;
     params.bump_login(client)?;
@@ -130,9 +195,119 @@ fn get(auth: AuthResult, username: String, params: ResourceParams) -> ApiResult<
     })
 }
 
-#[derive(Deserialize)]
+/// Lists the posts favorited by a user, starting with the most recently favorited.
+#[utoipa::path(
+    get,
+    path = "/user/{name}/favorites",
+    summary = "Lists posts favorited by a user",
+    params(("name" = String, Path), PageParams, ResourceParams),
+    responses((status = 200, body = PagedResponse<PostInfo>))
+)]
+fn list_favorites(auth: AuthResult, username: String, params: PageParams) -> ApiResult<PagedResponse<PostInfo>> {
+    list_user_posts(auth, username, params, false, UserPosts::Favorites)
+}
+
+/// Lists the posts uploaded by a user, starting with the most recent upload.
+#[utoipa::path(
+    get,
+    path = "/user/{name}/uploads",
+    summary = "Lists posts uploaded by a user",
+    params(("name" = String, Path), PageParams, ResourceParams),
+    responses((status = 200, body = PagedResponse<PostInfo>))
+)]
+fn list_uploads(auth: AuthResult, username: String, params: PageParams) -> ApiResult<PagedResponse<PostInfo>> {
+    list_user_posts(auth, username, params, false, UserPosts::Uploads)
+}
+
+/// Lists the posts liked by a user, starting with the most recently liked.
+#[utoipa::path(
+    get,
+    path = "/user/{name}/liked",
+    summary = "Lists posts liked by a user",
+    params(("name" = String, Path), PageParams, ResourceParams),
+    responses((status = 200, body = PagedResponse<PostInfo>))
+)]
+fn list_liked(auth: AuthResult, username: String, params: PageParams) -> ApiResult<PagedResponse<PostInfo>> {
+    list_scored_posts(auth, username, params, Score::Like)
+}
+
+/// Lists the posts disliked by a user, starting with the most recently disliked.
+#[utoipa::path(
+    get,
+    path = "/user/{name}/disliked",
+    summary = "Lists posts disliked by a user",
+    params(("name" = String, Path), PageParams, ResourceParams),
+    responses((status = 200, body = PagedResponse<PostInfo>))
+)]
+fn list_disliked(auth: AuthResult, username: String, params: PageParams) -> ApiResult<PagedResponse<PostInfo>> {
+    list_scored_posts(auth, username, params, Score::Dislike)
+}
+
+/// Lists the posts given `score` by a user. Scores are private, so only the user themself can see them.
+fn list_scored_posts(
+    auth: AuthResult,
+    username: String,
+    params: PageParams,
+    score: Score,
+) -> ApiResult<PagedResponse<PostInfo>> {
+    list_user_posts(auth, username, params, true, |user_id| UserPosts::Scored(user_id, score))
+}
+
+/// Lists a page of posts related to the user named `username`. `user_posts` is given the id
+/// of the user and returns which of their posts to list.
+/// If `private` is set, only the user themself can list the posts.
+fn list_user_posts<F>(
+    auth: AuthResult,
+    username: String,
+    params: PageParams,
+    private: bool,
+    user_posts: F,
+) -> ApiResult<PagedResponse<PostInfo>>
+where
+    F: FnOnce(i64) -> UserPosts,
+{
+    let client = auth?;
+    params.bump_login(client)?;
+    api::verify_privilege(client, config::privileges().post_list)?;
+
+    let offset = params.offset.unwrap_or(0);
+    let pagination = config::pagination();
+    let limit = params.limit(pagination.default_post_page_size, pagination.max_post_page_size);
+    let fields = resource::create_table(params.fields()).map_err(Box::from)?;
+    let username = percent_encoding::percent_decode_str(&username).decode_utf8()?;
+
+    db::get_connection()?.transaction(|conn| {
+        let user_id = user::table
+            .select(user::id)
+            .filter(user::name.eq(username))
+            .first(conn)
+            .optional()?
+            .ok_or(api::Error::NotFound(ResourceType::User))?;
+        if client.id != Some(user_id) {
+            match private {
+                true => return Err(api::Error::InsufficientPrivileges),
+                false => api::verify_privilege(client, config::privileges().user_view)?,
+            }
+        }
+
+        let mut query_builder = search::post::QueryBuilder::new(client, "")?;
+        query_builder.set_user_posts(user_posts(user_id));
+        let page = api::load_page(conn, &mut query_builder, offset, limit)?;
+        Ok(PagedResponse {
+            query: params.into_query(),
+            offset,
+            limit,
+            total: page.total,
+            has_more: page.has_more,
+            results: PostInfo::new_batch_from_ids(conn, client, page.ids, &fields)?,
+        })
+    })
+}
+
+#[derive(Deserialize, ToSchema)]
 #[serde(deny_unknown_fields)]
 #[serde(rename_all = "camelCase")]
+#[schema(as = UserCreateBody)]
 struct CreateBody {
     name: SmallString,
     password: SmallString,
@@ -145,6 +320,14 @@ struct CreateBody {
     avatar_url: Option<Url>,
 }
 
+#[utoipa::path(
+    post,
+    path = "/users",
+    summary = "Creates a user",
+    params(ResourceParams),
+    request_body(content((CreateBody = "application/json"), ("multipart/form-data"))),
+    responses((status = 200, body = UserInfo))
+)]
 async fn create(auth: AuthResult, params: ResourceParams, body: CreateBody) -> ApiResult<UserInfo> {
     let client = auth?;
     params.bump_login(client)?;
@@ -165,12 +348,15 @@ async fn create(auth: AuthResult, params: ResourceParams, body: CreateBody) -> A
     }
 
     let fields = resource::create_table(params.fields()).map_err(Box::from)?;
-    api::verify_matches_regex(&body.name, RegexType::Username)?;
-    api::verify_matches_regex(&body.password, RegexType::Password)?;
-    api::verify_valid_email(body.email.as_deref())?;
+    api::verify_fields([
+        ("name", api::verify_matches_regex(&body.name, RegexType::Username)),
+        ("password", api::verify_matches_regex(&body.password, RegexType::Password)),
+        ("email", api::verify_valid_email(body.email.as_deref()).map_err(api::Error::from)),
+    ])?;
 
     let salt = SaltString::generate(&mut OsRng);
     let hash = password::hash_password(&body.password, &salt)?;
+    let verification_token = new_verification_token(body.email.as_deref());
     let new_user = NewUser {
         name: &body.name,
         password_hash: &hash,
@@ -178,6 +364,7 @@ async fn create(auth: AuthResult, params: ResourceParams, body: CreateBody) -> A
         email: body.email.as_deref(),
         rank: creation_rank,
         avatar_style: body.avatar_style.unwrap_or_default(),
+        email_verification_token: verification_token,
     };
 
     let custom_avatar = match Content::new(body.avatar, body.avatar_token, body.avatar_url) {
@@ -204,6 +391,9 @@ async fn create(auth: AuthResult, params: ResourceParams, body: CreateBody) -> A
 
         Ok::<_, api::Error>(user_id)
     })?;
+    if let (Some(email), Some(token)) = (body.email.as_deref(), verification_token) {
+        send_verification_email(&body.name, email, token);
+    }
     conn.transaction(|conn| UserInfo::new_from_id(conn, user_id, &fields, Visibility::Full).map_err(api::Error::from))
 }
 
@@ -219,9 +409,10 @@ async fn create_multipart(auth: AuthResult, params: ResourceParams, form_data: F
     }
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 #[serde(deny_unknown_fields)]
 #[serde(rename_all = "camelCase")]
+#[schema(as = UserUpdateBody)]
 struct UpdateBody {
     version: DateTime,
     name: Option<SmallString>,
@@ -236,18 +427,26 @@ struct UpdateBody {
     avatar_url: Option<Url>,
 }
 
+#[utoipa::path(
+    put,
+    path = "/user/{name}",
+    summary = "Updates a user",
+    params(("name" = String, Path), ResourceParams),
+    request_body(content((UpdateBody = "application/json"), ("multipart/form-data"))),
+    responses((status = 200, body = UserInfo))
+)]
 async fn update(auth: AuthResult, username: String, params: ResourceParams, body: UpdateBody) -> ApiResult<UserInfo> {
     let client = auth?;
     params.bump_login(client)?;
 
     let fields = resource::create_table(params.fields()).map_err(Box::from)?;
     let username = percent_encoding::percent_decode_str(&username).decode_utf8()?;
-
     let custom_avatar = match Content::new(body.avatar, body.avatar_token, body.avatar_url) {
         Some(content) => Some(content.thumbnail(ThumbnailType::Avatar).await?),
         None => None,
     };
 
+    let mut verification = None;
     let mut conn = db::get_connection()?;
     let (user_id, visibility) = conn.transaction(|conn| {
         let (user_id, user_version): (i64, DateTime) = user::table
@@ -262,14 +461,41 @@ async fn update(auth: AuthResult, username: String, params: ResourceParams, body
             false => Visibility::PublicOnly,
         };
 
-        if let Some(password) = body.password {
-            let required_rank = match editing_self {
-                true => config::privileges().user_edit_self_pass,
-                false => config::privileges().user_edit_any_pass,
-            };
-            api::verify_privilege(client, required_rank)?;
-            api::verify_matches_regex(&password, RegexType::Password)?;
+        // Privileges are checked before the fields are validated, so that clients which can't
+        // edit a field aren't told whether their value for it would have been valid
+        let verify_edit_privilege = |self_rank, any_rank| match editing_self {
+            true => api::verify_privilege(client, self_rank),
+            false => api::verify_privilege(client, any_rank),
+        };
+        let privileges = config::privileges();
+        if body.password.is_some() {
+            verify_edit_privilege(privileges.user_edit_self_pass, privileges.user_edit_any_pass)?;
+        }
+        if body.email.is_some() {
+            verify_edit_privilege(privileges.user_edit_self_email, privileges.user_edit_any_email)?;
+        }
+        if let Some(rank) = body.rank {
+            verify_rank_privilege(client, editing_self, rank)?;
+        }
+        if body.avatar_style.is_some() || custom_avatar.is_some() {
+            verify_edit_privilege(privileges.user_edit_self_avatar, privileges.user_edit_any_avatar)?;
+        }
+        if body.name.is_some() {
+            verify_edit_privilege(privileges.user_edit_self_name, privileges.user_edit_any_name)?;
+        }
 
+        let verify_optional_regex = |value: Option<&str>, regex_type| match value {
+            Some(value) => api::verify_matches_regex(value, regex_type),
+            None => Ok(()),
+        };
+        let new_email = body.email.as_ref().and_then(Option::as_deref);
+        api::verify_fields([
+            ("name", verify_optional_regex(body.name.as_deref(), RegexType::Username)),
+            ("password", verify_optional_regex(body.password.as_deref(), RegexType::Password)),
+            ("email", api::verify_valid_email(new_email).map_err(api::Error::from)),
+        ])?;
+
+        if let Some(password) = body.password {
             let salt = SaltString::generate(&mut OsRng);
             let hash = password::hash_password(&password, &salt)?;
             diesel::update(user::table.find(user_id))
@@ -277,59 +503,29 @@ async fn update(auth: AuthResult, username: String, params: ResourceParams, body
                 .execute(conn)?;
         }
         if let Some(email) = body.email {
-            let required_rank = match editing_self {
-                true => config::privileges().user_edit_self_email,
-                false => config::privileges().user_edit_any_email,
-            };
-            api::verify_privilege(client, required_rank)?;
-            api::verify_valid_email(email.as_deref())?;
-
+            // A new address has to be verified again
+            let verification_token = new_verification_token(email.as_deref());
+            verification = email.clone().zip(verification_token);
             diesel::update(user::table.find(user_id))
-                .set(user::email.eq(email))
+                .set((
+                    user::email.eq(email),
+                    user::email_verified.eq(false),
+                    user::email_verification_token.eq(verification_token),
+                ))
                 .execute(conn)?;
         }
         if let Some(rank) = body.rank {
-            let required_rank = match editing_self {
-                true => config::privileges().user_edit_self_rank,
-                false => config::privileges().user_edit_any_rank,
-            };
-            api::verify_privilege(client, required_rank)?;
-            if rank > config::default_rank() {
-                api::verify_privilege(client, rank)?;
-            }
-
-            diesel::update(user::table.find(user_id))
-                .set(user::rank.eq(rank))
-                .execute(conn)?;
+            update::user::rank(conn, user_id, client.id, rank)?;
         }
         if let Some(avatar_style) = body.avatar_style {
-            let required_rank = match editing_self {
-                true => config::privileges().user_edit_self_avatar,
-                false => config::privileges().user_edit_any_avatar,
-            };
-            api::verify_privilege(client, required_rank)?;
-
             diesel::update(user::table.find(user_id))
                 .set(user::avatar_style.eq(avatar_style))
                 .execute(conn)?;
         }
         if let Some(avatar) = custom_avatar {
-            let required_rank = match editing_self {
-                true => config::privileges().user_edit_self_avatar,
-                false => config::privileges().user_edit_any_avatar,
-            };
-            api::verify_privilege(client, required_rank)?;
-
             update::user::avatar(conn, user_id, &username, avatar)?;
         }
         if let Some(new_name) = body.name.as_deref() {
-            let required_rank = match editing_self {
-                true => config::privileges().user_edit_self_name,
-                false => config::privileges().user_edit_any_name,
-            };
-            api::verify_privilege(client, required_rank)?;
-            api::verify_matches_regex(new_name, RegexType::Username)?;
-
             // Update first to see if new name clashes with any existing names
             diesel::update(user::table.find(user_id))
                 .set(user::name.eq(new_name))
@@ -343,6 +539,10 @@ async fn update(auth: AuthResult, username: String, params: ResourceParams, body
         }
         update::user::last_edit_time(conn, user_id).map(|_| (user_id, visibility))
     })?;
+    if let Some((email, token)) = verification {
+        let name = body.name.as_deref().unwrap_or(&username);
+        send_verification_email(name, &email, token);
+    }
     conn.transaction(|conn| UserInfo::new_from_id(conn, user_id, &fields, visibility).map_err(api::Error::from))
 }
 
@@ -363,6 +563,171 @@ async fn update_multipart(
     }
 }
 
+#[derive(Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+#[serde(rename_all = "camelCase")]
+#[schema(as = UserPasswordChangeBody)]
+struct PasswordChangeBody {
+    old_password: String,
+    new_password: String,
+}
+
+#[utoipa::path(
+    post,
+    path = "/user/{name}/password",
+    summary = "Changes the password of the client",
+    params(("name" = String, Path)),
+    request_body = PasswordChangeBody,
+    responses((status = 200))
+)]
+fn change_password(auth: AuthResult, username: String, body: PasswordChangeBody) -> ApiResult<()> {
+    let client = auth?;
+    api::verify_privilege(client, config::privileges().user_edit_self_pass)?;
+    api::verify_matches_regex(&body.new_password, RegexType::Password)?;
+
+    let username = percent_encoding::percent_decode_str(&username).decode_utf8()?;
+    db::get_connection()?.transaction(|conn| {
+        let (user_id, password_hash): (i64, String) = user::table
+            .select((user::id, user::password_hash))
+            .filter(user::name.eq(&username))
+            .first(conn)?;
+
+        // Other users' passwords can only be changed through updating the user
+        if client.id != Some(user_id) {
+            return Err(api::Error::InsufficientPrivileges);
+        }
+        if !password::is_valid_password(&password_hash, &body.old_password) {
+            return Err(api::Error::IncorrectPassword);
+        }
+
+        let salt = SaltString::generate(&mut OsRng);
+        let hash = password::hash_password(&body.new_password, &salt)?;
+        diesel::update(user::table.find(user_id))
+            .set((user::password_salt.eq(salt.as_str()), user::password_hash.eq(hash)))
+            .execute(conn)?;
+        update::user::last_edit_time(conn, user_id)
+    })
+}
+
+#[derive(Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+#[schema(as = UserRankUpdateBody)]
+struct RankUpdateBody {
+    version: DateTime,
+    rank: UserRank,
+}
+
+#[utoipa::path(
+    put,
+    path = "/user/{name}/rank",
+    summary = "Changes the rank of a user",
+    params(("name" = String, Path), ResourceParams),
+    request_body = RankUpdateBody,
+    responses((status = 200, body = UserInfo))
+)]
+fn update_rank(
+    auth: AuthResult,
+    username: String,
+    params: ResourceParams,
+    body: RankUpdateBody,
+) -> ApiResult<UserInfo> {
+    let client = auth?;
+    params.bump_login(client)?;
+
+    let fields = resource::create_table(params.fields()).map_err(Box::from)?;
+    let username = percent_encoding::percent_decode_str(&username).decode_utf8()?;
+    db::get_connection()?.transaction(|conn| {
+        let (user_id, user_version): (i64, DateTime) = user::table
+            .select((user::id, user::last_edit_time))
+            .filter(user::name.eq(&username))
+            .first(conn)?;
+        api::verify_version(user_version, body.version)?;
+
+        let editing_self = client.id == Some(user_id);
+        verify_rank_privilege(client, editing_self, body.rank)?;
+        update::user::rank(conn, user_id, client.id, body.rank)?;
+        update::user::last_edit_time(conn, user_id)?;
+
+        let visibility = match editing_self {
+            true => Visibility::Full,
+            false => Visibility::PublicOnly,
+        };
+        UserInfo::new_from_id(conn, user_id, &fields, visibility).map_err(api::Error::from)
+    })
+}
+
+/// Checks that `client` may give a user the `rank`. Nobody can grant a rank above their own.
+fn verify_rank_privilege(client: Client, editing_self: bool, rank: UserRank) -> ApiResult<()> {
+    let required_rank = match editing_self {
+        true => config::privileges().user_edit_self_rank,
+        false => config::privileges().user_edit_any_rank,
+    };
+    api::verify_privilege(client, required_rank)?;
+    if rank > config::default_rank() {
+        api::verify_privilege(client, rank)?;
+    }
+    Ok(())
+}
+
+#[utoipa::path(
+    get,
+    path = "/user/{name}/verify/{token}",
+    summary = "Verifies a user's email address",
+    params(
+        ("name" = String, Path),
+        ("token" = Uuid, Path, description = "Token from the verification link sent to the user"),
+    ),
+    responses((status = 200, description = "Email address was verified"))
+)]
+fn verify_email(username: String, token: Uuid) -> ApiResult<()> {
+    let username = percent_encoding::percent_decode_str(&username).decode_utf8()?;
+    db::get_connection()?.transaction(|conn| {
+        let (user_id, verification_token): (i64, Option<Uuid>) = user::table
+            .select((user::id, user::email_verification_token))
+            .filter(user::name.eq(username))
+            .first(conn)?;
+        if verification_token != Some(token) {
+            return Err(api::Error::UnauthorizedEmailVerification);
+        }
+
+        diesel::update(user::table.find(user_id))
+            .set((user::email_verified.eq(true), user::email_verification_token.eq(None::<Uuid>)))
+            .execute(conn)?;
+        Ok(())
+    })
+}
+
+/// Creates a token for verifying `email`, if email verification is enabled.
+fn new_verification_token(email: Option<&str>) -> Option<Uuid> {
+    email.filter(|_| config::get().verify_emails).map(|_| Uuid::new_v4())
+}
+
+/// Sends a link for verifying `email` to the user. If smtp isn't configured, the email is logged instead.
+/// The user has already been saved at this point, so failures are logged rather than returned.
+fn send_verification_email(username: &str, email: &str, token: Uuid) {
+    let domain = config::get().domain.as_deref().unwrap_or("");
+    let encoded_username = percent_encoding::utf8_percent_encode(username, percent_encoding::NON_ALPHANUMERIC);
+    let url = format!("{domain}/api/user/{encoded_username}/verify/{token}");
+    let body = format!(
+        "Hello,
+
+         Please verify your email address on {} by clicking this link: {url}\n
+         If you didn't sign up, please ignore this email.",
+        config::get().public_info.name
+    );
+    if let Err(err) = mail::send_or_log(email, "Email Verification", body) {
+        eprintln!("Failed to send verification email to {username}: {err}");
+    }
+}
+
+#[utoipa::path(
+    delete,
+    path = "/user/{name}",
+    summary = "Deletes a user",
+    params(("name" = String, Path)),
+    request_body = DeleteBody,
+    responses((status = 200, description = "User was deleted"))
+)]
 fn delete(auth: AuthResult, username: String, client_version: DeleteBody) -> ApiResult<()> {
     let client = auth?;
     let username = percent_encoding::percent_decode_str(&username).decode_utf8()?;
@@ -373,12 +738,13 @@ fn delete(auth: AuthResult, username: String, client_version: DeleteBody) -> Api
             .first(conn)?;
         api::verify_version(user_version, *client_version)?;
 
-        let deleting_self = client.id == Some(user_id);
-        let required_rank = match deleting_self {
-            true => config::privileges().user_delete_self,
-            false => config::privileges().user_delete_any,
-        };
-        api::verify_privilege(client, required_rank)?;
+        let privileges = config::privileges();
+        api::verify_self_or_any_privilege(
+            client,
+            Some(user_id),
+            privileges.user_delete_self,
+            privileges.user_delete_any,
+        )?;
 
         diesel::delete(user::table.find(user_id)).execute(conn)?;
         Ok(())
@@ -387,17 +753,22 @@ fn delete(auth: AuthResult, username: String, client_version: DeleteBody) -> Api
 
 #[cfg(test)]
 mod test {
-    use crate::api::ApiResult;
+    use crate::api::{self, ApiResult, ResourceParams};
+    use crate::auth::header::Client;
+    use crate::auth::password;
+    use crate::model::enums::UserRank;
     use crate::model::user::User;
-    use crate::schema::{database_statistics, user, user_statistics};
+    use crate::schema::{database_statistics, user, user_audit, user_statistics};
     use crate::test::*;
     use crate::time::DateTime;
     use diesel::dsl::exists;
     use diesel::prelude::*;
     use serial_test::{parallel, serial};
+    use uuid::Uuid;
+    use warp::http::StatusCode;
 
     // Exclude fields that involve creation_time or last_edit_time
-    const FIELDS: &str = "&fields=name,email,rank,avatarStyle,avatarUrl,commentCount,uploadedPostCount,likedPostCount,dislikedPostCount,favoritePostCount";
+    const FIELDS: &str = "&fields=name,email,emailVerified,rank,avatarStyle,avatarUrl,commentCount,uploadedPostCount,likedPostCount,dislikedPostCount,favoritePostCount";
 
     #[tokio::test]
     #[parallel]
@@ -408,6 +779,33 @@ mod test {
         verify_query(&format!("{QUERY}=name:*user* {SORT}{FIELDS}"), "user/list_has_user_in_name.json").await
     }
 
+    #[tokio::test]
+    #[parallel]
+    async fn list_posts() -> ApiResult<()> {
+        const PARAMS: &str = "?limit=40&fields=id";
+        verify_query(&format!("GET /user/regular_user/favorites/{PARAMS}"), "user/list_favorites.json").await?;
+        verify_query(&format!("GET /user/regular_user/uploads/{PARAMS}"), "user/list_uploads.json").await?;
+        verify_query(&format!("GET /user/administrator/disliked/{PARAMS}"), "user/list_disliked.json").await?;
+        verify_query_with_user(
+            "regular_user",
+            &format!("GET /user/regular_user/liked/{PARAMS}"),
+            "user/list_liked.json",
+        )
+        .await?;
+
+        // Posts above the restricted safety cap are left out, even from the user's own lists
+        verify_query_with_user(
+            "restricted_user",
+            &format!("GET /user/restricted_user/disliked/{PARAMS}"),
+            "user/list_disliked_restricted.json",
+        )
+        .await?;
+
+        // Scores are private, even to administrators
+        verify_status_with_user("administrator", "GET /user/regular_user/liked", StatusCode::FORBIDDEN).await;
+        Ok(())
+    }
+
     #[tokio::test]
     #[parallel]
     async fn get() -> ApiResult<()> {
@@ -424,6 +822,9 @@ mod test {
 
         verify_query(&format!("GET /user/{NAME}/?{FIELDS}"), "user/get.json").await?;
 
+        // Private counts are only visible to the user themselves
+        verify_query(&format!("GET /user/administrator/?{FIELDS}"), "user/get_self.json").await?;
+
         let new_last_edit_time = get_last_edit_time(&mut conn)?;
         assert_eq!(new_last_edit_time, last_edit_time);
         Ok(())
@@ -460,6 +861,29 @@ mod test {
         Ok(())
     }
 
+    #[tokio::test]
+    #[parallel]
+    async fn create_invalid() -> ApiResult<()> {
+        verify_error("POST /users", "user/create_invalid.json", StatusCode::BAD_REQUEST).await
+    }
+
+    #[tokio::test]
+    #[parallel]
+    async fn update_unprivileged_invalid() -> ApiResult<()> {
+        // Privileges are checked before the fields are validated
+        let regular_user = Client::new(Some(2), UserRank::Regular);
+        let body: super::UpdateBody =
+            serde_json::from_str(r#"{"version": "2025-01-17T06:25:08.461692853Z", "password": "bad"}"#)?;
+        let params = ResourceParams {
+            query: None,
+            fields: None,
+            bump_login: None,
+        };
+        let result = super::update(Ok(regular_user), String::from("restricted_user"), params, body).await;
+        assert!(matches!(result, Err(api::Error::InsufficientPrivileges)));
+        Ok(())
+    }
+
     #[tokio::test]
     #[serial]
     async fn update() -> ApiResult<()> {
@@ -522,4 +946,100 @@ mod test {
         assert_eq!(new_upload_count, upload_count);
         Ok(())
     }
+
+    #[tokio::test]
+    #[serial]
+    async fn change_password() -> ApiResult<()> {
+        const BODY: &str = "user/change_password.json";
+        let get_password_hash = |conn: &mut PgConnection| -> QueryResult<String> {
+            user::table
+                .select(user::password_hash)
+                .filter(user::name.eq("administrator"))
+                .first(conn)
+        };
+
+        verify_status_with_body("POST /user/regular_user/password", BODY, StatusCode::FORBIDDEN).await?;
+        verify_status_with_body(
+            "POST /user/administrator/password",
+            "user/change_password_incorrect.json",
+            StatusCode::FORBIDDEN,
+        )
+        .await?;
+
+        let mut conn = get_connection()?;
+        let password_hash = get_password_hash(&mut conn)?;
+        assert_eq!(password_hash, TEST_HASH);
+
+        verify_status_with_body("POST /user/administrator/password", BODY, StatusCode::OK).await?;
+
+        let password_hash = get_password_hash(&mut conn)?;
+        assert!(password::is_valid_password(&password_hash, "new_password"));
+        assert!(!password::is_valid_password(&password_hash, TEST_PASSWORD));
+        Ok(reset_database())
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn update_rank() -> ApiResult<()> {
+        const NAME: &str = "restricted_user";
+        verify_query(&format!("PUT /user/{NAME}/rank/?{FIELDS}"), "user/update_rank.json").await?;
+
+        let mut conn = get_connection()?;
+        let get_user_id = |conn: &mut PgConnection, name: &str| -> QueryResult<i64> {
+            user::table.select(user::id).filter(user::name.eq(name)).first(conn)
+        };
+        let user_id = get_user_id(&mut conn, NAME)?;
+        let administrator_id = get_user_id(&mut conn, "administrator")?;
+        let audit_entry: (Option<i64>, UserRank, UserRank) = user_audit::table
+            .select((user_audit::changed_by, user_audit::old_rank, user_audit::new_rank))
+            .filter(user_audit::user_id.eq(user_id))
+            .order(user_audit::id.desc())
+            .first(&mut conn)?;
+        assert_eq!(audit_entry, (Some(administrator_id), UserRank::Restricted, UserRank::Power));
+
+        // Setting the same rank again isn't a change worth recording
+        verify_query(&format!("PUT /user/{NAME}/rank/?{FIELDS}"), "user/update_rank.json").await?;
+        let audit_entry_count: i64 = user_audit::table
+            .filter(user_audit::user_id.eq(user_id))
+            .filter(user_audit::new_rank.eq(UserRank::Power))
+            .count()
+            .first(&mut conn)?;
+        assert_eq!(audit_entry_count, 1);
+        Ok(reset_database())
+    }
+
+    #[tokio::test]
+    #[serial]
+    async fn verify_email() -> ApiResult<()> {
+        const NAME: &str = "power_user";
+        let get_verification = |conn: &mut PgConnection| -> QueryResult<(bool, Option<Uuid>)> {
+            user::table
+                .select((user::email_verified, user::email_verification_token))
+                .filter(user::name.eq(NAME))
+                .first(conn)
+        };
+
+        let mut conn = get_connection()?;
+        let token = Uuid::new_v4();
+        diesel::update(user::table.filter(user::name.eq(NAME)))
+            .set(user::email_verification_token.eq(token))
+            .execute(&mut conn)?;
+
+        let wrong_token = Uuid::new_v4();
+        let query = format!("GET /user/{NAME}/verify/{wrong_token}");
+        verify_status_with_user(NAME, &query, StatusCode::UNAUTHORIZED).await;
+        assert_eq!(get_verification(&mut conn)?, (false, Some(token)));
+
+        let query = format!("GET /user/{NAME}/verify/{token}");
+        verify_status_with_user(NAME, &query, StatusCode::OK).await;
+        assert_eq!(get_verification(&mut conn)?, (true, None));
+
+        // Tokens can only be used once
+        verify_status_with_user(NAME, &query, StatusCode::UNAUTHORIZED).await;
+
+        diesel::update(user::table.filter(user::name.eq(NAME)))
+            .set(user::email_verified.eq(false))
+            .execute(&mut conn)?;
+        Ok(())
+    }
 }
@@ -9,6 +9,7 @@ use crate::time::DateTime;
 use crate::{api, config, db, resource};
 use diesel::prelude::*;
 use serde::Deserialize;
+use utoipa::ToSchema;
 use uuid::Uuid;
 use warp::{Filter, Rejection, Reply};
 
@@ -19,18 +20,24 @@ pub fn routes() -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone
         .and(api::resource_query())
         .map(list)
         .map(api::Reply::from);
+    let get = warp::get()
+        .and(api::auth())
+        .and(warp::path!("user-token" / String / Uuid))
+        .and(api::resource_query())
+        .map(get)
+        .map(api::Reply::from);
     let create = warp::post()
         .and(api::auth())
         .and(warp::path!("user-token" / String))
         .and(api::resource_query())
-        .and(warp::body::json())
+        .and(api::json_body())
         .map(create)
         .map(api::Reply::from);
     let update = warp::put()
         .and(api::auth())
         .and(warp::path!("user-token" / String / Uuid))
         .and(api::resource_query())
-        .and(warp::body::json())
+        .and(api::json_body())
         .map(update)
         .map(api::Reply::from);
     let delete = warp::delete()
@@ -39,9 +46,16 @@ pub fn routes() -> impl Filter<Extract = impl Reply, Error = Rejection> + Clone
         .map(delete)
         .map(api::Reply::from);
 
-    list.or(create).or(update).or(delete)
+    list.or(get).or(create).or(update).or(delete)
 }
 
+#[utoipa::path(
+    get,
+    path = "/user-tokens/{name}",
+    summary = "Lists the tokens of a user",
+    params(("name" = String, Path), ResourceParams),
+    responses((status = 200, body = UnpagedResponse<UserTokenInfo>))
+)]
 fn list(auth: AuthResult, username: String, params: ResourceParams) -> ApiResult<UnpagedResponse<UserTokenInfo>> {
     let client = auth?;
     let username = percent_encoding::percent_decode_str(&username).decode_utf8()?;
@@ -52,11 +66,13 @@ fn list(auth: AuthResult, username: String, params: ResourceParams) -> ApiResult
             .filter(user::name.eq(&username))
             .first(conn)?;
 
-        let required_rank = match client.id == Some(user_id) {
-            true => config::privileges().user_token_list_self,
-            false => config::privileges().user_token_list_any,
-        };
-        api::verify_privilege(client, required_rank)?;
+        let privileges = config::privileges();
+        api::verify_self_or_any_privilege(
+            client,
+            Some(user_id),
+            privileges.user_token_list_self,
+            privileges.user_token_list_any,
+        )?;
 
         user_token::table
             .filter(user_token::user_id.eq(user_id))
@@ -73,15 +89,59 @@ fn list(auth: AuthResult, username: String, params: ResourceParams) -> ApiResult
     Ok(UnpagedResponse { results })
 }
 
-#[derive(Deserialize)]
+#[utoipa::path(
+    get,
+    path = "/user-token/{name}/{token}",
+    summary = "Gets a user token",
+    params(("name" = String, Path), ("token" = Uuid, Path), ResourceParams),
+    responses((status = 200, body = UserTokenInfo))
+)]
+fn get(auth: AuthResult, username: String, token: Uuid, params: ResourceParams) -> ApiResult<UserTokenInfo> {
+    let client = auth?;
+    let username = percent_encoding::percent_decode_str(&username).decode_utf8()?;
+    let fields = resource::create_table(params.fields()).map_err(Box::from)?;
+    let (avatar_style, user_token) = db::get_connection()?.transaction(|conn| {
+        let (user_id, avatar_style): (i64, AvatarStyle) = user::table
+            .select((user::id, user::avatar_style))
+            .filter(user::name.eq(&username))
+            .first(conn)?;
+
+        let privileges = config::privileges();
+        api::verify_self_or_any_privilege(
+            client,
+            Some(user_id),
+            privileges.user_token_list_self,
+            privileges.user_token_list_any,
+        )?;
+
+        user_token::table
+            .find(token)
+            .filter(user_token::user_id.eq(user_id))
+            .first(conn)
+            .map(|user_token| (avatar_style, user_token))
+            .map_err(api::Error::from)
+    })?;
+    Ok(UserTokenInfo::new(MicroUser::new(username.into(), avatar_style), user_token, &fields))
+}
+
+#[derive(Deserialize, ToSchema)]
 #[serde(deny_unknown_fields)]
 #[serde(rename_all = "camelCase")]
+#[schema(as = UserTokenCreateBody)]
 struct CreateBody {
     enabled: bool,
     note: Option<String>,
     expiration_time: Option<DateTime>,
 }
 
+#[utoipa::path(
+    post,
+    path = "/user-token/{name}",
+    summary = "Creates a user token",
+    params(("name" = String, Path), ResourceParams),
+    request_body = CreateBody,
+    responses((status = 200, body = UserTokenInfo))
+)]
 fn create(auth: AuthResult, username: String, params: ResourceParams, body: CreateBody) -> ApiResult<UserTokenInfo> {
     let client = auth?;
     let username = percent_encoding::percent_decode_str(&username).decode_utf8()?;
@@ -94,11 +154,13 @@ fn create(auth: AuthResult, username: String, params: ResourceParams, body: Crea
             .filter(user::name.eq(&username))
             .first(conn)?;
 
-        let required_rank = match client.id == Some(user_id) {
-            true => config::privileges().user_token_create_self,
-            false => config::privileges().user_token_create_any,
-        };
-        api::verify_privilege(client, required_rank)?;
+        let privileges = config::privileges();
+        api::verify_self_or_any_privilege(
+            client,
+            Some(user_id),
+            privileges.user_token_create_self,
+            privileges.user_token_create_any,
+        )?;
 
         // Delete any expired or disabled tokens owned by user
         let current_time = DateTime::now();
@@ -127,9 +189,10 @@ fn create(auth: AuthResult, username: String, params: ResourceParams, body: Crea
     Ok(UserTokenInfo::new(MicroUser::new(username.into(), avatar_style), user_token, &fields))
 }
 
-#[derive(Deserialize)]
+#[derive(Deserialize, ToSchema)]
 #[serde(deny_unknown_fields)]
 #[serde(rename_all = "camelCase")]
+#[schema(as = UserTokenUpdateBody)]
 struct UpdateBody {
     version: DateTime,
     enabled: Option<bool>,
@@ -138,6 +201,14 @@ struct UpdateBody {
     expiration_time: Option<Option<DateTime>>,
 }
 
+#[utoipa::path(
+    put,
+    path = "/user-token/{name}/{token}",
+    summary = "Updates a user token",
+    params(("name" = String, Path), ("token" = Uuid, Path), ResourceParams),
+    request_body = UpdateBody,
+    responses((status = 200, body = UserTokenInfo))
+)]
 fn update(
     auth: AuthResult,
     username: String,
@@ -156,13 +227,18 @@ fn update(
             .filter(user::name.eq(&username))
             .first(conn)?;
 
-        let required_rank = match client.id == Some(user_id) {
-            true => config::privileges().user_token_edit_self,
-            false => config::privileges().user_token_edit_any,
-        };
-        api::verify_privilege(client, required_rank)?;
+        let privileges = config::privileges();
+        api::verify_self_or_any_privilege(
+            client,
+            Some(user_id),
+            privileges.user_token_edit_self,
+            privileges.user_token_edit_any,
+        )?;
 
-        let mut user_token: UserToken = user_token::table.find(token).first(conn)?;
+        let mut user_token: UserToken = user_token::table
+            .find(token)
+            .filter(user_token::user_id.eq(user_id))
+            .first(conn)?;
         api::verify_version(user_token.last_edit_time, body.version)?;
 
         if let Some(enabled) = body.enabled {
@@ -182,6 +258,13 @@ fn update(
     Ok(UserTokenInfo::new(MicroUser::new(username.into(), avatar_style), updated_user_token, &fields))
 }
 
+#[utoipa::path(
+    delete,
+    path = "/user-token/{name}/{token}",
+    summary = "Deletes a user token",
+    params(("name" = String, Path), ("token" = Uuid, Path)),
+    responses((status = 200, description = "User token was deleted"))
+)]
 fn delete(auth: AuthResult, username: String, token: Uuid) -> ApiResult<()> {
     let client = auth?;
     let username = percent_encoding::percent_decode_str(&username).decode_utf8()?;
@@ -193,11 +276,13 @@ fn delete(auth: AuthResult, username: String, token: Uuid) -> ApiResult<()> {
             .filter(user_token::id.eq(token))
             .first(conn)?;
 
-        let required_rank = match client.id == Some(user_token_owner) {
-            true => config::privileges().user_token_delete_self,
-            false => config::privileges().user_token_delete_any,
-        };
-        api::verify_privilege(client, required_rank)?;
+        let privileges = config::privileges();
+        api::verify_self_or_any_privilege(
+            client,
+            Some(user_token_owner),
+            privileges.user_token_delete_self,
+            privileges.user_token_delete_any,
+        )?;
 
         diesel::delete(user_token::table.find(token)).execute(conn)?;
         Ok(())
@@ -214,6 +299,7 @@ mod test {
     use diesel::prelude::*;
     use serial_test::{parallel, serial};
     use uuid::Uuid;
+    use warp::http::StatusCode;
 
     // Exclude fields that involve token, creation_time, last_edit_time, or last_usage_time
     const FIELDS: &str = "&fields=user,note,enabled,expirationTime";
@@ -225,6 +311,17 @@ mod test {
         verify_query(&format!("GET /user-tokens/{USER}/?{FIELDS}"), "user_token/list.json").await
     }
 
+    #[tokio::test]
+    #[parallel]
+    async fn get() -> ApiResult<()> {
+        const USER: &str = "administrator";
+        verify_query(&format!("GET /user-token/{USER}/{TEST_TOKEN}/?{FIELDS}"), "user_token/get.json").await?;
+
+        // Tokens can only be looked up through their owner
+        verify_status_with_user(USER, &format!("GET /user-token/moderator/{TEST_TOKEN}"), StatusCode::NOT_FOUND).await;
+        Ok(())
+    }
+
     #[tokio::test]
     #[serial]
     async fn create() -> ApiResult<()> {
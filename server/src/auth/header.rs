@@ -2,6 +2,8 @@ use crate::model::enums::UserRank;
 use crate::schema::{user, user_token};
 use crate::time::DateTime;
 use crate::{auth, db};
+use argon2::password_hash::SaltString;
+use argon2::password_hash::rand_core::OsRng;
 use base64::DecodeError;
 use base64::prelude::*;
 use diesel::prelude::*;
@@ -80,9 +82,30 @@ fn basic_access_authentication(credentials: &str) -> Result<Client, Authenticati
         .first(&mut conn)
         .optional()?
         .ok_or(AuthenticationError::UsernamePasswordMismatch)?;
-    auth::password::is_valid_password(&password_hash, &password)
-        .then_some(Client::new(Some(user_id), rank))
-        .ok_or(AuthenticationError::UsernamePasswordMismatch)
+    if !auth::password::is_valid_password(&password_hash, &password) {
+        return Err(AuthenticationError::UsernamePasswordMismatch);
+    }
+    if auth::password::needs_rehash(&password_hash) {
+        rehash_password(&mut conn, user_id, &password);
+    }
+    Ok(Client::new(Some(user_id), rank))
+}
+
+/// Replaces the password hash of the user with `user_id` with one that uses the configured
+/// hashing parameters. Failures are only logged, as the user has already been authenticated.
+fn rehash_password(conn: &mut PgConnection, user_id: i64, password: &str) {
+    let salt = SaltString::generate(&mut OsRng);
+    let result = auth::password::hash_password(password, &salt)
+        .map_err(|err| err.to_string())
+        .and_then(|hash| {
+            diesel::update(user::table.find(user_id))
+                .set((user::password_salt.eq(salt.as_str()), user::password_hash.eq(hash)))
+                .execute(conn)
+                .map_err(|err| err.to_string())
+        });
+    if let Err(err) = result {
+        eprintln!("ERROR: Cannot upgrade password hash of user {user_id} for reason: {err}");
+    }
 }
 
 /// Checks that the given `credentials` are of the form "username:token"
@@ -13,6 +13,19 @@ pub fn hash_password(password: &str, salt: &SaltString) -> Result<String, HashEr
     Ok(password_hash.to_string())
 }
 
+/// Returns if `password_hash` was computed with different cost parameters than the ones
+/// that are currently configured, in which case it should be replaced with a new hash.
+pub fn needs_rehash(password_hash: &str) -> bool {
+    let current_params = ARGON_CONTEXT.params();
+    PasswordHash::new(password_hash)
+        .and_then(|parsed_hash| Params::try_from(&parsed_hash))
+        .is_ok_and(|params| {
+            params.m_cost() != current_params.m_cost()
+                || params.t_cost() != current_params.t_cost()
+                || params.p_cost() != current_params.p_cost()
+        })
+}
+
 /// Returns if the given `user` and `password` match.
 pub fn is_valid_password(password_hash: &str, password: &str) -> bool {
     PasswordHash::new(password_hash)
@@ -25,7 +38,7 @@ static ARGON_CONTEXT: LazyLock<Argon2> = LazyLock::new(|| {
         config::get().password_secret.as_bytes(),
         Algorithm::default(),
         Version::default(),
-        Params::default(),
+        config::password_hashing().params().unwrap(),
     )
     .unwrap()
 });
@@ -40,4 +53,17 @@ mod test {
         assert!(is_valid_password(TEST_HASH, TEST_PASSWORD));
         assert!(!is_valid_password(TEST_HASH, "wrong_password"));
     }
+
+    #[test]
+    fn needs_rehash() {
+        let salt = SaltString::encode_b64(TEST_SALT.as_bytes()).unwrap();
+        let current_hash = super::hash_password(TEST_PASSWORD, &salt).unwrap();
+        assert!(!super::needs_rehash(&current_hash));
+
+        let weak_params = Params::new(Params::MIN_M_COST, Params::MIN_T_COST, Params::MIN_P_COST, None).unwrap();
+        let weak_context = Argon2::new(Algorithm::default(), Version::default(), weak_params);
+        let weak_hash = weak_context.hash_password(TEST_PASSWORD.as_bytes(), &salt).unwrap();
+        assert!(super::needs_rehash(&weak_hash.to_string()));
+        assert!(!super::needs_rehash("not a hash"));
+    }
 }
@@ -1,13 +1,19 @@
 use crate::db;
-use crate::model::enums::UserRank;
+use crate::model::enums::{ChecksumAlgorithm, PostSafety, UserRank};
 use crate::string::SmallString;
+use argon2::Params;
+use cron::Schedule;
+use image::imageops::FilterType;
 use lettre::message::Mailbox;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
 use std::path::{Path, PathBuf};
+use std::str::FromStr;
 use std::sync::LazyLock;
 use strum::Display;
+use utoipa::ToSchema;
+use warp::http::HeaderValue;
 
 #[derive(Debug, Display, Clone, Copy)]
 #[strum(serialize_all = "lowercase")]
@@ -22,12 +28,199 @@ pub enum RegexType {
     Password,
 }
 
+#[derive(Clone, Copy, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ResizeFilter {
+    Nearest,
+    Triangle,
+    CatmullRom,
+    Gaussian,
+    Lanczos3,
+}
+
+impl From<ResizeFilter> for FilterType {
+    fn from(value: ResizeFilter) -> Self {
+        match value {
+            ResizeFilter::Nearest => Self::Nearest,
+            ResizeFilter::Triangle => Self::Triangle,
+            ResizeFilter::CatmullRom => Self::CatmullRom,
+            ResizeFilter::Gaussian => Self::Gaussian,
+            ResizeFilter::Lanczos3 => Self::Lanczos3,
+        }
+    }
+}
+
+/// How post thumbnails are fit into the configured dimensions.
+#[derive(Clone, Copy, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ThumbnailFit {
+    /// Fills the dimensions, cropping whatever doesn't fit.
+    Fill,
+    /// Shrinks the thumbnail along one dimension so that nothing is cropped.
+    Fit,
+    /// Like `Fit`, but pads the thumbnail to the full dimensions with `pad_color`.
+    Pad,
+}
+
+impl Default for ThumbnailFit {
+    fn default() -> Self {
+        Self::Fill
+    }
+}
+
+/// Which image of a video its thumbnail is made from.
+#[derive(Clone, Copy, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ThumbnailStrategy {
+    /// Uses the cover art embedded in the video, or the first frame if it has none.
+    Cover,
+    /// Uses the first frame.
+    FirstFrame,
+    /// Uses the frame at the given position through the video, from 0 (start) to 1 (end).
+    MiddleFrame(f32),
+}
+
+impl Default for ThumbnailStrategy {
+    fn default() -> Self {
+        Self::FirstFrame
+    }
+}
+
 #[derive(Deserialize)]
 pub struct Thumbnails {
     pub avatar_width: u32,
     pub avatar_height: u32,
     pub post_width: u32,
     pub post_height: u32,
+    #[serde(default = "default_resize_filter")]
+    pub resize_filter: ResizeFilter,
+    #[serde(default = "default_quality")]
+    pub quality: u8,
+    #[serde(default)]
+    pub fit: ThumbnailFit,
+    #[serde(default)]
+    pub pad_color: [u8; 3],
+    #[serde(default = "default_background_color")]
+    pub background_color: [u8; 3],
+    #[serde(default)]
+    pub video_strategy: ThumbnailStrategy,
+}
+
+// Defaults so that configs written before these fields existed still load
+fn default_resize_filter() -> ResizeFilter {
+    ResizeFilter::Gaussian
+}
+
+fn default_quality() -> u8 {
+    80
+}
+
+fn default_background_color() -> [u8; 3] {
+    [255, 255, 255]
+}
+
+#[derive(Serialize, Deserialize, ToSchema)]
+#[serde(default, deny_unknown_fields)]
+#[serde(rename_all(serialize = "camelCase"))]
+#[schema(rename_all = "camelCase")]
+pub struct Pagination {
+    pub default_post_page_size: i64,
+    pub max_post_page_size: i64,
+    pub default_comment_page_size: i64,
+    pub max_comment_page_size: i64,
+    pub skip_total_count: bool,
+}
+
+// Optional so that configs written before the section existed still load
+impl Default for Pagination {
+    fn default() -> Self {
+        Self {
+            default_post_page_size: 100,
+            max_post_page_size: 1000,
+            default_comment_page_size: 100,
+            max_comment_page_size: 1000,
+            skip_total_count: false,
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Database {
+    pub max_pool_size: Option<u32>,
+    pub connection_timeout: u64,
+    pub idle_timeout: Option<u64>,
+    pub deadlock_retries: u32,
+}
+
+// Optional so that configs written before the section existed still load
+impl Default for Database {
+    fn default() -> Self {
+        Self {
+            max_pool_size: None,
+            connection_timeout: 30,
+            idle_timeout: None,
+            deadlock_retries: 3,
+        }
+    }
+}
+
+/// Cost parameters of the Argon2 password hash.
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PasswordHashing {
+    pub m_cost: u32,
+    pub t_cost: u32,
+    pub p_cost: u32,
+}
+
+impl Default for PasswordHashing {
+    fn default() -> Self {
+        Self {
+            m_cost: Params::DEFAULT_M_COST,
+            t_cost: Params::DEFAULT_T_COST,
+            p_cost: Params::DEFAULT_P_COST,
+        }
+    }
+}
+
+impl PasswordHashing {
+    pub fn params(&self) -> Result<Params, argon2::Error> {
+        Params::new(self.m_cost, self.t_cost, self.p_cost, None)
+    }
+}
+
+/// Maximum sizes of request bodies in bytes.
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BodyLimits {
+    pub json: u64,
+    pub upload: u64,
+    pub reverse_search: u64,
+}
+
+impl Default for BodyLimits {
+    fn default() -> Self {
+        Self {
+            json: 1024_u64.pow(2),
+            upload: 4 * 1024_u64.pow(3),
+            reverse_search: 64 * 1024_u64.pow(2),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Security {
+    pub content_security_policy: SmallString,
+}
+
+impl Default for Security {
+    fn default() -> Self {
+        Self {
+            content_security_policy: SmallString::new("default-src 'none'"),
+        }
+    }
 }
 
 #[derive(Clone, Serialize, Deserialize)]
@@ -37,7 +230,7 @@ pub struct SmtpInfo {
     pub from: Mailbox,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, ToSchema)]
 pub struct Privileges {
     pub user_create_self: UserRank,
     pub user_create_any: UserRank,
@@ -71,14 +264,24 @@ pub struct Privileges {
     pub post_reverse_search: UserRank,
     pub post_view: UserRank,
     pub post_view_featured: UserRank,
+    #[serde(default = "default_post_view_hidden")]
+    pub post_view_hidden: UserRank,
+    #[serde(default = "default_post_view_any_safety")]
+    pub post_view_any_safety: UserRank,
+    #[serde(default = "default_post_view_exif")]
+    pub post_view_exif: UserRank,
     pub post_edit_content: UserRank,
     pub post_edit_flag: UserRank,
+    #[serde(default = "default_post_edit_flag_hidden")]
+    pub post_edit_flag_hidden: UserRank,
     pub post_edit_note: UserRank,
     pub post_edit_relation: UserRank,
     pub post_edit_safety: UserRank,
     pub post_edit_source: UserRank,
     pub post_edit_tag: UserRank,
     pub post_edit_thumbnail: UserRank,
+    #[serde(default = "default_post_lock")]
+    pub post_lock: UserRank,
     pub post_feature: UserRank,
     pub post_delete: UserRank,
     pub post_score: UserRank,
@@ -134,31 +337,45 @@ pub struct Privileges {
     pub comment_list: UserRank,
     pub comment_view: UserRank,
     pub comment_score: UserRank,
+    #[serde(default = "default_comment_pin")]
+    pub comment_pin: UserRank,
 
     pub snapshot_list: UserRank,
 
+    #[serde(default = "default_signature_recompute")]
+    pub signature_recompute: UserRank,
+
+    #[serde(default = "default_statistics_refresh")]
+    pub statistics_refresh: UserRank,
+
     pub upload_create: UserRank,
     pub upload_use_downloader: UserRank,
 }
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, ToSchema)]
 #[serde(deny_unknown_fields)]
 #[serde(rename_all(serialize = "camelCase"))]
+#[schema(rename_all = "camelCase")]
 pub struct PublicInfo {
     pub name: SmallString,
     pub default_user_rank: UserRank,
     pub enable_safety: bool,
     pub contact_email: Option<SmallString>,
-    #[serde(skip_deserializing)]
+    // Always overwritten on load, but must not be skipped so the field appears in the API description
+    #[serde(default)]
     pub can_send_mails: bool,
     #[serde(with = "serde_regex")]
     #[serde(rename(serialize = "userNameRegex"))]
+    #[schema(value_type = String, rename = "userNameRegex")]
     pub username_regex: Regex,
     #[serde(with = "serde_regex")]
+    #[schema(value_type = String)]
     pub password_regex: Regex,
     #[serde(with = "serde_regex")]
+    #[schema(value_type = String)]
     pub tag_name_regex: Regex,
     #[serde(with = "serde_regex")]
+    #[schema(value_type = String)]
     pub tag_category_name_regex: Regex,
     pub privileges: Privileges,
 }
@@ -168,28 +385,145 @@ pub struct PublicInfo {
 pub struct Config {
     data_dir: SmallString,
     pub data_url: SmallString,
+    #[serde(default)]
+    content_base_url: Option<SmallString>,
+    #[serde(default)]
+    thumbnail_base_url: Option<SmallString>,
     pub password_secret: SmallString,
     pub content_secret: SmallString,
     pub domain: Option<SmallString>,
     pub delete_source_files: bool,
     pub post_similarity_threshold: f64,
+    #[serde(default = "default_similar_posts_limit")]
+    pub similar_posts_limit: usize,
+    #[serde(default = "default_similar_posts_threshold")]
+    pub similar_posts_threshold: f64,
+    #[serde(default = "default_max_restricted_safety")]
+    pub max_restricted_safety: PostSafety,
+    #[serde(default)]
+    pub preferred_checksum_algorithm: ChecksumAlgorithm,
+    #[serde(default)]
+    pub verify_emails: bool,
+    #[serde(default)]
+    pub prevent_self_favorite: bool,
+    #[serde(default = "default_shutdown_timeout")]
+    pub shutdown_timeout_seconds: u64,
+    #[serde(default = "default_view_flush_interval")]
+    pub view_flush_interval_seconds: u64,
+    #[serde(default)]
+    statistics_refresh_schedule: Option<SmallString>,
     #[serde(with = "serde_regex")]
     pub pool_name_regex: Regex,
     #[serde(with = "serde_regex")]
     pub pool_category_regex: Regex,
     pub thumbnails: Thumbnails,
+    #[serde(default)]
+    pub pagination: Pagination,
+    #[serde(default)]
+    pub database: Database,
+    #[serde(default)]
+    pub body_limits: BodyLimits,
+    #[serde(default)]
+    pub password_hashing: PasswordHashing,
+    #[serde(default)]
+    pub security: Security,
     pub smtp: Option<SmtpInfo>,
     pub public_info: PublicInfo,
 }
 
+// Defaults that keep showing all posts to everyone for configs written before these options existed
+fn default_max_restricted_safety() -> PostSafety {
+    PostSafety::Unsafe
+}
+
+fn default_post_view_any_safety() -> UserRank {
+    UserRank::Anonymous
+}
+
+fn default_post_view_hidden() -> UserRank {
+    UserRank::Moderator
+}
+
+fn default_post_view_exif() -> UserRank {
+    UserRank::Anonymous
+}
+
+fn default_post_edit_flag_hidden() -> UserRank {
+    UserRank::Moderator
+}
+
+fn default_post_lock() -> UserRank {
+    UserRank::Moderator
+}
+
+fn default_comment_pin() -> UserRank {
+    UserRank::Moderator
+}
+
+fn default_signature_recompute() -> UserRank {
+    UserRank::Administrator
+}
+
+fn default_statistics_refresh() -> UserRank {
+    UserRank::Administrator
+}
+
+fn default_shutdown_timeout() -> u64 {
+    30
+}
+
+fn default_view_flush_interval() -> u64 {
+    60
+}
+
+fn default_similar_posts_limit() -> usize {
+    10
+}
+
+fn default_similar_posts_threshold() -> f64 {
+    0.55
+}
+
 pub fn get() -> &'static Config {
     &CONFIG
 }
 
+/// Schedule on which database statistics are refreshed in the background, if any.
+pub fn statistics_refresh_schedule() -> Option<&'static Schedule> {
+    static SCHEDULE: LazyLock<Option<Schedule>> = LazyLock::new(|| {
+        // Validity of the schedule is checked when the config is loaded
+        CONFIG
+            .statistics_refresh_schedule
+            .as_ref()
+            .map(|schedule| Schedule::from_str(schedule).unwrap())
+    });
+    SCHEDULE.as_ref()
+}
+
 pub fn smtp() -> Option<&'static SmtpInfo> {
     CONFIG.smtp.as_ref()
 }
 
+pub fn pagination() -> &'static Pagination {
+    &CONFIG.pagination
+}
+
+pub fn database() -> &'static Database {
+    &CONFIG.database
+}
+
+pub fn password_hashing() -> &'static PasswordHashing {
+    &CONFIG.password_hashing
+}
+
+pub fn body_limits() -> &'static BodyLimits {
+    &CONFIG.body_limits
+}
+
+pub fn security() -> &'static Security {
+    &CONFIG.security
+}
+
 pub fn privileges() -> &'static Privileges {
     &CONFIG.public_info.privileges
 }
@@ -211,6 +545,16 @@ pub fn default_rank() -> UserRank {
     CONFIG.public_info.default_user_rank
 }
 
+/// Base of post content urls. Defaults to `data_url`, but can point elsewhere, such as a CDN.
+pub fn content_base_url() -> &'static str {
+    CONFIG.content_base_url.as_deref().unwrap_or(&*CONFIG.data_url)
+}
+
+/// Base of post thumbnail urls. Defaults to `data_url`, but can point elsewhere, such as a CDN.
+pub fn thumbnail_base_url() -> &'static str {
+    CONFIG.thumbnail_base_url.as_deref().unwrap_or(&*CONFIG.data_url)
+}
+
 pub fn data_dir() -> &'static str {
     static DATA_DIR: LazyLock<Cow<str>> = LazyLock::new(|| match std::env::var("DOCKER_DEPLOYMENT") {
         Ok(_) => Cow::Borrowed(&CONFIG.data_dir),
@@ -250,6 +594,45 @@ static CONFIG: LazyLock<Config> = LazyLock::new(|| {
         }
     };
     config.public_info.can_send_mails = config.smtp.is_some();
+
+    let pagination = &config.pagination;
+    if pagination.default_post_page_size > pagination.max_post_page_size {
+        panic!("ERROR: pagination.default_post_page_size must not exceed pagination.max_post_page_size");
+    }
+    if pagination.default_comment_page_size > pagination.max_comment_page_size {
+        panic!("ERROR: pagination.default_comment_page_size must not exceed pagination.max_comment_page_size");
+    }
+    if !(1..=100).contains(&config.thumbnails.quality) {
+        panic!("ERROR: thumbnails.quality must be between 1 and 100");
+    }
+    if let ThumbnailStrategy::MiddleFrame(position) = config.thumbnails.video_strategy {
+        if !(0.0..=1.0).contains(&position) {
+            panic!("ERROR: thumbnails.video_strategy middle-frame position must be between 0 and 1");
+        }
+    }
+    if !(0.0..=1.0).contains(&config.similar_posts_threshold) {
+        panic!("ERROR: similar_posts_threshold must be between 0 and 1");
+    }
+    if let Some(schedule) = &config.statistics_refresh_schedule {
+        if let Err(err) = Schedule::from_str(schedule) {
+            panic!("ERROR: statistics_refresh_schedule is not a valid cron expression: {err}");
+        }
+    }
+    if config.database.max_pool_size == Some(0) {
+        panic!("ERROR: database.max_pool_size must be positive");
+    }
+    if config.database.connection_timeout == 0 {
+        panic!("ERROR: database.connection_timeout must be positive");
+    }
+    if let Err(err) = config.password_hashing.params() {
+        panic!("ERROR: password_hashing parameters are invalid: {err}");
+    }
+    if config.view_flush_interval_seconds == 0 {
+        panic!("ERROR: view_flush_interval_seconds must be positive");
+    }
+    if HeaderValue::from_str(&config.security.content_security_policy).is_err() {
+        panic!("ERROR: security.content_security_policy must be a valid header value");
+    }
     config
 });
 
@@ -265,3 +648,39 @@ fn get_config_path() -> PathBuf {
         [parent_path, Path::new("config.toml")].iter().collect()
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn parse_thumbnails(resize_filter: &str) -> Result<Thumbnails, toml::de::Error> {
+        toml::from_str(&format!(
+            "avatar_width = 300\navatar_height = 300\npost_width = 300\npost_height = 300\nresize_filter = \"{resize_filter}\""
+        ))
+    }
+
+    #[test]
+    fn resize_filter() {
+        for filter in ["nearest", "triangle", "catmull-rom", "gaussian", "lanczos3"] {
+            assert!(parse_thumbnails(filter).is_ok(), "{filter} should be a valid resize filter");
+        }
+        for filter in ["", "bicubic", "Gaussian", "catmull_rom"] {
+            assert!(parse_thumbnails(filter).is_err(), "{filter} should not be a valid resize filter");
+        }
+    }
+
+    #[test]
+    fn video_strategy() {
+        let parse_video_strategy = |video_strategy: &str| -> Result<Thumbnails, toml::de::Error> {
+            toml::from_str(&format!(
+                "avatar_width = 300\navatar_height = 300\npost_width = 300\npost_height = 300\nvideo_strategy = {video_strategy}"
+            ))
+        };
+        for strategy in ["\"cover\"", "\"first-frame\"", "{ middle-frame = 0.5 }"] {
+            assert!(parse_video_strategy(strategy).is_ok(), "{strategy} should be a valid video strategy");
+        }
+        for strategy in ["\"\"", "\"middle-frame\"", "\"last-frame\"", "{ cover = 0.5 }"] {
+            assert!(parse_video_strategy(strategy).is_err(), "{strategy} should not be a valid video strategy");
+        }
+    }
+}
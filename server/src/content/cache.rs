@@ -1,9 +1,9 @@
 use crate::api::ApiResult;
 use crate::content::signature::COMPRESSED_SIGNATURE_LEN;
 use crate::content::thumbnail::ThumbnailType;
-use crate::content::{FileContents, decode, hash, signature, thumbnail};
+use crate::content::{self, FileContents, decode, hash, signature, thumbnail};
 use crate::filesystem;
-use crate::model::enums::{MimeType, PostFlag, PostFlags, PostType};
+use crate::model::enums::{ChecksumAlgorithm, MimeType, PostFlag, PostFlags, PostType};
 use image::DynamicImage;
 use std::collections::VecDeque;
 use std::sync::{LazyLock, Mutex, MutexGuard};
@@ -13,6 +13,7 @@ use std::sync::{LazyLock, Mutex, MutexGuard};
 pub struct CachedProperties {
     pub token: String,
     pub checksum: Vec<u8>,
+    pub checksum_algorithm: ChecksumAlgorithm,
     pub md5_checksum: [u8; 16],
     pub signature: [i64; COMPRESSED_SIGNATURE_LEN],
     pub thumbnail: DynamicImage,
@@ -97,9 +98,9 @@ fn get_cache_guard() -> MutexGuard<'static, RingCache> {
 /// Computes content properties without storing them in cache.
 fn compute_properties_no_cache(token: String) -> ApiResult<CachedProperties> {
     let temp_path = filesystem::temporary_upload_filepath(&token);
-    let file_size = std::fs::metadata(&temp_path)?.len();
-    let data = std::fs::read(&temp_path)?;
-    let checksum = hash::compute_checksum(&data);
+    let data = content::read_temporary_upload(&token)?;
+    let file_size = data.len() as u64;
+    let (checksum, checksum_algorithm) = hash::compute_checksum(&data);
     let md5_checksum = hash::compute_md5_checksum(&data);
 
     let (_uuid, extension) = token.split_once('.').unwrap();
@@ -122,6 +123,7 @@ fn compute_properties_no_cache(token: String) -> ApiResult<CachedProperties> {
     Ok(CachedProperties {
         token,
         checksum,
+        checksum_algorithm,
         md5_checksum,
         signature: signature::compute(&image),
         thumbnail: thumbnail::create(&image, ThumbnailType::Post),
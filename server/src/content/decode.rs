@@ -1,4 +1,5 @@
 use crate::api::ApiResult;
+use crate::config::ThumbnailStrategy;
 use crate::content::{FileContents, flash};
 use crate::model::enums::PostType;
 use crate::{api, config};
@@ -9,11 +10,12 @@ use std::path::Path;
 use swf::Tag;
 use video_rs::Decoder;
 use video_rs::ffmpeg::format::Pixel;
+use video_rs::ffmpeg::format::stream::Disposition;
 use video_rs::ffmpeg::media::Type;
 
 /// Returns a representative image for the given content.
 /// For images, this is simply the decoded image.
-/// For videos, it is the first frame of the video.
+/// For videos, it is chosen according to the configured video thumbnail strategy.
 /// For Flash media, it is the largest image that can be decoded from the Flash tags.
 pub fn representative_image(file_contents: &FileContents, file_path: &Path) -> ApiResult<DynamicImage> {
     match PostType::from(file_contents.mime_type) {
@@ -24,7 +26,7 @@ pub fn representative_image(file_contents: &FileContents, file_path: &Path) -> A
                 .expect("Mime type should be convertable to image format");
             image(&file_contents.data, image_format).map_err(api::Error::from)
         }
-        PostType::Video => video_frame(file_path)
+        PostType::Video => video_frame(file_path, config::get().thumbnails.video_strategy)
             .map_err(api::Error::from)
             .and_then(|frame| frame.ok_or(api::Error::EmptyVideo)),
         PostType::Flash => flash_image(file_path).and_then(|frame| frame.ok_or(api::Error::EmptySwf)),
@@ -67,9 +69,21 @@ pub fn image(bytes: &[u8], format: ImageFormat) -> ImageResult<DynamicImage> {
     reader.decode()
 }
 
-/// Decodes first frame of video contents.
-fn video_frame(path: &Path) -> Result<Option<DynamicImage>, video_rs::Error> {
+/// Decodes the frame of video contents picked by `strategy`.
+fn video_frame(path: &Path, strategy: ThumbnailStrategy) -> Result<Option<DynamicImage>, video_rs::Error> {
+    if let ThumbnailStrategy::Cover = strategy {
+        if let Some(cover) = video_cover(path)? {
+            return Ok(Some(cover));
+        }
+    }
+
     let mut decoder = Decoder::new(path)?;
+    if let ThumbnailStrategy::MiddleFrame(position) = strategy {
+        // Seeking lands on the closest keyframe before the requested time
+        let duration = decoder.duration()?.as_secs_f64();
+        let timestamp_milliseconds = (duration * f64::from(position) * 1000.0) as i64;
+        decoder.seek(timestamp_milliseconds)?;
+    }
     let frame = decoder.decode_raw()?;
 
     if frame.planes() == 0 {
@@ -87,6 +101,25 @@ fn video_frame(path: &Path) -> Result<Option<DynamicImage>, video_rs::Error> {
     }))
 }
 
+/// Decodes the cover art attached to the video at `path`, if it has any.
+fn video_cover(path: &Path) -> Result<Option<DynamicImage>, video_rs::Error> {
+    let mut input = video_rs::ffmpeg::format::input(path)?;
+    let cover_stream_index = input
+        .streams()
+        .find(|stream| stream.disposition().contains(Disposition::ATTACHED_PIC))
+        .map(|stream| stream.index());
+    let Some(cover_stream_index) = cover_stream_index else {
+        return Ok(None);
+    };
+
+    // Attached pictures are stored as a single packet holding an encoded image
+    let cover = input
+        .packets()
+        .find(|(stream, _)| stream.index() == cover_stream_index)
+        .and_then(|(_, packet)| packet.data().and_then(|data| image::load_from_memory(data).ok()));
+    Ok(cover)
+}
+
 /// Search swf tags for the largest decodable image
 fn flash_image(path: &Path) -> ApiResult<Option<DynamicImage>> {
     let file = File::open(path)?;
@@ -129,7 +162,7 @@ fn flash_image(path: &Path) -> ApiResult<Option<DynamicImage>> {
 
     // Some Flash files only have video frames, which are hard to decode.
     // So, we feed to ffmpeg and see if it can decode a representaive frame.
-    if let Ok(Some(frame)) = video_frame(path) {
+    if let Ok(Some(frame)) = video_frame(path, ThumbnailStrategy::FirstFrame) {
         images.push(frame);
     }
 
@@ -163,3 +196,32 @@ fn rgb24_frame(data: &[u8], width: u32, height: u32, stride: usize) -> DynamicIm
     });
     DynamicImage::ImageRgb8(rgb_image)
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::content::signature;
+    use crate::content::thumbnail::{self, ThumbnailType};
+    use crate::test::*;
+
+    #[test]
+    fn modern_image_formats() -> ImageResult<()> {
+        let png = DynamicImage::ImageRgb8(image::open(image_path("png.png"))?.to_rgb8());
+        let png_signature_cache = signature::cache(&signature::compute(&png));
+
+        for format in [ImageFormat::WebP, ImageFormat::Avif] {
+            let mut encoded = Vec::new();
+            png.write_to(&mut Cursor::new(&mut encoded), format)?;
+
+            let decoded = image(&encoded, format)?;
+            assert_eq!(decoded.width(), png.width());
+            assert_eq!(decoded.height(), png.height());
+            assert!(signature::distance(&png_signature_cache, &signature::compute(&decoded)) < 0.1);
+
+            let thumbnail = thumbnail::create(&decoded, ThumbnailType::Post);
+            assert_eq!(thumbnail.width(), config::get().thumbnails.post_width);
+            assert_eq!(thumbnail.height(), config::get().thumbnails.post_height);
+        }
+        Ok(())
+    }
+}
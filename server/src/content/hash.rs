@@ -1,9 +1,10 @@
 use crate::filesystem::Directory;
-use crate::model::enums::MimeType;
+use crate::model::enums::{ChecksumAlgorithm, MimeType};
 use crate::{config, filesystem};
 use base64::engine::general_purpose::URL_SAFE_NO_PAD;
 use base64::prelude::*;
-use hmac::digest::CtOutput;
+use hmac::digest::core_api::BlockSizeUser;
+use hmac::digest::{CtOutput, Digest};
 use hmac::{Mac, SimpleHmac};
 use std::path::PathBuf;
 
@@ -26,7 +27,7 @@ impl PostHash {
     }
 
     pub fn content_url(&self, content_type: MimeType) -> String {
-        format!("{}/posts/{}_{}.{}", config::get().data_url, self.post_id, self.hash, content_type.extension())
+        format!("{}/posts/{}_{}.{}", config::content_base_url(), self.post_id, self.hash, content_type.extension())
     }
 
     pub fn thumbnail_url(&self) -> String {
@@ -34,7 +35,7 @@ impl PostHash {
             true => "custom-thumbnails",
             false => "generated-thumbnails",
         };
-        format!("{}/{thumbnail_folder}/{}_{}.jpg", config::get().data_url, self.post_id, self.hash)
+        format!("{}/{thumbnail_folder}/{}_{}.jpg", config::thumbnail_base_url(), self.post_id, self.hash)
     }
 
     pub fn content_path(&self, content_type: MimeType) -> PathBuf {
@@ -71,12 +72,20 @@ pub fn custom_avatar_path(username: &str) -> PathBuf {
     format!("{}/{}.png", filesystem::as_str(Directory::Avatars), username.to_lowercase()).into()
 }
 
-/// Computes a checksum for duplicate detection. Uses raw file data instead of decoded
-/// pixel data because different compression schemes can compress identical pixel data
-/// in different ways.
-pub fn compute_checksum(content: &[u8]) -> Vec<u8> {
-    let hash = hmac_hash(content);
-    hash.into_bytes().to_vec()
+/// Computes a checksum for duplicate detection using the preferred checksum algorithm.
+/// Uses raw file data instead of decoded pixel data because different compression schemes
+/// can compress identical pixel data in different ways.
+pub fn compute_checksum(content: &[u8]) -> (Vec<u8>, ChecksumAlgorithm) {
+    let algorithm = config::get().preferred_checksum_algorithm;
+    (algorithm.compute(content), algorithm)
+}
+
+/// Computes an HMAC of `bytes` using the hash function `D`, keyed with the content secret.
+pub fn keyed_checksum<D: Digest + BlockSizeUser>(bytes: &[u8]) -> Vec<u8> {
+    let mut mac = SimpleHmac::<D>::new_from_slice(config::get().content_secret.as_bytes())
+        .expect("HMAC can take key of any size");
+    mac.update(bytes);
+    mac.finalize().into_bytes().to_vec()
 }
 
 pub fn compute_md5_checksum(content: &[u8]) -> [u8; 16] {
@@ -13,6 +13,7 @@ use crate::content::thumbnail::ThumbnailType;
 use crate::model::enums::MimeType;
 use crate::{api, filesystem};
 use image::DynamicImage;
+use std::io::ErrorKind;
 use url::Url;
 
 /// Stores file contents and mime type of an uploaded file.
@@ -27,9 +28,7 @@ impl FileContents {
         let (_uuid, extension) = token.split_once('.').unwrap();
         let mime_type = MimeType::from_extension(extension)?;
 
-        let temp_path = filesystem::temporary_upload_filepath(token);
-        let data = std::fs::read(&temp_path)?;
-
+        let data = read_temporary_upload(token)?;
         Ok(Self { data, mime_type })
     }
 
@@ -39,6 +38,16 @@ impl FileContents {
     }
 }
 
+/// Reads the file uploaded to the temporary uploads directory under `token`.
+/// A missing file means the upload was cleaned up or never happened, which the client can fix by uploading again.
+pub fn read_temporary_upload(token: &str) -> ApiResult<Vec<u8>> {
+    let temp_path = filesystem::temporary_upload_filepath(token);
+    std::fs::read(temp_path).map_err(|err| match err.kind() {
+        ErrorKind::NotFound => api::Error::ContentTokenExpired,
+        _ => api::Error::from(err),
+    })
+}
+
 /// Contains either the name of a file uploaded to the temporary uploads
 /// directory, a url pointing to a file on the web, or the contents of the
 /// file sent via a multipart request.
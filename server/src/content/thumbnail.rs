@@ -1,5 +1,5 @@
-use crate::config;
-use image::DynamicImage;
+use crate::config::{self, ThumbnailFit};
+use image::{DynamicImage, Rgba, RgbaImage, imageops};
 
 pub enum ThumbnailType {
     Post,
@@ -12,7 +12,9 @@ pub enum ThumbnailCategory {
 }
 
 /// Returns a thumbnail of the given `image`. The size of the thumbnail depends on the `thumbnail_type`
-/// and the thumbnail settings in config.toml.
+/// and the thumbnail settings in config.toml. Avatars always fill their dimensions, while post
+/// thumbnails are fit according to the `fit` setting. Transparent areas are filled with the background
+/// color, as thumbnails are stored without an alpha channel.
 pub fn create(image: &DynamicImage, thumbnail_type: ThumbnailType) -> DynamicImage {
     let thumbnail_width = match thumbnail_type {
         ThumbnailType::Post => config::get().thumbnails.post_width,
@@ -22,5 +24,123 @@ pub fn create(image: &DynamicImage, thumbnail_type: ThumbnailType) -> DynamicIma
         ThumbnailType::Post => config::get().thumbnails.post_height,
         ThumbnailType::Avatar => config::get().thumbnails.avatar_height,
     };
-    image.resize_to_fill(thumbnail_width, thumbnail_height, image::imageops::FilterType::Gaussian)
+    let filter = config::get().thumbnails.resize_filter.into();
+    let fit = match thumbnail_type {
+        ThumbnailType::Post => config::get().thumbnails.fit,
+        ThumbnailType::Avatar => ThumbnailFit::Fill,
+    };
+    match fit {
+        ThumbnailFit::Fill => flatten(image.resize_to_fill(thumbnail_width, thumbnail_height, filter)),
+        ThumbnailFit::Fit => flatten(image.resize(thumbnail_width, thumbnail_height, filter)),
+        ThumbnailFit::Pad => {
+            pad(&flatten(image.resize(thumbnail_width, thumbnail_height, filter)), thumbnail_width, thumbnail_height)
+        }
+    }
+}
+
+/// Returns the dimensions of the thumbnail [create] makes for a post that is `width`x`height`.
+/// Posts without dimensions are assumed to fill the configured dimensions.
+pub fn post_dimensions(width: u32, height: u32) -> (u32, u32) {
+    let thumbnail_width = config::get().thumbnails.post_width;
+    let thumbnail_height = config::get().thumbnails.post_height;
+    match config::get().thumbnails.fit {
+        ThumbnailFit::Fit if width > 0 && height > 0 => {
+            fit_dimensions(width, height, thumbnail_width, thumbnail_height)
+        }
+        _ => (thumbnail_width, thumbnail_height),
+    }
+}
+
+/// Returns the dimensions a `width`x`height` image is resized to when it is fit
+/// within `max_width`x`max_height`. Rounds the same way as [DynamicImage::resize].
+fn fit_dimensions(width: u32, height: u32, max_width: u32, max_height: u32) -> (u32, u32) {
+    let ratio = f64::min(f64::from(max_width) / f64::from(width), f64::from(max_height) / f64::from(height));
+    let fitted_length = |length: u32| std::cmp::max((f64::from(length) * ratio).round() as u32, 1);
+    (fitted_length(width), fitted_length(height))
+}
+
+/// Fills transparent areas of `image` with the configured background color.
+fn flatten(image: DynamicImage) -> DynamicImage {
+    match image.color().has_alpha() {
+        true => composite(&image, image.width(), image.height(), config::get().thumbnails.background_color),
+        false => image,
+    }
+}
+
+/// Centers `image` on a `width`x`height` canvas filled with the configured pad color.
+fn pad(image: &DynamicImage, width: u32, height: u32) -> DynamicImage {
+    composite(image, width, height, config::get().thumbnails.pad_color)
+}
+
+/// Centers `image` on a `width`x`height` canvas filled with `color`.
+/// Transparent pixels of `image` are blended with the canvas, so the result is fully opaque.
+fn composite(image: &DynamicImage, width: u32, height: u32, color: [u8; 3]) -> DynamicImage {
+    let [red, green, blue] = color;
+    let mut canvas = RgbaImage::from_pixel(width, height, Rgba([red, green, blue, u8::MAX]));
+    let x = (width - image.width()) / 2;
+    let y = (height - image.height()) / 2;
+    imageops::overlay(&mut canvas, &image.to_rgba8(), i64::from(x), i64::from(y));
+    DynamicImage::ImageRgba8(canvas)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::test::*;
+    use image::imageops::FilterType;
+    use image::{ImageResult, RgbImage};
+
+    #[test]
+    fn padding() {
+        let image = DynamicImage::ImageRgb8(RgbImage::from_pixel(100, 50, image::Rgb([255, 255, 255])));
+        let padded = pad(&image, 100, 100).to_rgba8();
+        assert_eq!(padded.dimensions(), (100, 100));
+
+        let [red, green, blue] = config::get().thumbnails.pad_color;
+        let pad_pixel = Rgba([red, green, blue, u8::MAX]);
+        assert_eq!(*padded.get_pixel(0, 0), pad_pixel);
+        assert_eq!(*padded.get_pixel(99, 24), pad_pixel);
+        assert_eq!(*padded.get_pixel(0, 25), Rgba([255, 255, 255, u8::MAX]));
+        assert_eq!(*padded.get_pixel(99, 74), Rgba([255, 255, 255, u8::MAX]));
+        assert_eq!(*padded.get_pixel(50, 75), pad_pixel);
+    }
+
+    #[test]
+    fn fitting() -> ImageResult<()> {
+        for (width, height) in [(1000, 500), (500, 1000), (300, 200), (7, 3), (1, 1000), (2000, 2)] {
+            let resized = DynamicImage::new_rgb8(width, height).resize(300, 200, FilterType::Nearest);
+            assert_eq!(fit_dimensions(width, height, 300, 200), (resized.width(), resized.height()));
+        }
+
+        let image = image::open(image_path("lisa-wide.jpg"))?;
+        let thumbnail = create(&image, ThumbnailType::Post);
+        assert_eq!(post_dimensions(image.width(), image.height()), (thumbnail.width(), thumbnail.height()));
+        Ok(())
+    }
+
+    #[test]
+    fn transparency() {
+        let mut image = RgbaImage::from_pixel(2, 1, Rgba([255, 255, 255, u8::MAX]));
+        image.put_pixel(1, 0, Rgba([255, 255, 255, 0]));
+        let flattened = flatten(DynamicImage::ImageRgba8(image)).to_rgba8();
+
+        let [red, green, blue] = config::get().thumbnails.background_color;
+        assert_eq!(*flattened.get_pixel(0, 0), Rgba([255, 255, 255, u8::MAX]));
+        assert_eq!(*flattened.get_pixel(1, 0), Rgba([red, green, blue, u8::MAX]));
+    }
+
+    #[test]
+    fn transparent_png() -> ImageResult<()> {
+        // The left half of the image is opaque red and the right half is fully transparent
+        let image = image::open(image_path("transparent.png"))?;
+        assert!(image.color().has_alpha());
+
+        let thumbnail = create(&image, ThumbnailType::Post).to_rgba8();
+        let (width, height) = thumbnail.dimensions();
+        let [red, green, blue] = config::get().thumbnails.background_color;
+        assert_eq!(*thumbnail.get_pixel(0, height / 2), Rgba([255, 0, 0, u8::MAX]));
+        assert_eq!(*thumbnail.get_pixel(width - 1, height / 2), Rgba([red, green, blue, u8::MAX]));
+        assert!(thumbnail.pixels().all(|pixel| pixel[3] == u8::MAX));
+        Ok(())
+    }
 }
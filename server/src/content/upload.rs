@@ -12,8 +12,6 @@ use warp::Buf;
 use warp::filters::multipart::Part;
 use warp::multipart::FormData;
 
-pub const MAX_UPLOAD_SIZE: u64 = 4 * 1024_u64.pow(3);
-
 #[derive(Clone, Copy, PartialEq, Eq, IntoStaticStr)]
 #[strum(serialize_all = "lowercase")]
 pub enum PartName {
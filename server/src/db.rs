@@ -1,17 +1,22 @@
-use crate::admin::{AdminTask, database};
-use crate::config;
+use crate::admin::{AdminTask, DatabaseResult, database};
+use crate::api::{self, ApiResult};
 use crate::content::signature::SIGNATURE_VERSION;
 use crate::schema::database_statistics;
 #[cfg(test)]
 use crate::test;
+use crate::{config, update};
+use chrono::Utc;
+use cron::Schedule;
 use diesel::migration::Migration;
 use diesel::pg::Pg;
 use diesel::prelude::*;
 use diesel::r2d2::{ConnectionManager, Pool, PoolError, PooledConnection};
+use diesel::result::DatabaseErrorKind;
 use diesel_migrations::{EmbeddedMigrations, MigrationHarness, embed_migrations};
 use std::borrow::Cow;
 use std::sync::LazyLock;
 use std::time::Duration;
+use tokio::time::MissedTickBehavior;
 
 pub type Connection = PooledConnection<ConnectionManager<PgConnection>>;
 pub type ConnectionPool = Pool<ConnectionManager<PgConnection>>;
@@ -34,6 +39,34 @@ pub fn get_prod_connection() -> ConnectionResult {
     CONNECTION_POOL.get()
 }
 
+/// Runs `function` in a transaction, retrying it up to `deadlock_retries` times if it fails because
+/// of a deadlock or a serialization failure. Each retry runs `function` from the start, so any side
+/// effects outside of the database must be safe to repeat.
+pub fn deadlock_prone_transaction<T, F>(conn: &mut Connection, mut function: F) -> ApiResult<T>
+where
+    F: FnMut(&mut Connection) -> ApiResult<T>,
+{
+    let mut retries = config::database().deadlock_retries;
+    loop {
+        match conn.transaction(&mut function) {
+            Err(api::Error::FailedQuery(err)) if retries > 0 && is_deadlock(&err) => retries -= 1,
+            result => return result,
+        }
+    }
+}
+
+/// Returns whether `err` was caused by a deadlock or a serialization failure.
+fn is_deadlock(err: &diesel::result::Error) -> bool {
+    match err {
+        diesel::result::Error::DatabaseError(DatabaseErrorKind::SerializationFailure, _) => true,
+        // Diesel has no error kind for deadlocks, so they are recognized by their message
+        diesel::result::Error::DatabaseError(DatabaseErrorKind::Unknown, info) => {
+            info.message().starts_with("deadlock detected")
+        }
+        _ => false,
+    }
+}
+
 /// Runs embedded migrations on the database. Used to update database for end-users who don't build server themselves.
 /// Doesn't perform any error handling, as this is meant to be run once on application start.
 pub fn run_migrations(conn: &mut PgConnection) {
@@ -73,6 +106,52 @@ pub fn run_migrations(conn: &mut PgConnection) {
     }
 }
 
+/// Refreshes database statistics every time the given cron `schedule` fires. Never returns,
+/// so it should be spawned as a background task.
+pub async fn refresh_statistics_on_schedule(schedule: &'static Schedule) {
+    for refresh_time in schedule.upcoming(Utc) {
+        let time_until_refresh = (refresh_time - Utc::now()).to_std().unwrap_or_default();
+        tokio::time::sleep(time_until_refresh).await;
+
+        let refresh = tokio::task::spawn_blocking(|| -> DatabaseResult<()> {
+            let mut conn = get_connection()?;
+            database::refresh_statistics(&mut conn)?;
+            Ok(())
+        });
+        match refresh.await {
+            Ok(Ok(())) => (),
+            Ok(Err(err)) => eprintln!("ERROR: Could not refresh statistics: {err}"),
+            Err(err) => eprintln!("ERROR: Statistics refresh task failed: {err}"),
+        }
+    }
+}
+
+/// Writes buffered post views to the database every `interval`. Never returns,
+/// so it should be spawned as a background task.
+pub async fn flush_views_periodically(interval: Duration) {
+    let mut interval = tokio::time::interval(interval);
+    interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    loop {
+        interval.tick().await;
+        match tokio::task::spawn_blocking(flush_views).await {
+            Ok(()) => (),
+            Err(err) => eprintln!("ERROR: View flushing task failed: {err}"),
+        }
+    }
+}
+
+/// Writes buffered post views to the database, logging any errors.
+pub fn flush_views() {
+    let flush = || -> DatabaseResult<()> {
+        let mut conn = get_connection()?;
+        update::post::flush_views(&mut conn)?;
+        Ok(())
+    };
+    if let Err(err) = flush() {
+        eprintln!("ERROR: Could not flush post views: {err}");
+    }
+}
+
 /// Returns a url for the database using `POSTGRES_USER`, `POSTGRES_PASSWORD`, `POSTGRES_HOST`, and `POSTGRES_DATABASE`
 /// environment variables. If `database_override` is not `None`, then it's value will be used in place of `POSTGRES_DATABASE`.
 pub fn create_url(database_override: Option<&str>) -> String {
@@ -135,12 +214,58 @@ static CONNECTION_POOL: LazyLock<ConnectionPool> = LazyLock::new(|| {
     let num_rayon_threads = rayon::current_num_threads();
     let num_threads = std::cmp::max(num_tokio_threads, num_rayon_threads) as u32;
 
+    let database_config = config::database();
     let manager = ConnectionManager::new(config::database_url());
     Pool::builder()
-        .max_size(num_threads)
+        .max_size(database_config.max_pool_size.unwrap_or(num_threads))
+        .connection_timeout(Duration::from_secs(database_config.connection_timeout))
         .max_lifetime(None)
-        .idle_timeout(None)
+        .idle_timeout(database_config.idle_timeout.map(Duration::from_secs))
         .test_on_check_out(true)
         .build(manager)
         .expect("Could not build connection pool")
 });
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use diesel::result::Error;
+    use serial_test::parallel;
+
+    #[test]
+    #[parallel]
+    fn deadlock_retries() -> ApiResult<()> {
+        let mut conn = get_connection()?;
+        let database_error =
+            |kind, message: &str| api::Error::from(Error::DatabaseError(kind, Box::new(message.to_owned())));
+
+        // Transactions that keep failing are given up on after the configured number of retries
+        let mut attempts = 0;
+        let result: ApiResult<()> = deadlock_prone_transaction(&mut conn, |_| {
+            attempts += 1;
+            Err(database_error(DatabaseErrorKind::SerializationFailure, "could not serialize access"))
+        });
+        assert!(result.is_err());
+        assert_eq!(attempts, config::database().deadlock_retries + 1);
+
+        let mut attempts = 0;
+        let result = deadlock_prone_transaction(&mut conn, |_| {
+            attempts += 1;
+            match attempts {
+                1 => Err(database_error(DatabaseErrorKind::Unknown, "deadlock detected")),
+                _ => Ok(attempts),
+            }
+        });
+        assert_eq!(result?, 2);
+
+        // Other errors aren't retried
+        let mut attempts = 0;
+        let result: ApiResult<()> = deadlock_prone_transaction(&mut conn, |_| {
+            attempts += 1;
+            Err(database_error(DatabaseErrorKind::UniqueViolation, "duplicate key value"))
+        });
+        assert!(result.is_err());
+        assert_eq!(attempts, 1);
+        Ok(())
+    }
+}
@@ -250,6 +250,17 @@ impl ErrorKind for crate::search::TimeParsingError {
             Self::TooManyArgs => "TooManyArgs",
             Self::NotAnInteger(err) => err.kind().kind(),
             Self::OutOfRange(_) => "OutOfRange",
+            Self::InvalidTimestamp(_) => "InvalidTimestamp",
+        }
+    }
+}
+
+impl ErrorKind for crate::search::FileSizeParsingError {
+    fn kind(&self) -> &'static str {
+        match self {
+            Self::NotAnInteger(err) => err.kind().kind(),
+            Self::OutOfRange => "OutOfRange",
+            Self::UnknownUnit => "UnknownUnit",
         }
     }
 }
@@ -366,8 +377,10 @@ impl ErrorKind for crate::api::Error {
             Self::BadHash(err) => err.kind(),
             Self::BadIncomingHeader(_) => "BadIncomingHeader",
             Self::BadResponseHeader(_) => "BadResponseHeader",
+            Self::ContentTokenExpired => "ContentTokenExpired",
             Self::ContentTypeMismatch(..) => "ContentTypeMismatch",
             Self::CyclicDependency(_) => "CyclicDependency",
+            Self::DatabaseUnavailable(_) => "DatabaseUnavailable",
             Self::DeleteDefault(_) => "DeleteDefault",
             Self::EmptySwf => "EmptySwf",
             Self::EmptyVideo => "EmptyVideo",
@@ -377,33 +390,45 @@ impl ErrorKind for crate::api::Error {
             Self::FailedEmailTransport(_) => "FailedEmailTransport",
             Self::FailedQuery(err) => err.kind(),
             Self::FromStr(_) => "FromStrError",
+            Self::ImplicationCycle(_) => "ImplicationCycle",
+            Self::IncorrectPassword => "IncorrectPassword",
             Self::InsufficientPrivileges => "InsufficientPrivileges",
             Self::InvalidEmailAddress(err) => err.kind(),
             Self::InvalidEmail(err) => err.kind(),
+            Self::InvalidFileSize(err) => err.kind(),
             Self::InvalidHeader(_) => "InvalidHeader",
             Self::InvalidMetadataType => "InvalidMetadataType",
+            Self::InvalidPoolOrder => "InvalidPoolOrder",
             Self::InvalidSort => "InvalidSort",
             Self::InvalidTime(err) => err.kind(),
             Self::InvalidUserRank => "InvalidUserRank",
             Self::Image(err) => err.kind(),
+            Self::JobAlreadyRunning => "JobAlreadyRunning",
             Self::JsonSerialization(err) => err.classify().kind(),
-            Self::NoEmail => "NoEmail",
+            Self::NoExif => "NoExif",
             Self::MissingContent(_) => "MissingContent",
             Self::MissingContentType => "MissingContentType",
             Self::MissingFormData => "MissingFormData",
             Self::MissingMetadata => "MissingMetadata",
-            Self::MissingSmtpInfo => "MissingSmtpInfo",
+            Self::MovedPermanently(_) => "MovedPermanently",
             Self::NoNamesGiven(_) => "NoNamesGiven",
             Self::NotAnInteger(err) => err.kind().kind(),
             Self::NotFound(_) => "NotFound",
             Self::NotLoggedIn => "NotLoggedIn",
+            Self::PayloadTooLarge => "PayloadTooLarge",
+            Self::PostLocked => "PostLocked",
             Self::Request(_) => "RequestError",
-            Self::ResourceModified => "ResourceModified",
+            Self::ResourceModified { .. } => "ResourceModified",
+            Self::SelfFavorite => "SelfFavorite",
             Self::SelfMerge(_) => "SelfMerge",
             Self::StdIo(err) => err.kind().kind(),
             Self::SwfDecoding(err) => err.kind(),
+            Self::TagNameTaken(_) => "TagNameTaken",
+            Self::TooManyResources(..) => "TooManyResources",
+            Self::UnauthorizedEmailVerification => "UnauthorizedEmailVerification",
             Self::UnauthorizedPasswordReset => "UnauthorizedPasswordReset",
             Self::Utf8Conversion(_) => "Utf8ConversionError",
+            Self::ValidationError(_) => "ValidationError",
             Self::VideoDecoding(err) => err.kind(),
             Self::Warp(_) => "WarpError",
         }
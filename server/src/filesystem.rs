@@ -2,9 +2,11 @@ use crate::config;
 use crate::content::hash::{self, PostHash};
 use crate::content::thumbnail::ThumbnailCategory;
 use crate::model::enums::MimeType;
+use image::codecs::jpeg::JpegEncoder;
 use image::{DynamicImage, ImageResult};
 use std::ffi::OsStr;
-use std::io::ErrorKind;
+use std::fs::File;
+use std::io::{BufWriter, ErrorKind};
 use std::os::unix::fs::PermissionsExt;
 use std::path::{Path, PathBuf};
 use std::sync::LazyLock;
@@ -53,6 +55,7 @@ pub fn save_uploaded_file(data: &[u8], mime_type: MimeType) -> std::io::Result<S
 }
 
 /// Saves custom avatar `thumbnail` for user with name `username` to disk.
+/// Avatars are stored as lossless PNGs, so the configured thumbnail quality doesn't apply to them.
 /// Returns size of the thumbnail in bytes.
 pub fn save_custom_avatar(username: &str, thumbnail: DynamicImage) -> ImageResult<u64> {
     assert_eq!(thumbnail.width(), config::get().thumbnails.avatar_width);
@@ -82,8 +85,9 @@ pub fn save_post_thumbnail(
     thumbnail: DynamicImage,
     thumbnail_type: ThumbnailCategory,
 ) -> ImageResult<u64> {
-    assert_eq!(thumbnail.width(), config::get().thumbnails.post_height);
-    assert_eq!(thumbnail.height(), config::get().thumbnails.post_height);
+    // Thumbnails may be smaller than the configured dimensions if they are fit rather than filled
+    assert!(thumbnail.width() <= config::get().thumbnails.post_width);
+    assert!(thumbnail.height() <= config::get().thumbnails.post_height);
 
     let thumbnail_path = match thumbnail_type {
         ThumbnailCategory::Generated => {
@@ -96,7 +100,9 @@ pub fn save_post_thumbnail(
         }
     };
 
-    thumbnail.to_rgb8().save(&thumbnail_path)?;
+    let file = BufWriter::new(File::create(&thumbnail_path)?);
+    let encoder = JpegEncoder::new_with_quality(file, config::get().thumbnails.quality);
+    thumbnail.to_rgb8().write_with_encoder(encoder)?;
     let metadata = std::fs::metadata(thumbnail_path)?;
     Ok(metadata.len())
 }
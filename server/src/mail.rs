@@ -0,0 +1,39 @@
+use crate::api::{self, ApiResult};
+use crate::config::{self, SmtpInfo};
+use lettre::message::Mailbox;
+use lettre::message::header::ContentType;
+use lettre::transport::smtp::authentication::Credentials;
+use lettre::{Message, SmtpTransport, Transport};
+
+/// Sends a plain text email with the given `subject` and `body` to `email_address`.
+pub fn send(smtp_info: &SmtpInfo, email_address: &str, subject: &str, body: String) -> ApiResult<()> {
+    let user_mailbox: Mailbox = format!("User <{email_address}>").parse()?;
+    let email = Message::builder()
+        .from(smtp_info.from.clone())
+        .to(user_mailbox)
+        .subject(subject)
+        .header(ContentType::TEXT_PLAIN)
+        .body(body)?;
+    let credentials = Credentials::new(smtp_info.username.to_string(), smtp_info.password.to_string());
+
+    // Open a remote connection to gmail
+    let mailer = SmtpTransport::relay("smtp.gmail.com")
+        .unwrap()
+        .credentials(credentials)
+        .build();
+
+    // Send the email
+    mailer.send(&email).map(|_| ()).map_err(api::Error::from)
+}
+
+/// Sends an email like [send] if smtp is configured. Otherwise, the email is printed to the server log,
+/// so that administrators of instances without a mailer can forward it by hand.
+pub fn send_or_log(email_address: &str, subject: &str, body: String) -> ApiResult<()> {
+    match config::smtp() {
+        Some(smtp_info) => send(smtp_info, email_address, subject, body),
+        None => {
+            println!("{subject} for {email_address}:\n{body}");
+            Ok(())
+        }
+    }
+}
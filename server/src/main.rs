@@ -8,6 +8,7 @@ mod content;
 mod db;
 mod error;
 mod filesystem;
+mod mail;
 mod math;
 mod model;
 mod resource;
@@ -19,25 +20,72 @@ mod test;
 mod time;
 mod update;
 
+use std::time::Duration;
+use tokio::signal::unix::{SignalKind, signal};
+use tokio::sync::oneshot;
+
 #[tokio::main]
 async fn main() {
+    // Parse config eagerly so that misconfigurations are reported on startup
+    config::get();
+
     db::run_migrations(&mut db::get_connection().unwrap());
     if admin::enabled() {
         return admin::command_line_mode();
     }
     db::check_signature_version();
+    admin::job::fail_interrupted_jobs().unwrap();
 
     println!("Oxibooru server running on {} threads", tokio::runtime::Handle::current().metrics().num_workers());
     filesystem::purge_temporary_uploads().unwrap();
+    if let Some(schedule) = config::statistics_refresh_schedule() {
+        tokio::spawn(db::refresh_statistics_on_schedule(schedule));
+    }
+    let view_flush_interval = Duration::from_secs(config::get().view_flush_interval_seconds);
+    tokio::spawn(db::flush_views_periodically(view_flush_interval));
 
-    // Run the warp server. Can be shut down gracefully with ctrl+c (SIGINT).
+    // Run the warp server. Can be shut down gracefully with ctrl+c (SIGINT) or SIGTERM.
+    let (shutdown_sender, shutdown_receiver) = oneshot::channel();
     let (_addr, server) =
-        warp::serve(api::routes()).bind_with_graceful_shutdown(([0, 0, 0, 0], config::port()), async {
-            match tokio::signal::ctrl_c().await {
-                Ok(()) => println!("Stopping server..."),
-                Err(err) => eprintln!("Unable to listen for shutdown signal: {err}"),
-            };
+        warp::serve(api::routes()).bind_with_graceful_shutdown(([0, 0, 0, 0], config::port()), async move {
+            shutdown_signal().await;
+            println!("Stopping server with {} requests in flight...", api::active_requests());
+            let _ = shutdown_sender.send(());
         });
+    tokio::pin!(server);
+
+    tokio::select! {
+        () = &mut server => return,
+        Ok(()) = shutdown_receiver => (),
+    }
 
-    server.await;
+    // New connections are no longer accepted, so give in-flight requests some time to finish
+    let shutdown_timeout = Duration::from_secs(config::get().shutdown_timeout_seconds);
+    if tokio::time::timeout(shutdown_timeout, server).await.is_err() {
+        eprintln!("Shutdown timed out with {} requests in flight", api::active_requests());
+    }
+    db::flush_views();
+}
+
+/// Completes when the server receives SIGINT or SIGTERM.
+async fn shutdown_signal() {
+    let terminate = async {
+        match signal(SignalKind::terminate()) {
+            Ok(mut terminate) => {
+                terminate.recv().await;
+            }
+            Err(err) => {
+                eprintln!("Unable to listen for SIGTERM: {err}");
+                std::future::pending::<()>().await;
+            }
+        }
+    };
+    tokio::select! {
+        result = tokio::signal::ctrl_c() => {
+            if let Err(err) = result {
+                eprintln!("Unable to listen for shutdown signal: {err}");
+            }
+        }
+        () = terminate => (),
+    }
 }
@@ -27,6 +27,7 @@ pub struct Comment {
     pub text: String,
     pub creation_time: DateTime,
     pub last_edit_time: DateTime,
+    pub pinned: bool,
 }
 
 #[derive(Insertable)]
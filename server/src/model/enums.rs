@@ -1,3 +1,4 @@
+use crate::content::hash;
 use diesel::deserialize::{self, FromSql};
 use diesel::pg::{Pg, PgValue};
 use diesel::serialize::{self, Output, ToSql};
@@ -6,10 +7,14 @@ use diesel::{AsExpression, FromSqlRow};
 use image::ImageFormat;
 use serde::{Deserialize, Serialize};
 use serde_repr::{Deserialize_repr, Serialize_repr};
+use sha2::{Sha256, Sha512};
 use std::ops::{BitOr, BitOrAssign};
 use std::path::Path;
 use strum::{Display, EnumCount, EnumString, FromRepr, IntoStaticStr};
 use thiserror::Error;
+use utoipa::openapi::RefOr;
+use utoipa::openapi::schema::Schema;
+use utoipa::{PartialSchema, ToSchema};
 
 /// In general, the order of these enums should not be changed.
 /// They are encoded in the database as an integer, so changing
@@ -27,7 +32,7 @@ pub struct ParseExtensionError {
 #[error("Cannot convert None to Score")]
 pub struct FromRatingError;
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, FromRepr, AsExpression, FromSqlRow, Serialize, Deserialize)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, FromRepr, AsExpression, FromSqlRow, Serialize, Deserialize, ToSchema)]
 #[diesel(sql_type = SmallInt)]
 #[serde(rename_all = "lowercase")]
 #[repr(i16)]
@@ -57,7 +62,10 @@ impl FromSql<SmallInt, Pg> for AvatarStyle {
     }
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, FromRepr, EnumString, AsExpression, FromSqlRow, Serialize, Deserialize)]
+/// Post types are stored by discriminant, so their order is also the order of `sort:type`.
+#[derive(
+    Debug, Copy, Clone, PartialEq, Eq, FromRepr, EnumString, AsExpression, FromSqlRow, Serialize, Deserialize, ToSchema,
+)]
 #[diesel(sql_type = SmallInt)]
 #[serde(rename_all = "lowercase")]
 #[strum(serialize_all = "lowercase")]
@@ -81,6 +89,7 @@ impl From<MimeType> for PostType {
             MimeType::Mov => Self::Video,
             MimeType::Webm => Self::Video,
             MimeType::Swf => Self::Flash,
+            MimeType::Avif => Self::Image,
         }
     }
 }
@@ -114,6 +123,7 @@ impl FromSql<SmallInt, Pg> for PostType {
     FromSqlRow,
     Serialize,
     Deserialize,
+    ToSchema,
 )]
 #[diesel(sql_type = SmallInt)]
 #[repr(i16)]
@@ -145,6 +155,10 @@ pub enum MimeType {
     #[serde(rename = "application/x-shockwave-flash")]
     #[strum(serialize = "application/x-shockwave-flash")]
     Swf,
+    // Not grouped with other image formats because the database representation depends on variant order
+    #[serde(rename = "image/avif")]
+    #[strum(serialize = "image/avif")]
+    Avif,
 }
 
 impl MimeType {
@@ -155,6 +169,7 @@ impl MimeType {
             "jpg" | "jpeg" | "JPG" | "JPEG" => Ok(Self::Jpeg),
             "png" | "PNG" => Ok(Self::Png),
             "webp" | "WEBP" => Ok(Self::Webp),
+            "avif" | "AVIF" => Ok(Self::Avif),
             "mp4" | "MP4" => Ok(Self::Mp4),
             "mov" | "MOV" => Ok(Self::Mov),
             "webm" | "WEBM" => Ok(Self::Webm),
@@ -177,6 +192,7 @@ impl MimeType {
             Self::Jpeg => "jpg",
             Self::Png => "png",
             Self::Webp => "webp",
+            Self::Avif => "avif",
             Self::Mp4 => "mp4",
             Self::Mov => "mov",
             Self::Webm => "webm",
@@ -191,6 +207,7 @@ impl MimeType {
             MimeType::Jpeg => Some(ImageFormat::Jpeg),
             MimeType::Png => Some(ImageFormat::Png),
             MimeType::Webp => Some(ImageFormat::WebP),
+            MimeType::Avif => Some(ImageFormat::Avif),
             MimeType::Mov | MimeType::Mp4 | MimeType::Webm | MimeType::Swf => None,
         }
     }
@@ -211,6 +228,49 @@ impl FromSql<SmallInt, Pg> for MimeType {
     }
 }
 
+#[derive(Debug, Copy, Clone, PartialEq, Eq, EnumString, FromRepr, AsExpression, FromSqlRow, Deserialize)]
+#[diesel(sql_type = SmallInt)]
+#[serde(rename_all = "lowercase")]
+#[strum(serialize_all = "lowercase")]
+#[repr(i16)]
+pub enum ChecksumAlgorithm {
+    Blake3,
+    Sha256,
+    Sha512,
+}
+
+impl ChecksumAlgorithm {
+    /// Computes a checksum of `data` with this algorithm, keyed with the content secret.
+    pub fn compute(self, data: &[u8]) -> Vec<u8> {
+        match self {
+            Self::Blake3 => hash::keyed_checksum::<blake3::Hasher>(data),
+            Self::Sha256 => hash::keyed_checksum::<Sha256>(data),
+            Self::Sha512 => hash::keyed_checksum::<Sha512>(data),
+        }
+    }
+}
+
+impl Default for ChecksumAlgorithm {
+    fn default() -> Self {
+        Self::Blake3
+    }
+}
+
+impl ToSql<SmallInt, Pg> for ChecksumAlgorithm {
+    fn to_sql<'a>(&'a self, out: &mut Output<'a, '_, Pg>) -> serialize::Result {
+        // SAFETY: ChecksumAlgorithm is repr(i16) so a valid ChecksumAlgorithm is a valid i16
+        let value: &'a i16 = unsafe { &*(self as *const ChecksumAlgorithm as *const i16) };
+        <i16 as ToSql<SmallInt, Pg>>::to_sql(value, out)
+    }
+}
+
+impl FromSql<SmallInt, Pg> for ChecksumAlgorithm {
+    fn from_sql(value: PgValue<'_>) -> deserialize::Result<Self> {
+        let database_value = i16::from_sql(value)?;
+        ChecksumAlgorithm::from_repr(database_value).ok_or("Failed to deserialize checksum algorithm".into())
+    }
+}
+
 #[derive(
     Debug,
     Copy,
@@ -225,6 +285,7 @@ impl FromSql<SmallInt, Pg> for MimeType {
     FromSqlRow,
     Serialize,
     Deserialize,
+    ToSchema,
 )]
 #[diesel(sql_type = SmallInt)]
 #[serde(rename_all = "lowercase")]
@@ -251,12 +312,16 @@ impl FromSql<SmallInt, Pg> for PostSafety {
     }
 }
 
-#[derive(Copy, Clone, EnumCount, EnumString, FromRepr, IntoStaticStr, Deserialize)]
+#[derive(
+    Debug, Copy, Clone, PartialEq, Eq, EnumCount, EnumString, FromRepr, IntoStaticStr, Serialize, Deserialize, ToSchema,
+)]
 #[serde(rename_all = "lowercase")]
 #[strum(serialize_all = "lowercase")]
 pub enum PostFlag {
     Loop,
     Sound,
+    Hidden,
+    Locked,
 }
 
 impl From<PostFlag> for u16 {
@@ -285,6 +350,27 @@ impl PostFlags {
     pub fn from_slice(flags: &[PostFlag]) -> Self {
         flags.iter().fold(Self::new(), |flags, &flag| flags | flag)
     }
+
+    pub fn contains(self, flag: PostFlag) -> bool {
+        self.flags & u16::from(flag) != 0
+    }
+
+    /// Returns a copy of these flags with `flag` cleared.
+    pub fn without(self, flag: PostFlag) -> Self {
+        Self {
+            flags: self.flags & !u16::from(flag),
+        }
+    }
+
+    /// Expands the bit mask back into its constituent flags. Inverse of [PostFlags::from_slice].
+    pub fn to_vec(self) -> Vec<PostFlag> {
+        const _: () = assert!(PostFlag::COUNT <= 16);
+
+        (0..PostFlag::COUNT)
+            .filter(|f| self.flags & (1 << f) != 0) // Check if flag is set
+            .map(|f| PostFlag::from_repr(f).unwrap())
+            .collect()
+    }
 }
 
 impl From<PostFlags> for u16 {
@@ -329,16 +415,18 @@ impl Serialize for PostFlags {
     where
         S: serde::Serializer,
     {
-        const _: () = assert!(PostFlag::COUNT <= 16);
+        self.to_vec().serialize(serializer)
+    }
+}
 
-        let flags: Vec<&'static str> = (0..PostFlag::COUNT)
-            .filter(|f| self.flags & (1 << f) != 0) // Check if flag is set
-            .map(|f| PostFlag::from_repr(f).unwrap().into())
-            .collect();
-        flags.serialize(serializer)
+impl PartialSchema for PostFlags {
+    fn schema() -> RefOr<Schema> {
+        Vec::<PostFlag>::schema()
     }
 }
 
+impl ToSchema for PostFlags {}
+
 #[derive(
     Debug,
     Clone,
@@ -353,6 +441,7 @@ impl Serialize for PostFlags {
     FromSqlRow,
     Serialize,
     Deserialize,
+    ToSchema,
 )]
 #[diesel(sql_type = SmallInt)]
 #[serde(rename_all = "lowercase")]
@@ -382,7 +471,7 @@ impl FromSql<SmallInt, Pg> for UserRank {
     }
 }
 
-#[derive(Debug, Copy, Clone, Serialize_repr, Deserialize_repr)]
+#[derive(Debug, Copy, Clone, Serialize_repr, Deserialize_repr, ToSchema)]
 #[repr(i16)]
 pub enum Rating {
     Dislike = -1,
@@ -447,6 +536,8 @@ pub enum ResourceType {
     #[strum(serialize = "pool category")]
     PoolCategory,
     Post,
+    #[strum(serialize = "post note")]
+    PostNote,
     Tag,
     #[strum(serialize = "tag category")]
     TagCategory,
@@ -469,6 +560,13 @@ mod test {
         assert_ne!(PostSafety::Safe, PostSafety::Unsafe);
     }
 
+    #[test]
+    fn type_ordering() {
+        assert!((PostType::Image as i16) < (PostType::Animation as i16));
+        assert!((PostType::Animation as i16) < (PostType::Video as i16));
+        assert!((PostType::Video as i16) < (PostType::Flash as i16));
+    }
+
     #[test]
     fn rank_ordering() {
         assert!(UserRank::Restricted < UserRank::Regular);
@@ -476,4 +574,25 @@ mod test {
         assert_eq!(UserRank::Regular, UserRank::Regular);
         assert_ne!(UserRank::Regular, UserRank::Moderator);
     }
+
+    #[test]
+    fn flags_round_trip() {
+        let flag_sets: [&[PostFlag]; 5] = [
+            &[],
+            &[PostFlag::Loop],
+            &[PostFlag::Sound],
+            &[PostFlag::Loop, PostFlag::Sound],
+            &[PostFlag::Sound, PostFlag::Hidden],
+        ];
+        for flag_set in flag_sets {
+            let flags = PostFlags::from_slice(flag_set);
+            assert_eq!(flags.to_vec(), flag_set);
+            assert!(flag_set.iter().all(|&flag| flags.contains(flag)));
+
+            let serialized = serde_json::to_string(&flags).unwrap();
+            let deserialized: Vec<PostFlag> = serde_json::from_str(&serialized).unwrap();
+            assert_eq!(PostFlags::from_slice(&deserialized), flags);
+        }
+        assert_eq!(serde_json::to_string(&PostFlags::new_with(PostFlag::Sound)).unwrap(), r#"["sound"]"#);
+    }
 }
@@ -10,6 +10,7 @@ use diesel::prelude::*;
 use diesel::sql_types::{Bool, Text};
 use serde::Serialize;
 use std::rc::Rc;
+use utoipa::ToSchema;
 
 #[derive(Insertable)]
 #[diesel(table_name = pool_category)]
@@ -29,8 +30,9 @@ pub struct PoolCategory {
     pub last_edit_time: DateTime,
 }
 
-#[derive(Debug, Clone, FromSqlRow, Serialize)]
+#[derive(Debug, Clone, FromSqlRow, Serialize, ToSchema)]
 #[diesel(sql_type = Text)]
+#[schema(value_type = String)]
 pub struct PoolDescription(Rc<str>);
 
 impl FromSql<Text, Pg> for PoolDescription {
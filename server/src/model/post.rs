@@ -1,9 +1,10 @@
 use crate::content::signature::{COMPRESSED_SIGNATURE_LEN, NUM_WORDS};
-use crate::model::enums::{MimeType, PostFlags, PostSafety, PostType, Score};
+use crate::model::enums::{ChecksumAlgorithm, MimeType, PostFlags, PostSafety, PostType, Score};
 use crate::model::tag::Tag;
 use crate::model::user::User;
 use crate::schema::{
-    post, post_favorite, post_feature, post_note, post_relation, post_score, post_signature, post_tag,
+    post, post_favorite, post_feature, post_note, post_redirect, post_relation, post_score, post_signature,
+    post_statistics, post_tag,
 };
 use crate::time::DateTime;
 use byteorder::{NetworkEndian, ReadBytesExt};
@@ -49,6 +50,7 @@ pub struct NewPost<'a> {
     pub flags: PostFlags,
     pub source: &'a str,
     pub description: &'a str,
+    pub checksum_algorithm: ChecksumAlgorithm,
 }
 
 #[derive(AsChangeset, Associations, Identifiable, Queryable, Selectable)]
@@ -74,6 +76,37 @@ pub struct Post {
     pub last_edit_time: DateTime,
     pub generated_thumbnail_size: i64,
     pub custom_thumbnail_size: i64,
+    pub checksum_algorithm: ChecksumAlgorithm,
+}
+
+/// Statistics of a post, maintained by database triggers.
+#[derive(Associations, Identifiable, Queryable, Selectable)]
+#[diesel(belongs_to(Post))]
+#[diesel(table_name = post_statistics)]
+#[diesel(primary_key(post_id))]
+#[diesel(check_for_backend(Pg))]
+pub struct PostStatistics {
+    pub post_id: i64,
+    pub tag_count: i64,
+    pub pool_count: i64,
+    pub note_count: i64,
+    pub comment_count: i64,
+    pub relation_count: i64,
+    pub score: i64,
+    pub favorite_count: i64,
+    pub feature_count: i64,
+    pub last_comment_time: Option<DateTime>,
+    pub last_favorite_time: Option<DateTime>,
+    pub last_feature_time: Option<DateTime>,
+    pub view_count: i64,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = post_redirect)]
+#[diesel(check_for_backend(Pg))]
+pub struct NewPostRedirect {
+    pub removed_id: i64,
+    pub target_id: i64,
 }
 
 #[derive(Clone, Copy, PartialEq, Eq, Hash, Associations, Identifiable, Insertable, Queryable, Selectable)]
@@ -242,14 +275,15 @@ pub struct PostSignature {
 }
 
 impl PostSignature {
-    pub fn find_similar_candidates(conn: &mut PgConnection, words: [i32; NUM_WORDS]) -> QueryResult<Vec<Self>> {
+    /// Retrieves every signature that contains at least one of the given `words`.
+    pub fn find_similar_candidates(conn: &mut PgConnection, words: &[i32]) -> QueryResult<Vec<Self>> {
         conn.transaction(|conn| {
             // Postgres really wants to perform a seq scan here, which is much slower than
             // an index scan. We temporarily disable seq scans to force it to use the index scan.
             diesel::sql_query("SET LOCAL enable_seqscan=false").execute(conn)?;
             post_signature::table
                 .select(PostSignature::as_select())
-                .filter(post_signature::words.overlaps_with(words.as_slice()))
+                .filter(post_signature::words.overlaps_with(words))
                 .load(conn)
         })
     }
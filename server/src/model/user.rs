@@ -1,6 +1,6 @@
 use crate::content::hash;
 use crate::model::enums::{AvatarStyle, UserRank};
-use crate::schema::{user, user_token};
+use crate::schema::{password_reset_token, user, user_audit, user_token};
 use crate::string::SmallString;
 use crate::time::DateTime;
 use diesel::pg::Pg;
@@ -18,6 +18,7 @@ pub struct NewUser<'a> {
     pub email: Option<&'a str>,
     pub rank: UserRank,
     pub avatar_style: AvatarStyle,
+    pub email_verification_token: Option<Uuid>,
 }
 
 #[derive(Identifiable, Queryable, Selectable)]
@@ -36,6 +37,9 @@ pub struct User {
     pub last_edit_time: DateTime,
     #[allow(dead_code)]
     custom_avatar_size: i64,
+    pub email_verified: bool,
+    #[allow(dead_code)]
+    email_verification_token: Option<Uuid>,
 }
 
 impl User {
@@ -73,3 +77,22 @@ pub struct UserToken {
     pub last_edit_time: DateTime,
     pub last_usage_time: DateTime,
 }
+
+#[derive(Insertable)]
+#[diesel(table_name = password_reset_token)]
+#[diesel(check_for_backend(Pg))]
+pub struct NewPasswordResetToken<'a> {
+    pub user_id: i64,
+    pub token_hash: &'a str,
+    pub expiration_time: DateTime,
+}
+
+#[derive(Insertable)]
+#[diesel(table_name = user_audit)]
+#[diesel(check_for_backend(Pg))]
+pub struct NewUserAudit {
+    pub user_id: i64,
+    pub changed_by: Option<i64>,
+    pub old_rank: UserRank,
+    pub new_rank: UserRank,
+}
@@ -2,7 +2,7 @@ use crate::auth::header::Client;
 use crate::model::comment::{Comment, CommentScore};
 use crate::model::enums::{AvatarStyle, Rating};
 use crate::resource::user::MicroUser;
-use crate::resource::{self, BoolFill};
+use crate::resource::{self, BoolFill, SelectSubField};
 use crate::schema::{comment, comment_score, comment_statistics, user};
 use crate::string::SmallString;
 use crate::time::DateTime;
@@ -10,6 +10,7 @@ use diesel::prelude::*;
 use serde::Serialize;
 use serde_with::skip_serializing_none;
 use strum::{EnumString, EnumTable};
+use utoipa::ToSchema;
 
 #[derive(Clone, Copy, EnumString, EnumTable)]
 #[strum(serialize_all = "camelCase")]
@@ -20,6 +21,7 @@ pub enum Field {
     Text,
     CreationTime,
     LastEditTime,
+    Pinned,
     User,
     Score,
     OwnScore,
@@ -31,8 +33,10 @@ impl BoolFill for FieldTable<bool> {
     }
 }
 
+impl SelectSubField<Field> for FieldTable<bool> {}
+
 #[skip_serializing_none]
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct CommentInfo {
     pub version: Option<DateTime>, // TODO: Remove last_edit_time as it fills the same role as version here
@@ -41,6 +45,7 @@ pub struct CommentInfo {
     pub text: Option<String>,
     pub creation_time: Option<DateTime>,
     pub last_edit_time: Option<DateTime>,
+    pub pinned: Option<bool>,
     pub user: Option<Option<MicroUser>>,
     pub score: Option<i64>,
     pub own_score: Option<Rating>,
@@ -94,6 +99,7 @@ impl CommentInfo {
                 text: fields[Field::Text].then_some(comment.text),
                 creation_time: fields[Field::CreationTime].then_some(comment.creation_time),
                 last_edit_time: fields[Field::LastEditTime].then_some(comment.last_edit_time),
+                pinned: fields[Field::Pinned].then_some(comment.pinned),
                 user: owners.pop(),
                 score: scores.pop(),
                 own_score: client_scores.pop(),
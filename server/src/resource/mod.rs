@@ -10,9 +10,10 @@ pub mod user_token;
 use crate::string::SmallString;
 use diesel::{Identifiable, define_sql_function};
 use std::collections::HashMap;
-use std::ops::IndexMut;
+use std::ops::{Index, IndexMut};
 use std::rc::Rc;
 use std::str::FromStr;
+use strum::ParseError;
 
 // NOTE: The more complicated queries in this module rely on the behavior of diesel's
 // grouped_by function preserving the relative order between elements. This seems to be the
@@ -26,16 +27,33 @@ pub trait BoolFill {
     fn filled(val: bool) -> Self;
 }
 
+/// Selects a sub-field of a nested micro resource (e.g. `tags.names`). By default,
+/// resources have no nested fields, so all sub-fields are rejected.
+pub trait SelectSubField<E> {
+    fn select_sub_field(&mut self, _field: E, _sub_field: &str) -> Result<(), ParseError> {
+        Err(ParseError::VariantNotFound)
+    }
+}
+
 /// Creates a boolean FieldTable from an (optional) comma separated `fields` [str].
-pub fn create_table<T, E>(fields: Option<&str>) -> Result<T, <E as FromStr>::Err>
+/// Fields may also be dotted paths that select sub-fields of nested micro resources.
+pub fn create_table<T, E>(fields: Option<&str>) -> Result<T, ParseError>
 where
-    T: BoolFill + IndexMut<E, Output = bool>,
-    E: FromStr,
+    T: BoolFill + SelectSubField<E> + IndexMut<E, Output = bool>,
+    E: Copy + FromStr<Err = ParseError>,
 {
     if let Some(fields_str) = fields {
         let mut table = T::filled(false);
-        for field in fields_str.split(',') {
-            table[E::from_str(field)?] = true;
+        for field_path in fields_str.split(',') {
+            let (field, sub_field) = match field_path.split_once('.') {
+                Some((field, sub_field)) => (field, Some(sub_field)),
+                None => (field_path, None),
+            };
+            let field = E::from_str(field)?;
+            table[field] = true;
+            if let Some(sub_field) = sub_field {
+                table.select_sub_field(field, sub_field)?;
+            }
         }
         Ok(table)
     } else {
@@ -43,6 +61,26 @@ where
     }
 }
 
+/// Selects `sub_field` in the field `table` of a micro resource. The table is created
+/// empty the first time one of its sub-fields is selected.
+fn select_micro_field<T, E>(table: &mut Option<T>, sub_field: &str) -> Result<(), ParseError>
+where
+    T: BoolFill + IndexMut<E, Output = bool>,
+    E: FromStr<Err = ParseError>,
+{
+    table.get_or_insert_with(|| T::filled(false))[E::from_str(sub_field)?] = true;
+    Ok(())
+}
+
+/// Returns whether `field` is selected in the field `table` of a micro resource.
+/// All fields are selected if there is no table.
+fn is_micro_field_selected<T, E>(table: Option<&T>, field: E) -> bool
+where
+    T: Index<E, Output = bool>,
+{
+    table.is_none_or(|table| table[field])
+}
+
 fn check_batch_results(batch_size: usize, post_count: usize) {
     assert!(batch_size == 0 || batch_size == post_count);
 }
@@ -1,8 +1,9 @@
-use crate::content::hash::PostHash;
+use crate::auth::header::Client;
 use crate::model::pool::{Pool, PoolDescription, PoolName, PoolPost};
 use crate::resource::post::MicroPost;
-use crate::resource::{self, BoolFill};
+use crate::resource::{self, BoolFill, SelectSubField};
 use crate::schema::{pool, pool_category, pool_name, pool_post, pool_statistics};
+use crate::search;
 use crate::string::SmallString;
 use crate::time::DateTime;
 use diesel::prelude::*;
@@ -10,8 +11,9 @@ use serde::Serialize;
 use serde_with::skip_serializing_none;
 use std::rc::Rc;
 use strum::{EnumString, EnumTable};
+use utoipa::ToSchema;
 
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct MicroPool {
     pub id: i64,
@@ -19,6 +21,12 @@ pub struct MicroPool {
     pub category: SmallString,
     pub description: PoolDescription,
     pub post_count: i64,
+    /// Zero-based position of the post within the pool.
+    pub position: i64,
+    /// Post preceding the post within the pool, if any.
+    pub previous_post_id: Option<i64>,
+    /// Post following the post within the pool, if any.
+    pub next_post_id: Option<i64>,
 }
 
 #[derive(Clone, Copy, EnumString, EnumTable)]
@@ -41,8 +49,10 @@ impl BoolFill for FieldTable<bool> {
     }
 }
 
+impl SelectSubField<Field> for FieldTable<bool> {}
+
 #[skip_serializing_none]
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct PoolInfo {
     version: Option<DateTime>,
@@ -57,19 +67,29 @@ pub struct PoolInfo {
 }
 
 impl PoolInfo {
-    pub fn new(conn: &mut PgConnection, pool: Pool, fields: &FieldTable<bool>) -> QueryResult<Self> {
-        let mut pool_info = Self::new_batch(conn, vec![pool], fields)?;
+    pub fn new(conn: &mut PgConnection, client: Client, pool: Pool, fields: &FieldTable<bool>) -> QueryResult<Self> {
+        let mut pool_info = Self::new_batch(conn, client, vec![pool], fields)?;
         assert_eq!(pool_info.len(), 1);
         Ok(pool_info.pop().unwrap())
     }
 
-    pub fn new_from_id(conn: &mut PgConnection, pool_id: i64, fields: &FieldTable<bool>) -> QueryResult<Self> {
-        let mut pool_info = Self::new_batch_from_ids(conn, vec![pool_id], fields)?;
+    pub fn new_from_id(
+        conn: &mut PgConnection,
+        client: Client,
+        pool_id: i64,
+        fields: &FieldTable<bool>,
+    ) -> QueryResult<Self> {
+        let mut pool_info = Self::new_batch_from_ids(conn, client, vec![pool_id], fields)?;
         assert_eq!(pool_info.len(), 1);
         Ok(pool_info.pop().unwrap())
     }
 
-    pub fn new_batch(conn: &mut PgConnection, pools: Vec<Pool>, fields: &FieldTable<bool>) -> QueryResult<Vec<Self>> {
+    pub fn new_batch(
+        conn: &mut PgConnection,
+        client: Client,
+        pools: Vec<Pool>,
+        fields: &FieldTable<bool>,
+    ) -> QueryResult<Vec<Self>> {
         let batch_size = pools.len();
 
         let mut categories = fields[Field::Category]
@@ -85,7 +105,7 @@ impl PoolInfo {
         resource::check_batch_results(names.len(), batch_size);
 
         let mut posts = fields[Field::Posts]
-            .then(|| get_posts(conn, &pools))
+            .then(|| get_posts(conn, client, &pools))
             .transpose()?
             .unwrap_or_default();
         resource::check_batch_results(posts.len(), batch_size);
@@ -116,12 +136,13 @@ impl PoolInfo {
 
     pub fn new_batch_from_ids(
         conn: &mut PgConnection,
+        client: Client,
         pool_ids: Vec<i64>,
         fields: &FieldTable<bool>,
     ) -> QueryResult<Vec<Self>> {
         let unordered_pools = pool::table.filter(pool::id.eq_any(&pool_ids)).load(conn)?;
         let pools = resource::order_as(unordered_pools, &pool_ids);
-        Self::new_batch(conn, pools, fields)
+        Self::new_batch(conn, client, pools, fields)
     }
 }
 
@@ -150,19 +171,18 @@ fn get_names(conn: &mut PgConnection, pools: &[Pool]) -> QueryResult<Vec<Vec<Sma
         .collect())
 }
 
-fn get_posts(conn: &mut PgConnection, pools: &[Pool]) -> QueryResult<Vec<Vec<MicroPost>>> {
-    Ok(PoolPost::belonging_to(pools)
-        .order_by(pool_post::order)
-        .load::<PoolPost>(conn)?
+fn get_posts(conn: &mut PgConnection, client: Client, pools: &[Pool]) -> QueryResult<Vec<Vec<MicroPost>>> {
+    let pool_posts: Vec<PoolPost> = PoolPost::belonging_to(pools).order_by(pool_post::order).load(conn)?;
+    let post_ids: Vec<_> = pool_posts.iter().map(|pool_post| pool_post.post_id).collect();
+    let visible_ids = search::post::visible_post_ids(conn, client, &post_ids)?;
+    Ok(pool_posts
         .grouped_by(pools)
         .into_iter()
         .map(|posts_in_pool| {
             posts_in_pool
                 .into_iter()
-                .map(|pool_post| MicroPost {
-                    id: pool_post.post_id,
-                    thumbnail_url: PostHash::new(pool_post.post_id).thumbnail_url(),
-                })
+                .filter(|pool_post| visible_ids.contains(&pool_post.post_id))
+                .map(|pool_post| MicroPost::new(pool_post.post_id, None))
                 .collect()
         })
         .collect())
@@ -1,5 +1,5 @@
 use crate::model::pool::PoolCategory;
-use crate::resource::BoolFill;
+use crate::resource::{BoolFill, SelectSubField};
 use crate::schema::{pool_category, pool_category_statistics};
 use crate::string::SmallString;
 use crate::time::DateTime;
@@ -7,6 +7,7 @@ use diesel::prelude::*;
 use serde::Serialize;
 use serde_with::skip_serializing_none;
 use strum::{EnumString, EnumTable};
+use utoipa::ToSchema;
 
 #[derive(Clone, Copy, EnumString, EnumTable)]
 #[strum(serialize_all = "camelCase")]
@@ -24,8 +25,10 @@ impl BoolFill for FieldTable<bool> {
     }
 }
 
+impl SelectSubField<Field> for FieldTable<bool> {}
+
 #[skip_serializing_none]
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 pub struct PoolCategoryInfo {
     version: Option<DateTime>,
     name: Option<SmallString>,
@@ -1,29 +1,36 @@
 use crate::auth::header::Client;
+use crate::config;
 use crate::content::hash::PostHash;
-use crate::get_post_stats;
+use crate::content::{signature, thumbnail};
 use crate::model::comment::Comment;
-use crate::model::enums::{AvatarStyle, MimeType, PostFlags, PostSafety, PostType, Rating, Score};
+use crate::model::enums::{AvatarStyle, MimeType, PostFlag, PostFlags, PostSafety, PostType, Rating, Score};
 use crate::model::pool::{PoolDescription, PoolPost};
-use crate::model::post::{NewPostNote, Post, PostFavorite, PostNote, PostRelation, PostScore, PostTag};
+use crate::model::post::{
+    NewPostNote, Post, PostFavorite, PostNote, PostRelation, PostScore, PostSignature, PostStatistics, PostTag,
+};
 use crate::model::tag::TagName;
-use crate::resource::comment::CommentInfo;
+use crate::resource::comment::{CommentInfo, Field as CommentField, FieldTable as CommentFieldTable};
 use crate::resource::pool::MicroPool;
-use crate::resource::tag::MicroTag;
-use crate::resource::user::MicroUser;
-use crate::resource::{self, BoolFill};
+use crate::resource::tag::{MicroTag, MicroTagFieldTable};
+use crate::resource::user::{MicroUser, MicroUserFieldTable};
+use crate::resource::{self, BoolFill, SelectSubField};
 use crate::schema::{
-    comment, comment_score, comment_statistics, pool, pool_category, pool_name, pool_statistics, post, post_favorite,
-    post_relation, post_score, post_statistics, tag, tag_category, tag_name, tag_statistics, user,
+    comment, comment_score, comment_statistics, pool, pool_category, pool_name, pool_post, pool_statistics, post,
+    post_favorite, post_feature, post_note, post_relation, post_score, post_signature, tag, tag_category, tag_name,
+    tag_statistics, user,
 };
+use crate::search;
 use crate::string::SmallString;
 use crate::time::DateTime;
 use diesel::prelude::*;
 use serde::{Deserialize, Serialize};
 use serde_with::skip_serializing_none;
 use std::collections::{HashMap, HashSet};
-use strum::{EnumString, EnumTable};
+use std::ops::{Index, IndexMut};
+use strum::{EnumString, EnumTable, ParseError};
+use utoipa::ToSchema;
 
-#[derive(Serialize, Deserialize)]
+#[derive(Serialize, Deserialize, ToSchema)]
 #[serde(deny_unknown_fields)]
 pub struct Note {
     polygon: Vec<[f32; 2]>,
@@ -52,11 +59,76 @@ impl Note {
     }
 }
 
-#[derive(Serialize)]
+/// A note submitted as part of a post update. Notes with an `id` replace the existing
+/// note with that id, while notes without one are created.
+#[derive(Deserialize, ToSchema)]
+#[serde(deny_unknown_fields)]
+pub struct NoteUpdate {
+    pub id: Option<i64>,
+    pub polygon: Vec<[f32; 2]>,
+    pub text: String,
+}
+
+#[skip_serializing_none]
+#[derive(Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct MicroPost {
-    pub id: i64,
-    pub thumbnail_url: String,
+    id: Option<i64>,
+    thumbnail_url: Option<String>,
+}
+
+impl MicroPost {
+    /// Creates a micro post containing the selected `fields`, or all fields if `fields` is `None`.
+    pub fn new(post_id: i64, fields: Option<&MicroPostFieldTable<bool>>) -> Self {
+        Self {
+            id: resource::is_micro_field_selected(fields, MicroPostField::Id).then_some(post_id),
+            thumbnail_url: resource::is_micro_field_selected(fields, MicroPostField::ThumbnailUrl)
+                .then(|| PostHash::new(post_id).thumbnail_url()),
+        }
+    }
+}
+
+#[derive(Clone, Copy, EnumString, EnumTable)]
+#[strum(serialize_all = "camelCase")]
+pub enum MicroPostField {
+    Id,
+    ThumbnailUrl,
+}
+
+impl BoolFill for MicroPostFieldTable<bool> {
+    fn filled(val: bool) -> Self {
+        Self::filled(val)
+    }
+}
+
+#[derive(Serialize, ToSchema)]
+pub struct SimilarMicroPost {
+    distance: f64,
+    post: MicroPost,
+}
+
+#[derive(Serialize, ToSchema)]
+#[serde(rename_all = "camelCase")]
+pub struct ThumbnailInfo {
+    url: String,
+    width: u32,
+    height: u32,
+    mime_type: MimeType,
+}
+
+impl ThumbnailInfo {
+    /// Thumbnails are resized to fit within the configured dimensions and are currently always encoded as JPEG.
+    fn new(post: &Post) -> Self {
+        let content_width = u32::try_from(post.width).unwrap_or(0);
+        let content_height = u32::try_from(post.height).unwrap_or(0);
+        let (width, height) = thumbnail::post_dimensions(content_width, content_height);
+        Self {
+            url: PostHash::new(post.id).thumbnail_url(),
+            width,
+            height,
+            mime_type: MimeType::Jpeg,
+        }
+    }
 }
 
 #[derive(Clone, Copy, EnumString, EnumTable)]
@@ -74,11 +146,14 @@ pub enum Field {
     Checksum,
     ChecksumMd5,
     Flags,
+    Locked,
     Source,
     Description,
     CreationTime,
     LastEditTime,
     ContentUrl,
+    Thumbnail,
+    /// Url of the thumbnail, kept for clients written before [Field::Thumbnail] existed.
     ThumbnailUrl,
     Tags,
     Comments,
@@ -94,19 +169,88 @@ pub enum Field {
     NoteCount,
     FavoriteCount,
     FeatureCount,
+    ViewCount,
     LastFeatureTime,
+    FeaturedBy,
     FavoritedBy,
     HasCustomThumbnail,
+    SimilarPosts,
 }
 
-impl BoolFill for FieldTable<bool> {
+/// Fields that are read from the statistics of a post.
+const STATISTIC_FIELDS: [Field; 9] = [
+    Field::Score,
+    Field::TagCount,
+    Field::CommentCount,
+    Field::RelationCount,
+    Field::NoteCount,
+    Field::FavoriteCount,
+    Field::FeatureCount,
+    Field::ViewCount,
+    Field::LastFeatureTime,
+];
+
+/// Fields selected for a post, along with the fields selected for the micro resources nested
+/// within it. Micro resources include all of their fields unless a sub-field is selected.
+pub struct FieldSelection {
+    fields: FieldTable<bool>,
+    user_fields: Option<MicroUserFieldTable<bool>>,
+    tag_fields: Option<MicroTagFieldTable<bool>>,
+    comment_fields: Option<CommentFieldTable<bool>>,
+    relation_fields: Option<MicroPostFieldTable<bool>>,
+    featured_by_fields: Option<MicroUserFieldTable<bool>>,
+    favorited_by_fields: Option<MicroUserFieldTable<bool>>,
+    similar_post_fields: Option<MicroPostFieldTable<bool>>,
+}
+
+impl BoolFill for FieldSelection {
     fn filled(val: bool) -> Self {
-        Self::filled(val)
+        let mut fields = FieldTable::filled(val);
+        // Searching for similar posts is expensive, so it is only done when explicitly requested
+        fields[Field::SimilarPosts] = false;
+        Self {
+            fields,
+            user_fields: None,
+            tag_fields: None,
+            comment_fields: None,
+            relation_fields: None,
+            featured_by_fields: None,
+            favorited_by_fields: None,
+            similar_post_fields: None,
+        }
+    }
+}
+
+impl SelectSubField<Field> for FieldSelection {
+    fn select_sub_field(&mut self, field: Field, sub_field: &str) -> Result<(), ParseError> {
+        match field {
+            Field::User => resource::select_micro_field(&mut self.user_fields, sub_field),
+            Field::Tags => resource::select_micro_field(&mut self.tag_fields, sub_field),
+            Field::Comments => resource::select_micro_field(&mut self.comment_fields, sub_field),
+            Field::Relations => resource::select_micro_field(&mut self.relation_fields, sub_field),
+            Field::FeaturedBy => resource::select_micro_field(&mut self.featured_by_fields, sub_field),
+            Field::FavoritedBy => resource::select_micro_field(&mut self.favorited_by_fields, sub_field),
+            Field::SimilarPosts => resource::select_micro_field(&mut self.similar_post_fields, sub_field),
+            _ => Err(ParseError::VariantNotFound),
+        }
+    }
+}
+
+impl Index<Field> for FieldSelection {
+    type Output = bool;
+    fn index(&self, field: Field) -> &Self::Output {
+        &self.fields[field]
+    }
+}
+
+impl IndexMut<Field> for FieldSelection {
+    fn index_mut(&mut self, field: Field) -> &mut Self::Output {
+        &mut self.fields[field]
     }
 }
 
 #[skip_serializing_none]
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct PostInfo {
     version: Option<DateTime>,
@@ -122,11 +266,13 @@ pub struct PostInfo {
     #[serde(rename = "checksumMD5")]
     checksum_md5: Option<String>,
     flags: Option<PostFlags>,
+    locked: Option<bool>,
     source: Option<String>,
     description: Option<String>,
     creation_time: Option<DateTime>,
     last_edit_time: Option<DateTime>,
     content_url: Option<String>,
+    thumbnail: Option<ThumbnailInfo>,
     thumbnail_url: Option<String>,
     tags: Option<Vec<MicroTag>>,
     comments: Option<Vec<CommentInfo>>,
@@ -142,13 +288,16 @@ pub struct PostInfo {
     note_count: Option<i64>,
     favorite_count: Option<i64>,
     feature_count: Option<i64>,
+    view_count: Option<i64>,
     last_feature_time: Option<Option<DateTime>>,
+    featured_by: Option<Option<MicroUser>>,
     favorited_by: Option<Vec<MicroUser>>,
     has_custom_thumbnail: Option<bool>,
+    similar_posts: Option<Vec<SimilarMicroPost>>,
 }
 
 impl PostInfo {
-    pub fn new(conn: &mut PgConnection, client: Client, post: Post, fields: &FieldTable<bool>) -> QueryResult<Self> {
+    pub fn new(conn: &mut PgConnection, client: Client, post: Post, fields: &FieldSelection) -> QueryResult<Self> {
         let mut post_info = Self::new_batch(conn, client, vec![post], fields)?;
         assert_eq!(post_info.len(), 1);
         Ok(post_info.pop().unwrap())
@@ -158,23 +307,24 @@ impl PostInfo {
         conn: &mut PgConnection,
         client: Client,
         post_id: i64,
-        fields: &FieldTable<bool>,
+        fields: &FieldSelection,
     ) -> QueryResult<Self> {
         let mut post_info = Self::new_batch_from_ids(conn, client, vec![post_id], fields)?;
         assert_eq!(post_info.len(), 1);
         Ok(post_info.pop().unwrap())
     }
 
+    /// Creates post infos for `posts`. Results are in the same order as `posts`.
     pub fn new_batch(
         conn: &mut PgConnection,
         client: Client,
         posts: Vec<Post>,
-        fields: &FieldTable<bool>,
+        fields: &FieldSelection,
     ) -> QueryResult<Vec<Self>> {
         let batch_size = posts.len();
 
         let mut owners = fields[Field::User]
-            .then(|| get_owners(conn, &posts))
+            .then(|| get_owners(conn, &posts, fields.user_fields.as_ref()))
             .transpose()?
             .unwrap_or_default();
         resource::check_batch_results(owners.len(), batch_size);
@@ -184,25 +334,25 @@ impl PostInfo {
             .unwrap_or_default();
         resource::check_batch_results(content_urls.len(), batch_size);
 
-        let mut thumbnail_urls = fields[Field::ThumbnailUrl]
-            .then(|| get_thumbnail_urls(&posts))
+        let mut thumbnails = fields[Field::Thumbnail]
+            .then(|| get_thumbnails(&posts))
             .unwrap_or_default();
-        resource::check_batch_results(thumbnail_urls.len(), batch_size);
+        resource::check_batch_results(thumbnails.len(), batch_size);
 
         let mut tags = fields[Field::Tags]
-            .then(|| get_tags(conn, &posts))
+            .then(|| get_tags(conn, &posts, fields.tag_fields.as_ref()))
             .transpose()?
             .unwrap_or_default();
         resource::check_batch_results(tags.len(), batch_size);
 
         let mut comments = fields[Field::Comments]
-            .then(|| get_comments(conn, client, &posts))
+            .then(|| get_comments(conn, client, &posts, fields.comment_fields.as_ref()))
             .transpose()?
             .unwrap_or_default();
         resource::check_batch_results(comments.len(), batch_size);
 
         let mut relations = fields[Field::Relations]
-            .then(|| get_relations(conn, &posts))
+            .then(|| get_relations(conn, client, &posts, fields.relation_fields.as_ref()))
             .transpose()?
             .unwrap_or_default();
         resource::check_batch_results(relations.len(), batch_size);
@@ -219,12 +369,6 @@ impl PostInfo {
             .unwrap_or_default();
         resource::check_batch_results(notes.len(), batch_size);
 
-        let mut scores = fields[Field::Score]
-            .then(|| get_post_stats!(conn, &posts, post_statistics::score, i64))
-            .transpose()?
-            .unwrap_or_default();
-        resource::check_batch_results(scores.len(), batch_size);
-
         let mut client_scores = fields[Field::OwnScore]
             .then(|| get_client_scores(conn, client, &posts))
             .transpose()?
@@ -237,104 +381,98 @@ impl PostInfo {
             .unwrap_or_default();
         resource::check_batch_results(client_favorites.len(), batch_size);
 
-        let mut tag_counts = fields[Field::TagCount]
-            .then(|| get_post_stats!(conn, &posts, post_statistics::tag_count, i64))
+        // All statistics share a row, so they are retrieved together if any of them are selected
+        let mut statistics = STATISTIC_FIELDS
+            .iter()
+            .any(|&field| fields[field])
+            .then(|| get_statistics(conn, &posts))
             .transpose()?
             .unwrap_or_default();
-        resource::check_batch_results(tag_counts.len(), batch_size);
+        resource::check_batch_results(statistics.len(), batch_size);
 
-        let mut comment_counts = fields[Field::CommentCount]
-            .then(|| get_post_stats!(conn, &posts, post_statistics::comment_count, i64))
+        let mut last_featured_by = fields[Field::FeaturedBy]
+            .then(|| get_last_featured_by(conn, &posts, fields.featured_by_fields.as_ref()))
             .transpose()?
             .unwrap_or_default();
-        resource::check_batch_results(comment_counts.len(), batch_size);
+        resource::check_batch_results(last_featured_by.len(), batch_size);
 
-        let mut relation_counts = fields[Field::RelationCount]
-            .then(|| get_post_stats!(conn, &posts, post_statistics::relation_count, i64))
-            .transpose()?
-            .unwrap_or_default();
-        resource::check_batch_results(relation_counts.len(), batch_size);
-
-        let mut note_counts = fields[Field::NoteCount]
-            .then(|| get_post_stats!(conn, &posts, post_statistics::note_count, i64))
-            .transpose()?
-            .unwrap_or_default();
-        resource::check_batch_results(note_counts.len(), batch_size);
-
-        let mut favorite_counts = fields[Field::FavoriteCount]
-            .then(|| get_post_stats!(conn, &posts, post_statistics::favorite_count, i64))
-            .transpose()?
-            .unwrap_or_default();
-        resource::check_batch_results(favorite_counts.len(), batch_size);
-
-        let mut feature_counts = fields[Field::FeatureCount]
-            .then(|| get_post_stats!(conn, &posts, post_statistics::feature_count, i64))
-            .transpose()?
-            .unwrap_or_default();
-        resource::check_batch_results(feature_counts.len(), batch_size);
-
-        let mut last_feature_times = fields[Field::LastFeatureTime]
-            .then(|| get_post_stats!(conn, &posts, post_statistics::last_feature_time, Option<DateTime>))
+        let mut users_who_favorited = fields[Field::FavoritedBy]
+            .then(|| get_users_who_favorited(conn, &posts, fields.favorited_by_fields.as_ref()))
             .transpose()?
             .unwrap_or_default();
-        resource::check_batch_results(last_feature_times.len(), batch_size);
+        resource::check_batch_results(users_who_favorited.len(), batch_size);
 
-        let mut users_who_favorited = fields[Field::FavoritedBy]
-            .then(|| get_users_who_favorited(conn, &posts))
+        let mut similar_posts = fields[Field::SimilarPosts]
+            .then(|| get_similar_posts(conn, client, &posts, fields.similar_post_fields.as_ref()))
             .transpose()?
             .unwrap_or_default();
-        resource::check_batch_results(users_who_favorited.len(), batch_size);
+        resource::check_batch_results(similar_posts.len(), batch_size);
 
         let results = posts
             .into_iter()
             .rev()
-            .map(|post| Self {
-                version: fields[Field::Version].then_some(post.last_edit_time),
-                id: fields[Field::Id].then_some(post.id),
-                user: owners.pop(),
-                file_size: fields[Field::FileSize].then_some(post.file_size),
-                canvas_width: fields[Field::CanvasWidth].then_some(post.width),
-                canvas_height: fields[Field::CanvasHeight].then_some(post.height),
-                safety: fields[Field::Safety].then_some(post.safety),
-                type_: fields[Field::Type].then_some(post.type_),
-                mime_type: fields[Field::MimeType].then_some(post.mime_type),
-                checksum: fields[Field::Checksum].then(|| hex::encode(&post.checksum)),
-                checksum_md5: fields[Field::ChecksumMd5].then(|| hex::encode(&post.checksum_md5)),
-                flags: fields[Field::Flags].then_some(post.flags),
-                source: fields[Field::Source].then_some(post.source),
-                description: fields[Field::Description].then_some(post.description),
-                creation_time: fields[Field::CreationTime].then_some(post.creation_time),
-                last_edit_time: fields[Field::LastEditTime].then_some(post.last_edit_time),
-                content_url: content_urls.pop(),
-                thumbnail_url: thumbnail_urls.pop(),
-                tags: tags.pop(),
-                relations: relations.pop(),
-                notes: notes.pop(),
-                score: scores.pop(),
-                own_score: client_scores.pop(),
-                own_favorite: client_favorites.pop(),
-                tag_count: tag_counts.pop(),
-                favorite_count: favorite_counts.pop(),
-                comment_count: comment_counts.pop(),
-                note_count: note_counts.pop(),
-                feature_count: feature_counts.pop(),
-                relation_count: relation_counts.pop(),
-                last_feature_time: last_feature_times.pop(),
-                favorited_by: users_who_favorited.pop(),
-                comments: comments.pop(),
-                pools: pools.pop(),
-                has_custom_thumbnail: fields[Field::HasCustomThumbnail]
-                    .then(|| PostHash::new(post.id).custom_thumbnail_path().exists()),
+            .map(|post| {
+                let stats = statistics.pop();
+                let stat = |field: Field, value: fn(&PostStatistics) -> i64| {
+                    stats.as_ref().filter(|_| fields[field]).map(value)
+                };
+                Self {
+                    version: fields[Field::Version].then_some(post.last_edit_time),
+                    id: fields[Field::Id].then_some(post.id),
+                    user: owners.pop(),
+                    file_size: fields[Field::FileSize].then_some(post.file_size),
+                    canvas_width: fields[Field::CanvasWidth].then_some(post.width),
+                    canvas_height: fields[Field::CanvasHeight].then_some(post.height),
+                    safety: fields[Field::Safety].then_some(post.safety),
+                    type_: fields[Field::Type].then_some(post.type_),
+                    mime_type: fields[Field::MimeType].then_some(post.mime_type),
+                    checksum: fields[Field::Checksum].then(|| hex::encode(&post.checksum)),
+                    checksum_md5: fields[Field::ChecksumMd5].then(|| hex::encode(&post.checksum_md5)),
+                    flags: fields[Field::Flags].then_some(post.flags),
+                    locked: fields[Field::Locked].then(|| post.flags.contains(PostFlag::Locked)),
+                    source: fields[Field::Source].then_some(post.source),
+                    description: fields[Field::Description].then_some(post.description),
+                    creation_time: fields[Field::CreationTime].then_some(post.creation_time),
+                    last_edit_time: fields[Field::LastEditTime].then_some(post.last_edit_time),
+                    content_url: content_urls.pop(),
+                    thumbnail: thumbnails.pop(),
+                    thumbnail_url: fields[Field::ThumbnailUrl].then(|| PostHash::new(post.id).thumbnail_url()),
+                    tags: tags.pop(),
+                    relations: relations.pop(),
+                    notes: notes.pop(),
+                    score: stat(Field::Score, |stats| stats.score),
+                    own_score: client_scores.pop(),
+                    own_favorite: client_favorites.pop(),
+                    tag_count: stat(Field::TagCount, |stats| stats.tag_count),
+                    favorite_count: stat(Field::FavoriteCount, |stats| stats.favorite_count),
+                    comment_count: stat(Field::CommentCount, |stats| stats.comment_count),
+                    note_count: stat(Field::NoteCount, |stats| stats.note_count),
+                    feature_count: stat(Field::FeatureCount, |stats| stats.feature_count),
+                    view_count: stat(Field::ViewCount, |stats| stats.view_count),
+                    relation_count: stat(Field::RelationCount, |stats| stats.relation_count),
+                    last_feature_time: stats
+                        .as_ref()
+                        .filter(|_| fields[Field::LastFeatureTime])
+                        .map(|stats| stats.last_feature_time),
+                    featured_by: last_featured_by.pop(),
+                    favorited_by: users_who_favorited.pop(),
+                    comments: comments.pop(),
+                    pools: pools.pop(),
+                    has_custom_thumbnail: fields[Field::HasCustomThumbnail]
+                        .then(|| PostHash::new(post.id).custom_thumbnail_path().exists()),
+                    similar_posts: similar_posts.pop(),
+                }
             })
             .collect::<Vec<_>>();
         Ok(results.into_iter().rev().collect())
     }
 
+    /// Creates post infos for the posts with the given `post_ids`, in the same order as `post_ids`.
     pub fn new_batch_from_ids(
         conn: &mut PgConnection,
         client: Client,
         post_ids: Vec<i64>,
-        fields: &FieldTable<bool>,
+        fields: &FieldSelection,
     ) -> QueryResult<Vec<Self>> {
         let unordered_posts = post::table.filter(post::id.eq_any(&post_ids)).load(conn)?;
         let posts = resource::order_as(unordered_posts, &post_ids);
@@ -342,7 +480,11 @@ impl PostInfo {
     }
 }
 
-fn get_owners(conn: &mut PgConnection, posts: &[Post]) -> QueryResult<Vec<Option<MicroUser>>> {
+fn get_owners(
+    conn: &mut PgConnection,
+    posts: &[Post],
+    fields: Option<&MicroUserFieldTable<bool>>,
+) -> QueryResult<Vec<Option<MicroUser>>> {
     let post_ids: Vec<_> = posts.iter().map(Identifiable::id).copied().collect();
     post::table
         .filter(post::id.eq_any(&post_ids))
@@ -352,7 +494,10 @@ fn get_owners(conn: &mut PgConnection, posts: &[Post]) -> QueryResult<Vec<Option
         .map(|post_info| {
             resource::order_like(post_info, posts, |&(id, ..)| id)
                 .into_iter()
-                .map(|post_owner| post_owner.map(|(_, username, avatar_style)| MicroUser::new(username, avatar_style)))
+                .map(|post_owner| {
+                    post_owner
+                        .map(|(_, username, avatar_style)| MicroUser::new_with_fields(username, avatar_style, fields))
+                })
                 .collect()
         })
 }
@@ -364,14 +509,15 @@ fn get_content_urls(posts: &[Post]) -> Vec<String> {
         .collect()
 }
 
-fn get_thumbnail_urls(posts: &[Post]) -> Vec<String> {
-    posts
-        .iter()
-        .map(|post| PostHash::new(post.id).thumbnail_url())
-        .collect()
+fn get_thumbnails(posts: &[Post]) -> Vec<ThumbnailInfo> {
+    posts.iter().map(ThumbnailInfo::new).collect()
 }
 
-fn get_tags(conn: &mut PgConnection, posts: &[Post]) -> QueryResult<Vec<Vec<MicroTag>>> {
+fn get_tags(
+    conn: &mut PgConnection,
+    posts: &[Post],
+    fields: Option<&MicroTagFieldTable<bool>>,
+) -> QueryResult<Vec<Vec<MicroTag>>> {
     let tag_info = tag::table
         .inner_join(tag_statistics::table)
         .inner_join(tag_category::table)
@@ -403,28 +549,38 @@ fn get_tags(conn: &mut PgConnection, posts: &[Post]) -> QueryResult<Vec<Vec<Micr
         .map(|tags_on_post| {
             tags_on_post
                 .into_iter()
-                .map(|(post_tag, category_id, usages)| MicroTag {
-                    names: names_map[&post_tag.tag_id].clone(),
-                    category: category_names[&category_id].clone(),
-                    usages,
+                .map(|(post_tag, category_id, usages)| {
+                    MicroTag::new(
+                        names_map[&post_tag.tag_id].clone(),
+                        category_names[&category_id].clone(),
+                        usages,
+                        fields,
+                    )
                 })
                 .collect()
         })
         .collect())
 }
 
-fn get_comments(conn: &mut PgConnection, client: Client, posts: &[Post]) -> QueryResult<Vec<Vec<CommentInfo>>> {
+fn get_comments(
+    conn: &mut PgConnection,
+    client: Client,
+    posts: &[Post],
+    fields: Option<&CommentFieldTable<bool>>,
+) -> QueryResult<Vec<Vec<CommentInfo>>> {
     type CommentData = (Comment, i64, Option<(SmallString, AvatarStyle)>);
     let comments: Vec<CommentData> = Comment::belonging_to(posts)
         .inner_join(comment_statistics::table)
         .left_join(user::table)
         .select((Comment::as_select(), comment_statistics::score, (user::name, user::avatar_style).nullable()))
-        .order(comment::creation_time)
+        .order((comment::pinned.desc(), comment::creation_time))
         .load(conn)?;
     let comment_ids: Vec<i64> = comments.iter().map(|(comment, ..)| comment.id).collect();
 
+    let selected = |field: CommentField| resource::is_micro_field_selected(fields, field);
     let client_scores: HashMap<i64, Score> = client
         .id
+        .filter(|_| selected(CommentField::OwnScore))
         .map(|user_id| {
             comment_score::table
                 .select((comment_score::comment_id, comment_score::score))
@@ -446,15 +602,18 @@ fn get_comments(conn: &mut PgConnection, client: Client, posts: &[Post]) -> Quer
                 .map(|(comment, score, owner)| {
                     let id = comment.id;
                     CommentInfo {
-                        version: Some(comment.last_edit_time),
-                        id: Some(id),
-                        post_id: Some(post.id),
-                        user: Some(owner.map(|(username, avatar_style)| MicroUser::new(username, avatar_style))),
-                        text: Some(comment.text),
-                        creation_time: Some(comment.creation_time),
-                        last_edit_time: Some(comment.last_edit_time),
-                        score: Some(score),
-                        own_score: Some(client_scores.get(&id).copied().map(Rating::from).unwrap_or_default()),
+                        version: selected(CommentField::Version).then_some(comment.last_edit_time),
+                        id: selected(CommentField::Id).then_some(id),
+                        post_id: selected(CommentField::PostId).then_some(post.id),
+                        user: selected(CommentField::User)
+                            .then(|| owner.map(|(username, avatar_style)| MicroUser::new(username, avatar_style))),
+                        text: selected(CommentField::Text).then_some(comment.text),
+                        creation_time: selected(CommentField::CreationTime).then_some(comment.creation_time),
+                        last_edit_time: selected(CommentField::LastEditTime).then_some(comment.last_edit_time),
+                        pinned: selected(CommentField::Pinned).then_some(comment.pinned),
+                        score: selected(CommentField::Score).then_some(score),
+                        own_score: selected(CommentField::OwnScore)
+                            .then(|| client_scores.get(&id).copied().map(Rating::from).unwrap_or_default()),
                     }
                 })
                 .collect()
@@ -462,19 +621,101 @@ fn get_comments(conn: &mut PgConnection, client: Client, posts: &[Post]) -> Quer
         .collect())
 }
 
-fn get_relations(conn: &mut PgConnection, posts: &[Post]) -> QueryResult<Vec<Vec<MicroPost>>> {
+fn get_relations(
+    conn: &mut PgConnection,
+    client: Client,
+    posts: &[Post],
+    fields: Option<&MicroPostFieldTable<bool>>,
+) -> QueryResult<Vec<Vec<MicroPost>>> {
     let related_posts: Vec<PostRelation> = PostRelation::belonging_to(posts)
         .order(post_relation::child_id)
         .load(conn)?;
+    let related_ids: Vec<_> = related_posts.iter().map(|relation| relation.child_id).collect();
+    let visible_ids = search::post::visible_post_ids(conn, client, &related_ids)?;
     Ok(related_posts
         .grouped_by(posts)
         .into_iter()
         .map(|post_relations| {
             post_relations
                 .into_iter()
-                .map(|relation| MicroPost {
-                    id: relation.child_id,
-                    thumbnail_url: PostHash::new(relation.child_id).thumbnail_url(),
+                .filter(|relation| visible_ids.contains(&relation.child_id))
+                .map(|relation| MicroPost::new(relation.child_id, fields))
+                .collect()
+        })
+        .collect())
+}
+
+/// Finds up to `similar_posts_limit` posts that look like each of the given `posts`, ordered from
+/// most to least similar. Only images and animations have meaningful signatures, so other post
+/// types have no similar posts.
+///
+/// Candidates for the whole batch are retrieved in a single query. Each post is then only compared
+/// against the candidates that share at least one signature word with it.
+fn get_similar_posts(
+    conn: &mut PgConnection,
+    client: Client,
+    posts: &[Post],
+    fields: Option<&MicroPostFieldTable<bool>>,
+) -> QueryResult<Vec<Vec<SimilarMicroPost>>> {
+    let image_post_ids: Vec<_> = posts
+        .iter()
+        .filter(|post| matches!(post.type_, PostType::Image | PostType::Animation))
+        .map(|post| post.id)
+        .collect();
+    let signatures: Vec<PostSignature> = post_signature::table
+        .select(PostSignature::as_select())
+        .filter(post_signature::post_id.eq_any(&image_post_ids))
+        .load(conn)?;
+    if signatures.is_empty() {
+        return Ok(posts.iter().map(|_| Vec::new()).collect());
+    }
+
+    let indexes: HashMap<i64, _> = signatures
+        .iter()
+        .map(|post_signature| (post_signature.post_id, signature::generate_indexes(&post_signature.signature)))
+        .collect();
+    let mut words: Vec<i32> = indexes.values().flatten().copied().collect();
+    words.sort_unstable();
+    words.dedup();
+
+    // Don't reveal posts the client isn't allowed to see
+    let mut candidates = PostSignature::find_similar_candidates(conn, &words)?;
+    let candidate_ids: Vec<_> = candidates.iter().map(|candidate| candidate.post_id).collect();
+    let visible_post_ids = search::post::visible_post_ids(conn, client, &candidate_ids)?;
+    candidates.retain(|candidate| visible_post_ids.contains(&candidate.post_id));
+    let candidates: Vec<_> = candidates
+        .into_iter()
+        .map(|candidate| (signature::generate_indexes(&candidate.signature), candidate))
+        .collect();
+
+    let distance_threshold = 1.0 - config::get().similar_posts_threshold;
+    Ok(resource::order_like(signatures, posts, |signature| signature.post_id)
+        .into_iter()
+        .map(|post_signature| {
+            let Some(post_signature) = post_signature else {
+                return Vec::new();
+            };
+
+            let post_indexes = indexes[&post_signature.post_id];
+            let signature_cache = signature::cache(&post_signature.signature);
+            let mut similar_signatures: Vec<_> = candidates
+                .iter()
+                .filter(|(_, candidate)| candidate.post_id != post_signature.post_id)
+                .filter(|(candidate_indexes, _)| std::iter::zip(post_indexes, candidate_indexes).any(|(a, &b)| a == b))
+                .filter_map(|(_, candidate)| {
+                    let distance = signature::distance(&signature_cache, &candidate.signature);
+                    (distance < distance_threshold).then_some((candidate.post_id, distance))
+                })
+                .collect();
+            similar_signatures.sort_unstable_by(|(id_a, dist_a), (id_b, dist_b)| {
+                dist_a.partial_cmp(dist_b).unwrap().then(id_a.cmp(id_b))
+            });
+            similar_signatures.truncate(config::get().similar_posts_limit);
+            similar_signatures
+                .into_iter()
+                .map(|(post_id, distance)| SimilarMicroPost {
+                    distance,
+                    post: MicroPost::new(post_id, fields),
                 })
                 .collect()
         })
@@ -503,23 +744,43 @@ fn get_pools(conn: &mut PgConnection, posts: &[Post]) -> QueryResult<Vec<Vec<Mic
         .collect();
     let pool_descriptions: HashMap<i64, PoolDescription> = pool::table
         .select((pool::id, pool::description))
-        .filter(pool::id.eq_any(pool_ids))
+        .filter(pool::id.eq_any(&pool_ids))
         .load(conn)?
         .into_iter()
         .collect();
 
+    // Pool orders can have gaps, so positions are determined from the full list of posts in each pool
+    let mut pool_members: HashMap<i64, Vec<i64>> = HashMap::new();
+    pool_post::table
+        .select((pool_post::pool_id, pool_post::post_id))
+        .filter(pool_post::pool_id.eq_any(pool_ids))
+        .order((pool_post::pool_id, pool_post::order))
+        .load::<(i64, i64)>(conn)?
+        .into_iter()
+        .for_each(|(pool_id, post_id)| pool_members.entry(pool_id).or_default().push(post_id));
+
     Ok(pool_posts
         .grouped_by(posts)
         .into_iter()
         .map(|pools_on_post| {
             pools_on_post
                 .into_iter()
-                .map(|(pool_post, category_id, post_count)| MicroPool {
-                    id: pool_post.pool_id,
-                    names: names_map[&pool_post.pool_id].clone(),
-                    category: category_names[&category_id].clone(),
-                    description: pool_descriptions[&pool_post.pool_id].clone(),
-                    post_count,
+                .map(|(pool_post, category_id, post_count)| {
+                    let members = &pool_members[&pool_post.pool_id];
+                    let index = members
+                        .iter()
+                        .position(|&post_id| post_id == pool_post.post_id)
+                        .unwrap_or_default();
+                    MicroPool {
+                        id: pool_post.pool_id,
+                        names: names_map[&pool_post.pool_id].clone(),
+                        category: category_names[&category_id].clone(),
+                        description: pool_descriptions[&pool_post.pool_id].clone(),
+                        post_count,
+                        position: index as i64,
+                        previous_post_id: index.checked_sub(1).map(|prev| members[prev]),
+                        next_post_id: members.get(index + 1).copied(),
+                    }
                 })
                 .collect()
         })
@@ -528,6 +789,7 @@ fn get_pools(conn: &mut PgConnection, posts: &[Post]) -> QueryResult<Vec<Vec<Mic
 
 fn get_notes(conn: &mut PgConnection, posts: &[Post]) -> QueryResult<Vec<Vec<Note>>> {
     Ok(PostNote::belonging_to(posts)
+        .order_by(post_note::id)
         .load(conn)?
         .grouped_by(posts)
         .into_iter()
@@ -567,7 +829,36 @@ fn get_client_favorites(conn: &mut PgConnection, client: Client, posts: &[Post])
     }
 }
 
-fn get_users_who_favorited(conn: &mut PgConnection, posts: &[Post]) -> QueryResult<Vec<Vec<MicroUser>>> {
+/// Retrieves the user who most recently featured each post, if any.
+fn get_last_featured_by(
+    conn: &mut PgConnection,
+    posts: &[Post],
+    fields: Option<&MicroUserFieldTable<bool>>,
+) -> QueryResult<Vec<Option<MicroUser>>> {
+    let post_ids: Vec<_> = posts.iter().map(Identifiable::id).copied().collect();
+    post_feature::table
+        .filter(post_feature::post_id.eq_any(&post_ids))
+        .inner_join(user::table)
+        .select((post_feature::post_id, user::name, user::avatar_style))
+        .distinct_on(post_feature::post_id)
+        .order_by((post_feature::post_id, post_feature::time.desc()))
+        .load::<(i64, SmallString, AvatarStyle)>(conn)
+        .map(|last_features| {
+            resource::order_like(last_features, posts, |&(id, ..)| id)
+                .into_iter()
+                .map(|last_feature| {
+                    last_feature
+                        .map(|(_, username, avatar_style)| MicroUser::new_with_fields(username, avatar_style, fields))
+                })
+                .collect()
+        })
+}
+
+fn get_users_who_favorited(
+    conn: &mut PgConnection,
+    posts: &[Post],
+    fields: Option<&MicroUserFieldTable<bool>>,
+) -> QueryResult<Vec<Vec<MicroUser>>> {
     let users_who_favorited: Vec<(i64, SmallString, AvatarStyle)> = PostFavorite::belonging_to(posts)
         .inner_join(user::table)
         .select((post_favorite::post_id, user::name, user::avatar_style))
@@ -587,26 +878,16 @@ fn get_users_who_favorited(conn: &mut PgConnection, posts: &[Post]) -> QueryResu
         .map(|(_, users)| {
             users
                 .into_iter()
-                .map(|(username, avatar_style)| MicroUser::new(username, avatar_style))
+                .map(|(username, avatar_style)| MicroUser::new_with_fields(username, avatar_style, fields))
                 .collect()
         })
         .collect())
 }
 
-#[doc(hidden)]
-#[macro_export]
-macro_rules! get_post_stats {
-    ($conn:expr, $posts:expr, $column:expr, $return_type:ty) => {{
-        let post_ids: Vec<_> = $posts.iter().map(Identifiable::id).copied().collect();
-        post_statistics::table
-            .select((post_statistics::post_id, $column))
-            .filter(post_statistics::post_id.eq_any(&post_ids))
-            .load($conn)
-            .map(|post_stats| {
-                resource::order_transformed_as(post_stats, &post_ids, |&(id, _)| id)
-                    .into_iter()
-                    .map(|(_, stat)| stat)
-                    .collect::<Vec<$return_type>>()
-            })
-    }};
+fn get_statistics(conn: &mut PgConnection, posts: &[Post]) -> QueryResult<Vec<PostStatistics>> {
+    let post_ids: Vec<_> = posts.iter().map(Identifiable::id).copied().collect();
+    let statistics = PostStatistics::belonging_to(posts)
+        .select(PostStatistics::as_select())
+        .load(conn)?;
+    Ok(resource::order_as(statistics, &post_ids))
 }
@@ -1,7 +1,7 @@
 use crate::model::tag::{Tag, TagImplication, TagName, TagSuggestion};
-use crate::resource::{self, BoolFill};
+use crate::resource::{self, BoolFill, SelectSubField};
 use crate::schema::{tag, tag_category, tag_implication, tag_name, tag_statistics, tag_suggestion};
-use crate::string::SmallString;
+use crate::string::{self, SmallString};
 use crate::time::DateTime;
 use diesel::prelude::*;
 use serde::Serialize;
@@ -9,13 +9,45 @@ use serde_with::skip_serializing_none;
 use std::collections::{HashMap, HashSet};
 use std::rc::Rc;
 use strum::{EnumString, EnumTable};
+use utoipa::ToSchema;
 
-#[derive(Serialize)]
+#[skip_serializing_none]
+#[derive(Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct MicroTag {
-    pub names: Rc<[SmallString]>,
-    pub category: SmallString,
-    pub usages: i64,
+    names: Option<Rc<[SmallString]>>,
+    category: Option<SmallString>,
+    usages: Option<i64>,
+}
+
+impl MicroTag {
+    /// Creates a micro tag containing the selected `fields`, or all fields if `fields` is `None`.
+    pub fn new(
+        names: Rc<[SmallString]>,
+        category: SmallString,
+        usages: i64,
+        fields: Option<&MicroTagFieldTable<bool>>,
+    ) -> Self {
+        Self {
+            names: resource::is_micro_field_selected(fields, MicroTagField::Names).then_some(names),
+            category: resource::is_micro_field_selected(fields, MicroTagField::Category).then_some(category),
+            usages: resource::is_micro_field_selected(fields, MicroTagField::Usages).then_some(usages),
+        }
+    }
+}
+
+#[derive(Clone, Copy, EnumString, EnumTable)]
+#[strum(serialize_all = "camelCase")]
+pub enum MicroTagField {
+    Names,
+    Category,
+    Usages,
+}
+
+impl BoolFill for MicroTagFieldTable<bool> {
+    fn filled(val: bool) -> Self {
+        Self::filled(val)
+    }
 }
 
 #[derive(Clone, Copy, EnumString, EnumTable)]
@@ -23,6 +55,7 @@ pub struct MicroTag {
 pub enum Field {
     Version,
     Description,
+    RenderedDescription,
     CreationTime,
     LastEditTime,
     Category,
@@ -38,12 +71,15 @@ impl BoolFill for FieldTable<bool> {
     }
 }
 
+impl SelectSubField<Field> for FieldTable<bool> {}
+
 #[skip_serializing_none]
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct TagInfo {
     version: Option<DateTime>,
     description: Option<String>,
+    rendered_description: Option<String>,
     creation_time: Option<DateTime>,
     last_edit_time: Option<DateTime>,
     category: Option<SmallString>,
@@ -98,6 +134,8 @@ impl TagInfo {
             .rev()
             .map(|tag| Self {
                 version: fields[Field::Version].then_some(tag.last_edit_time),
+                rendered_description: fields[Field::RenderedDescription]
+                    .then(|| string::render_markdown(&tag.description)),
                 description: fields[Field::Description].then_some(tag.description),
                 creation_time: fields[Field::CreationTime].then_some(tag.creation_time),
                 last_edit_time: fields[Field::LastEditTime].then_some(tag.last_edit_time),
@@ -179,10 +217,13 @@ fn get_implications(conn: &mut PgConnection, tags: &[Tag]) -> QueryResult<Vec<Ve
         .map(|implications_on_tag| {
             implications_on_tag
                 .into_iter()
-                .map(|(implication, category_id, usages)| MicroTag {
-                    names: names_map[&implication.child_id].clone(),
-                    category: category_names[&category_id].clone(),
-                    usages,
+                .map(|(implication, category_id, usages)| {
+                    MicroTag::new(
+                        names_map[&implication.child_id].clone(),
+                        category_names[&category_id].clone(),
+                        usages,
+                        None,
+                    )
                 })
                 .collect()
         })
@@ -218,10 +259,13 @@ fn get_suggestions(conn: &mut PgConnection, tags: &[Tag]) -> QueryResult<Vec<Vec
         .map(|suggestions_on_tag| {
             suggestions_on_tag
                 .into_iter()
-                .map(|(suggestion, category_id, usages)| MicroTag {
-                    names: names_map[&suggestion.child_id].clone(),
-                    category: category_names[&category_id].clone(),
-                    usages,
+                .map(|(suggestion, category_id, usages)| {
+                    MicroTag::new(
+                        names_map[&suggestion.child_id].clone(),
+                        category_names[&category_id].clone(),
+                        usages,
+                        None,
+                    )
                 })
                 .collect()
         })
@@ -3,7 +3,7 @@ use crate::get_user_stats;
 use crate::model::enums::{AvatarStyle, Score, UserRank};
 use crate::model::post::PostScore;
 use crate::model::user::User;
-use crate::resource::{self, BoolFill};
+use crate::resource::{self, BoolFill, SelectSubField};
 use crate::schema::{post_score, user, user_statistics};
 use crate::string::SmallString;
 use crate::time::DateTime;
@@ -12,21 +12,49 @@ use diesel::prelude::*;
 use serde::Serialize;
 use serde_with::skip_serializing_none;
 use strum::{EnumString, EnumTable};
+use utoipa::ToSchema;
 
-#[derive(Serialize)]
+#[skip_serializing_none]
+#[derive(Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct MicroUser {
-    name: SmallString,
-    avatar_url: String,
+    name: Option<SmallString>,
+    avatar_url: Option<String>,
 }
 
 impl MicroUser {
     pub fn new(name: SmallString, avatar_style: AvatarStyle) -> Self {
-        let avatar_url = match avatar_style {
-            AvatarStyle::Gravatar => hash::gravatar_url(&name),
-            AvatarStyle::Manual => hash::custom_avatar_url(&name),
-        };
-        Self { name, avatar_url }
+        Self::new_with_fields(name, avatar_style, None)
+    }
+
+    /// Creates a micro user containing the selected `fields`, or all fields if `fields` is `None`.
+    pub fn new_with_fields(
+        name: SmallString,
+        avatar_style: AvatarStyle,
+        fields: Option<&MicroUserFieldTable<bool>>,
+    ) -> Self {
+        let avatar_url =
+            resource::is_micro_field_selected(fields, MicroUserField::AvatarUrl).then(|| match avatar_style {
+                AvatarStyle::Gravatar => hash::gravatar_url(&name),
+                AvatarStyle::Manual => hash::custom_avatar_url(&name),
+            });
+        Self {
+            name: resource::is_micro_field_selected(fields, MicroUserField::Name).then_some(name),
+            avatar_url,
+        }
+    }
+}
+
+#[derive(Clone, Copy, EnumString, EnumTable)]
+#[strum(serialize_all = "camelCase")]
+pub enum MicroUserField {
+    Name,
+    AvatarUrl,
+}
+
+impl BoolFill for MicroUserFieldTable<bool> {
+    fn filled(val: bool) -> Self {
+        Self::filled(val)
     }
 }
 
@@ -42,6 +70,7 @@ pub enum Field {
     Version,
     Name,
     Email,
+    EmailVerified,
     Rank,
     LastLoginTime,
     CreationTime,
@@ -60,13 +89,16 @@ impl BoolFill for FieldTable<bool> {
     }
 }
 
+impl SelectSubField<Field> for FieldTable<bool> {}
+
 #[skip_serializing_none]
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct UserInfo {
     version: Option<DateTime>,
     name: Option<SmallString>,
     email: Option<PrivateData<Option<SmallString>>>,
+    email_verified: Option<PrivateData<bool>>,
     rank: Option<UserRank>,
     last_login_time: Option<DateTime>,
     creation_time: Option<DateTime>,
@@ -140,6 +172,10 @@ impl UserInfo {
                     Visibility::Full => PrivateData::Expose(user.email),
                     Visibility::PublicOnly => PrivateData::Visible(false),
                 }),
+                email_verified: fields[Field::EmailVerified].then_some(match visibility {
+                    Visibility::Full => PrivateData::Expose(user.email_verified),
+                    Visibility::PublicOnly => PrivateData::Visible(false),
+                }),
                 rank: fields[Field::Rank].then_some(user.rank),
                 last_login_time: fields[Field::LastLoginTime].then_some(user.last_login_time),
                 creation_time: fields[Field::CreationTime].then_some(user.creation_time),
@@ -166,7 +202,7 @@ impl UserInfo {
     }
 }
 
-#[derive(Clone, Serialize)]
+#[derive(Clone, Serialize, ToSchema)]
 #[serde(untagged)]
 enum PrivateData<T> {
     Expose(T),
@@ -1,10 +1,11 @@
 use crate::model::user::UserToken;
-use crate::resource::BoolFill;
 use crate::resource::user::MicroUser;
+use crate::resource::{BoolFill, SelectSubField};
 use crate::time::DateTime;
 use serde::Serialize;
 use serde_with::skip_serializing_none;
 use strum::{EnumString, EnumTable};
+use utoipa::ToSchema;
 use uuid::Uuid;
 
 #[derive(Clone, Copy, EnumString, EnumTable)]
@@ -27,8 +28,10 @@ impl BoolFill for FieldTable<bool> {
     }
 }
 
+impl SelectSubField<Field> for FieldTable<bool> {}
+
 #[skip_serializing_none]
-#[derive(Serialize)]
+#[derive(Serialize, ToSchema)]
 #[serde(rename_all = "camelCase")]
 pub struct UserTokenInfo {
     version: Option<DateTime>,
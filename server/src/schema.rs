@@ -1,5 +1,18 @@
 // @generated automatically by Diesel CLI.
 
+diesel::table! {
+    background_job (id) {
+        id -> Int8,
+        name -> Text,
+        total -> Int8,
+        processed -> Int8,
+        errors -> Int8,
+        started_at -> Timestamptz,
+        completed_at -> Nullable<Timestamptz>,
+        failed -> Bool,
+    }
+}
+
 diesel::table! {
     comment (id) {
         id -> Int8,
@@ -8,6 +21,7 @@ diesel::table! {
         text -> Text,
         creation_time -> Timestamptz,
         last_edit_time -> Timestamptz,
+        pinned -> Bool,
     }
 }
 
@@ -40,6 +54,15 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    password_reset_token (user_id) {
+        user_id -> Int8,
+        token_hash -> Text,
+        expiration_time -> Timestamptz,
+        creation_time -> Timestamptz,
+    }
+}
+
 diesel::table! {
     pool (id) {
         id -> Int8,
@@ -110,6 +133,7 @@ diesel::table! {
         last_edit_time -> Timestamptz,
         generated_thumbnail_size -> Int8,
         custom_thumbnail_size -> Int8,
+        checksum_algorithm -> Int2,
     }
 }
 
@@ -139,6 +163,14 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    post_redirect (removed_id) {
+        removed_id -> Int8,
+        target_id -> Int8,
+        merged_at -> Timestamptz,
+    }
+}
+
 diesel::table! {
     post_relation (parent_id, child_id) {
         parent_id -> Int8,
@@ -177,6 +209,7 @@ diesel::table! {
         last_comment_time -> Nullable<Timestamptz>,
         last_favorite_time -> Nullable<Timestamptz>,
         last_feature_time -> Nullable<Timestamptz>,
+        view_count -> Int8,
     }
 }
 
@@ -261,6 +294,19 @@ diesel::table! {
         last_login_time -> Timestamptz,
         last_edit_time -> Timestamptz,
         custom_avatar_size -> Int8,
+        email_verified -> Bool,
+        email_verification_token -> Nullable<Uuid>,
+    }
+}
+
+diesel::table! {
+    user_audit (id) {
+        id -> Int8,
+        user_id -> Int8,
+        changed_by -> Nullable<Int8>,
+        old_rank -> Int2,
+        new_rank -> Int2,
+        changed_at -> Timestamptz,
     }
 }
 
@@ -292,6 +338,7 @@ diesel::joinable!(comment -> user (user_id));
 diesel::joinable!(comment_score -> comment (comment_id));
 diesel::joinable!(comment_score -> user (user_id));
 diesel::joinable!(comment_statistics -> comment (comment_id));
+diesel::joinable!(password_reset_token -> user (user_id));
 diesel::joinable!(pool -> pool_category (category_id));
 diesel::joinable!(pool_category_statistics -> pool_category (category_id));
 diesel::joinable!(pool_name -> pool (pool_id));
@@ -304,6 +351,7 @@ diesel::joinable!(post_favorite -> user (user_id));
 diesel::joinable!(post_feature -> post (post_id));
 diesel::joinable!(post_feature -> user (user_id));
 diesel::joinable!(post_note -> post (post_id));
+diesel::joinable!(post_redirect -> post (target_id));
 diesel::joinable!(post_score -> post (post_id));
 diesel::joinable!(post_score -> user (user_id));
 diesel::joinable!(post_signature -> post (post_id));
@@ -318,10 +366,12 @@ diesel::joinable!(user_statistics -> user (user_id));
 diesel::joinable!(user_token -> user (user_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
+    background_job,
     comment,
     comment_score,
     comment_statistics,
     database_statistics,
+    password_reset_token,
     pool,
     pool_category,
     pool_category_statistics,
@@ -332,6 +382,7 @@ diesel::allow_tables_to_appear_in_same_query!(
     post_favorite,
     post_feature,
     post_note,
+    post_redirect,
     post_relation,
     post_score,
     post_signature,
@@ -345,6 +396,7 @@ diesel::allow_tables_to_appear_in_same_query!(
     tag_statistics,
     tag_suggestion,
     user,
+    user_audit,
     user_statistics,
     user_token,
 );
@@ -1,6 +1,6 @@
 use crate::api::ApiResult;
 use crate::schema::{comment, comment_statistics, database_statistics, user};
-use crate::search::{Order, ParsedSort, SearchCriteria};
+use crate::search::{Order, Paginate, ParsedSort, SearchCriteria};
 use crate::{api, apply_filter, apply_random_sort, apply_sort, apply_str_filter, apply_time_filter};
 use diesel::dsl::{InnerJoin, IntoBoxed, LeftJoin, Select};
 use diesel::pg::Pg;
@@ -106,5 +106,16 @@ impl<'a> QueryBuilder<'a> {
     }
 }
 
+impl Paginate for QueryBuilder<'_> {
+    fn total(&mut self, conn: &mut PgConnection) -> ApiResult<i64> {
+        self.count(conn)
+    }
+
+    fn page(&mut self, conn: &mut PgConnection, offset: i64, limit: i64) -> ApiResult<Vec<i64>> {
+        self.set_offset_and_limit(offset, limit);
+        self.load(conn)
+    }
+}
+
 type BoxedQuery<'a> =
     IntoBoxed<'a, LeftJoin<InnerJoin<Select<comment::table, comment::id>, comment_statistics::table>, user::table>, Pg>;
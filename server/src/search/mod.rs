@@ -6,6 +6,8 @@ pub mod post;
 pub mod tag;
 pub mod user;
 
+use crate::api::ApiResult;
+use diesel::PgConnection;
 use std::borrow::Cow;
 use std::collections::HashSet;
 use std::ops::{Not, Range};
@@ -20,6 +22,26 @@ pub enum TimeParsingError {
     TooManyArgs,
     NotAnInteger(#[from] std::num::ParseIntError),
     OutOfRange(#[from] time::error::ComponentRange),
+    InvalidTimestamp(#[from] time::error::Parse),
+}
+
+#[derive(Debug, thiserror::Error)]
+#[error(transparent)]
+pub enum FileSizeParsingError {
+    NotAnInteger(#[from] std::num::ParseIntError),
+    #[error("File size is too large")]
+    OutOfRange,
+    #[error("Unknown file size unit")]
+    UnknownUnit,
+}
+
+/// A search query whose results can be loaded one page at a time from a `Conn`.
+pub trait Paginate<Conn = PgConnection> {
+    /// Returns the total number of results.
+    fn total(&mut self, conn: &mut Conn) -> ApiResult<i64>;
+
+    /// Returns the ids of at most `limit` results, starting at `offset`.
+    fn page(&mut self, conn: &mut Conn, offset: i64, limit: i64) -> ApiResult<Vec<i64>>;
 }
 
 /// Stores filters, sorts, offset, and limit of a search query.
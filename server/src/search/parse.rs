@@ -1,10 +1,12 @@
 use crate::api::{self, ApiResult};
-use crate::search::{Condition, StrCondition, TimeParsingError};
+use crate::search::{Condition, FileSizeParsingError, StrCondition, TimeParsingError};
 use crate::time::DateTime;
 use std::borrow::Cow;
+use std::num::ParseFloatError;
 use std::ops::Range;
 use std::str::FromStr;
-use time::{Date, Duration, Month, OffsetDateTime, Time};
+use time::format_description::well_known::Rfc3339;
+use time::{Date, Duration, Month, OffsetDateTime, Time, UtcOffset};
 
 /// Splits `text` into two parts by an unescaped `delimiter`.
 pub fn split_once(text: &str, delimiter: char) -> Option<(&str, &str)> {
@@ -39,6 +41,51 @@ pub fn time_condition(condition: &str) -> ApiResult<Condition<Range<DateTime>>>
         .map_err(api::Error::from)
 }
 
+/// Parses file size `condition`. Sizes are in bytes unless they end with a unit suffix.
+pub fn file_size_condition(condition: &str) -> ApiResult<Condition<i64>> {
+    if let Some(split_str) = condition.split_once("..") {
+        return match split_str {
+            (left, "") => parse_file_size(left)
+                .map(Condition::GreaterEq)
+                .map_err(api::Error::from),
+            ("", right) => parse_file_size(right).map(Condition::LessEq).map_err(api::Error::from),
+            (left, right) => Ok(Condition::Range(parse_file_size(left)?..parse_file_size(right)?)),
+        };
+    }
+    condition
+        .split(',')
+        .map(parse_file_size)
+        .collect::<Result<_, _>>()
+        .map(Condition::Values)
+        .map_err(api::Error::from)
+}
+
+/// Parses aspect ratio `condition`. Ratios can be given as decimals or as fractions like `16:9`.
+/// `landscape` and `portrait` are shorthands for `1..` and `..1`, respectively.
+pub fn aspect_ratio_condition(condition: &str) -> ApiResult<Condition<f32>> {
+    match condition {
+        "landscape" => return Ok(Condition::GreaterEq(1.0)),
+        "portrait" => return Ok(Condition::LessEq(1.0)),
+        _ => (),
+    }
+    if let Some(split_str) = condition.split_once("..") {
+        return match split_str {
+            (left, "") => Ok(Condition::GreaterEq(parse_aspect_ratio(left).map_err(Box::from)?)),
+            ("", right) => Ok(Condition::LessEq(parse_aspect_ratio(right).map_err(Box::from)?)),
+            (left, right) => Ok(Condition::Range(
+                parse_aspect_ratio(left).map_err(Box::from)?..parse_aspect_ratio(right).map_err(Box::from)?,
+            )),
+        };
+    }
+    condition
+        .split(',')
+        .map(parse_aspect_ratio)
+        .collect::<Result<_, _>>()
+        .map(Condition::Values)
+        .map_err(Box::from)
+        .map_err(api::Error::from)
+}
+
 /// Parses a non-string non-time `condition`.
 pub fn condition<T>(condition: &str) -> ApiResult<Condition<T>>
 where
@@ -158,6 +205,13 @@ fn parse_time(time: &str) -> Result<Range<DateTime>, TimeParsingError> {
         return Ok(DateTime::yesterday()..DateTime::today());
     }
 
+    // Timestamps are normalized to UTC and match the second they fall in
+    if time.contains('T') {
+        let timestamp = OffsetDateTime::parse(time, &Rfc3339)?.to_offset(UtcOffset::UTC);
+        let start_time = timestamp.replace_nanosecond(0)?;
+        return Ok(start_time.into()..start_time.saturating_add(Duration::SECOND).into());
+    }
+
     let mut date_iterator = time.split('-');
     let year: i32 = date_iterator
         .next()
@@ -185,6 +239,37 @@ fn parse_time(time: &str) -> Result<Range<DateTime>, TimeParsingError> {
     Ok(start_date..end_date.into())
 }
 
+/// Parses single file `size`, which can have either an SI (KB, MB, GB, TB) or
+/// binary (KiB, MiB, GiB, TiB) unit suffix. Units are case-insensitive.
+fn parse_file_size(size: &str) -> Result<i64, FileSizeParsingError> {
+    let unit_start = size.find(|c: char| c.is_ascii_alphabetic()).unwrap_or(size.len());
+    let (number, unit) = size.split_at(unit_start);
+    let multiplier: i64 = match unit.to_ascii_lowercase().as_str() {
+        "" | "b" => 1,
+        "kb" => 1000,
+        "mb" => 1000_i64.pow(2),
+        "gb" => 1000_i64.pow(3),
+        "tb" => 1000_i64.pow(4),
+        "kib" => 1024,
+        "mib" => 1024_i64.pow(2),
+        "gib" => 1024_i64.pow(3),
+        "tib" => 1024_i64.pow(4),
+        _ => return Err(FileSizeParsingError::UnknownUnit),
+    };
+    number
+        .parse::<i64>()?
+        .checked_mul(multiplier)
+        .ok_or(FileSizeParsingError::OutOfRange)
+}
+
+/// Parses single aspect `ratio`, either as a decimal or as a `width:height` fraction.
+fn parse_aspect_ratio(ratio: &str) -> Result<f32, ParseFloatError> {
+    match ratio.split_once(':') {
+        Some((width, height)) => Ok(width.parse::<f32>()? / height.parse::<f32>()?),
+        None => ratio.parse(),
+    }
+}
+
 fn parse_month(text: &str) -> Result<Month, TimeParsingError> {
     let number: u8 = text.parse()?;
     number.try_into().map_err(TimeParsingError::from)
@@ -210,12 +295,61 @@ mod test {
         assert_eq!(parse_time("2024-07-11")?, DateTime::from_date(2024, jul, 11)?..DateTime::from_date(2024, jul, 12)?);
         assert_eq!(parse_time("2024-2-29")?, DateTime::from_date(2024, feb, 29)?..DateTime::from_date(2024, mar, 1)?);
 
+        let timestamp = |hour, second| -> Result<DateTime, TimeParsingError> {
+            let date = Date::from_calendar_date(2024, jul, 11)?;
+            Ok(OffsetDateTime::new_utc(date, Time::from_hms(hour, 0, second)?).into())
+        };
+        assert_eq!(parse_time("2024-07-11T10:00:00Z")?, timestamp(10, 0)?..timestamp(10, 1)?);
+        assert_eq!(parse_time("2024-07-11T12:00:00.5+02:00")?, timestamp(10, 0)?..timestamp(10, 1)?);
+        assert_eq!(parse_time("2024-07-11T05:00:00-05:00")?, timestamp(10, 0)?..timestamp(10, 1)?);
+
         assert!(parse_time("").is_err());
         assert!(parse_time("2000-01-01-01").is_err());
         assert!(parse_time("2025-2-29").is_err());
         assert!(parse_time("Hello World!").is_err());
         assert!(parse_time("a-b-c").is_err());
         assert!(parse_time("--").is_err());
+        assert!(parse_time("2024-07-11T10:00").is_err());
+        assert!(parse_time("2024-07-11T10:00:00").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn file_size_parsing() -> Result<(), FileSizeParsingError> {
+        assert_eq!(parse_file_size("0")?, 0);
+        assert_eq!(parse_file_size("512")?, 512);
+        assert_eq!(parse_file_size("512B")?, 512);
+        assert_eq!(parse_file_size("3KB")?, 3000);
+        assert_eq!(parse_file_size("3kib")?, 3072);
+        assert_eq!(parse_file_size("10MB")?, 10_000_000);
+        assert_eq!(parse_file_size("10MiB")?, 10 * 1024 * 1024);
+        assert_eq!(parse_file_size("2gb")?, 2_000_000_000);
+        assert_eq!(parse_file_size("1TiB")?, 1024_i64.pow(4));
+
+        assert!(parse_file_size("").is_err());
+        assert!(parse_file_size("MB").is_err());
+        assert!(parse_file_size("1.5MB").is_err());
+        assert!(parse_file_size("5XB").is_err());
+        assert!(parse_file_size("10000000TB").is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn aspect_ratio_parsing() -> ApiResult<()> {
+        assert_eq!(parse_aspect_ratio("1.5"), Ok(1.5));
+        assert_eq!(parse_aspect_ratio("16:9"), Ok(16.0 / 9.0));
+        assert_eq!(parse_aspect_ratio("3:4"), Ok(0.75));
+        assert!(parse_aspect_ratio("").is_err());
+        assert!(parse_aspect_ratio("16:").is_err());
+        assert!(parse_aspect_ratio("wide").is_err());
+
+        assert_eq!(aspect_ratio_condition("landscape")?, Condition::GreaterEq(1.0));
+        assert_eq!(aspect_ratio_condition("portrait")?, Condition::LessEq(1.0));
+        assert_eq!(aspect_ratio_condition("1:2,2")?, Condition::Values(vec![0.5, 2.0]));
+        assert_eq!(aspect_ratio_condition("4:3..")?, Condition::GreaterEq(4.0 / 3.0));
+        assert_eq!(aspect_ratio_condition("..0.5")?, Condition::LessEq(0.5));
+        assert_eq!(aspect_ratio_condition("16:9..2:1")?, Condition::Range(16.0 / 9.0..2.0));
+        assert!(aspect_ratio_condition("square").is_err());
         Ok(())
     }
 
@@ -251,6 +385,11 @@ mod test {
         assert_eq!(str_condition("a*,*b,*c*"), StrCondition::WildCard(String::from("a%,%b,%c%")));
         assert_eq!(str_condition("*a..b"), StrCondition::WildCard(String::from("%a..b")));
 
+        assert_eq!(file_size_condition("1MB..")?, Condition::GreaterEq(1_000_000));
+        assert_eq!(file_size_condition("..2KiB")?, Condition::LessEq(2048));
+        assert_eq!(file_size_condition("1KB..1MB")?, Condition::Range(1000..1_000_000));
+        assert_eq!(file_size_condition("100,1KB")?, Condition::Values(vec![100, 1000]));
+
         assert_eq!(condition("safe")?, Condition::Values(vec![PostSafety::Safe]));
         assert_eq!(condition("safe..unsafe")?, Condition::Range(PostSafety::Safe..PostSafety::Unsafe));
         Ok(())
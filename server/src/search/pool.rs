@@ -1,7 +1,7 @@
 use crate::api::ApiResult;
 use crate::model::pool::PoolName;
 use crate::schema::{database_statistics, pool, pool_category, pool_name, pool_statistics};
-use crate::search::{Order, ParsedSort, QueryCache, SearchCriteria, UnparsedFilter};
+use crate::search::{Order, Paginate, ParsedSort, QueryCache, SearchCriteria, UnparsedFilter};
 use crate::{api, apply_filter, apply_random_sort, apply_sort, apply_str_filter, apply_time_filter};
 use diesel::dsl::{InnerJoin, IntoBoxed, Select};
 use diesel::pg::Pg;
@@ -121,6 +121,17 @@ impl<'a> QueryBuilder<'a> {
     }
 }
 
+impl Paginate for QueryBuilder<'_> {
+    fn total(&mut self, conn: &mut PgConnection) -> ApiResult<i64> {
+        self.count(conn)
+    }
+
+    fn page(&mut self, conn: &mut PgConnection, offset: i64, limit: i64) -> ApiResult<Vec<i64>> {
+        self.set_offset_and_limit(offset, limit);
+        self.load(conn)
+    }
+}
+
 type BoxedQuery<'a> = IntoBoxed<
     'a,
     InnerJoin<InnerJoin<Select<pool::table, pool::id>, pool_statistics::table>, pool_category::table>,
@@ -1,18 +1,19 @@
 use crate::api::ApiResult;
 use crate::auth::header::Client;
-use crate::model::enums::{PostFlag, PostFlags, PostSafety, PostType};
+use crate::model::enums::{MimeType, PostFlag, PostFlags, PostSafety, PostType, Score};
 use crate::model::post::Checksum;
 use crate::schema::{
     comment, database_statistics, pool_post, post, post_favorite, post_feature, post_note, post_score, post_statistics,
     post_tag, tag_name, user,
 };
-use crate::search::{Order, ParsedSort, QueryCache, SearchCriteria, UnparsedFilter, parse};
-use crate::{api, apply_filter, apply_random_sort, apply_sort, apply_str_filter, apply_time_filter};
-use diesel::dsl::{InnerJoin, IntoBoxed, LeftJoin, Select, count, sql};
+use crate::search::{Order, Paginate, ParsedSort, QueryCache, SearchCriteria, StrCondition, UnparsedFilter, parse};
+use crate::{api, apply_filter, apply_random_sort, apply_sort, apply_str_filter, apply_time_filter, config};
+use diesel::dsl::{AsExprOf, InnerJoin, IntoBoxed, LeftJoin, Select, count, sql};
 use diesel::expression::{SqlLiteral, UncheckedBind};
 use diesel::pg::Pg;
 use diesel::prelude::*;
-use diesel::sql_types::{Float, SmallInt};
+use diesel::sql_types::{Float, SmallInt, Timestamptz};
+use std::collections::HashSet;
 use std::str::FromStr;
 use strum::{EnumIter, EnumString, IntoStaticStr};
 
@@ -32,12 +33,14 @@ pub enum Token {
         serialize = "ar",
         serialize = "aspect-ratio",
         serialize = "image-ar",
-        serialize = "image-aspect-ratio"
+        serialize = "image-aspect-ratio",
+        serialize = "image-ratio"
     )]
     AspectRatio,
     #[strum(serialize = "rating", serialize = "safety")]
     Safety,
     Type,
+    MimeType,
     ContentChecksum,
     Flag,
     Source,
@@ -62,15 +65,23 @@ pub enum Token {
     Uploader,
     Fav,
     Comment,
+    CommentText,
     NoteText,
     TagCount,
     CommentCount,
     RelationCount,
     NoteCount,
+    #[strum(serialize = "fav-count", serialize = "favorite-count")]
     FavCount,
     FeatureCount,
+    ViewCount,
     Score,
-    #[strum(serialize = "comment-date", serialize = "comment-time")]
+    #[strum(
+        serialize = "last-comment-date",
+        serialize = "last-comment-time",
+        serialize = "comment-date",
+        serialize = "comment-time"
+    )]
     CommentTime,
     #[strum(serialize = "fav-date", serialize = "fav-time")]
     FavTime,
@@ -79,9 +90,35 @@ pub enum Token {
     Special,
 }
 
+/// Posts related to a specific user, which a [QueryBuilder] can be restricted to.
+#[derive(Clone, Copy)]
+pub enum UserPosts {
+    /// Posts favorited by the user, listed by when they were favorited.
+    Favorites(i64),
+    /// Posts uploaded by the user.
+    Uploads(i64),
+    /// Posts given `score` by the user, listed by when they were scored.
+    Scored(i64, Score),
+}
+
+impl UserPosts {
+    /// Returns a SQL literal representing when the user related to a post, if the relation is timed.
+    fn time(self) -> Option<SqlLiteral<Timestamptz>> {
+        let (table, user_id) = match self {
+            Self::Favorites(user_id) => ("post_favorite", user_id),
+            Self::Uploads(_) => return None,
+            Self::Scored(user_id, _) => ("post_score", user_id),
+        };
+        Some(sql(&format!(
+            "(SELECT {table}.time FROM {table} WHERE {table}.post_id = post.id AND {table}.user_id = {user_id})"
+        )))
+    }
+}
+
 pub struct QueryBuilder<'a> {
     client: Client,
     search: SearchCriteria<'a, Token>,
+    user_posts: Option<UserPosts>,
     cache: QueryCache,
 }
 
@@ -90,7 +127,9 @@ impl<'a> QueryBuilder<'a> {
         let search = SearchCriteria::new(search_criteria, Token::Tag).map_err(Box::from)?;
         for sort in search.sorts.iter() {
             match sort.kind {
-                Token::ContentChecksum | Token::NoteText | Token::Special => return Err(api::Error::InvalidSort),
+                Token::MimeType | Token::ContentChecksum | Token::CommentText | Token::NoteText | Token::Special => {
+                    return Err(api::Error::InvalidSort);
+                }
                 _ => (),
             }
         }
@@ -98,6 +137,7 @@ impl<'a> QueryBuilder<'a> {
         Ok(Self {
             client,
             search,
+            user_posts: None,
             cache: QueryCache::new(),
         })
     }
@@ -110,8 +150,29 @@ impl<'a> QueryBuilder<'a> {
         self.search.set_offset_and_limit(offset, limit);
     }
 
+    /// Restricts the results to `user_posts`. Posts are listed by when the user
+    /// related to them, starting with the most recent, before any other sorts apply.
+    pub fn set_user_posts(&mut self, user_posts: UserPosts) {
+        self.user_posts = Some(user_posts);
+    }
+
+    /// Returns whether posts flagged as [PostFlag::Hidden] are included in the results.
+    pub fn includes_hidden(&self) -> bool {
+        can_view_hidden(self.client)
+    }
+
+    /// Returns whether posts of every safety are included in the results.
+    fn includes_any_safety(&self) -> bool {
+        can_view_any_safety(self.client)
+    }
+
     pub fn count(&mut self, conn: &mut PgConnection) -> ApiResult<i64> {
-        if self.search.has_filter() {
+        // Statistics count all posts, so they can only be used if none are excluded
+        if self.search.has_filter()
+            || self.user_posts.is_some()
+            || !self.includes_hidden()
+            || !self.includes_any_safety()
+        {
             let unsorted_query = self.build_filtered(conn)?;
             let unsorted_query = self.apply_cache_filters(unsorted_query);
             unsorted_query.count().first(conn)
@@ -146,30 +207,61 @@ impl<'a> QueryBuilder<'a> {
             .inner_join(post_statistics::table)
             .left_join(user::table)
             .into_boxed();
+        let base_query = match self.includes_hidden() {
+            true => base_query,
+            false => base_query.filter(is_not_hidden()),
+        };
+        let base_query = match self.includes_any_safety() {
+            true => base_query,
+            false => base_query.filter(post::safety.le(config::get().max_restricted_safety)),
+        };
+        let base_query = match self.user_posts {
+            Some(UserPosts::Favorites(user_id)) => base_query.filter(
+                post::id.eq_any(
+                    post_favorite::table
+                        .select(post_favorite::post_id)
+                        .filter(post_favorite::user_id.eq(user_id)),
+                ),
+            ),
+            Some(UserPosts::Uploads(user_id)) => base_query.filter(post::user_id.eq(user_id)),
+            Some(UserPosts::Scored(user_id, score)) => base_query.filter(
+                post::id.eq_any(
+                    post_score::table
+                        .select(post_score::post_id)
+                        .filter(post_score::user_id.eq(user_id))
+                        .filter(post_score::score.eq(score)),
+                ),
+            ),
+            None => base_query,
+        };
         let query = self
             .search
             .filters
             .iter()
             .try_fold(base_query, |query, &filter| match filter.kind {
                 Token::Id => apply_filter!(query, post::id, filter, i64),
-                Token::FileSize => apply_filter!(query, post::file_size, filter, i64),
+                Token::FileSize => parse::file_size_condition(filter.condition)
+                    .map(|condition| crate::apply_condition!(query, post::file_size, filter, condition)),
                 Token::Width => apply_filter!(query, post::width, filter, i32),
                 Token::Height => apply_filter!(query, post::height, filter, i32),
                 Token::Area => apply_filter!(query, post::width * post::height, filter, i32),
-                Token::AspectRatio => apply_filter!(query, aspect_ratio(), filter, f32),
+                Token::AspectRatio => parse::aspect_ratio_condition(filter.condition)
+                    .map(|condition| crate::apply_condition!(query, aspect_ratio(), filter, condition)),
                 Token::Safety => apply_filter!(query, post::safety, filter, PostSafety),
                 Token::Type => apply_filter!(query, post::type_, filter, PostType),
+                Token::MimeType => apply_mime_type_filter(query, filter),
                 Token::ContentChecksum => apply_checksum_filter(query, filter),
                 Token::Flag => apply_flag_filter(query, filter),
-                Token::Source => Ok(apply_str_filter!(query, post::source, filter)),
+                Token::Source => Ok(apply_source_filter(query, filter)),
                 Token::Description => Ok(apply_str_filter!(query, post::description, filter)),
                 Token::CreationTime => apply_time_filter!(query, post::creation_time, filter),
                 Token::LastEditTime => apply_time_filter!(query, post::last_edit_time, filter),
                 Token::Tag => apply_tag_filter(conn, query, filter, cache.as_mut()),
                 Token::Pool => apply_pool_filter(conn, query, filter, cache.as_mut()),
-                Token::Uploader => Ok(apply_str_filter!(query, user::name, filter)),
+                Token::Uploader => apply_uploader_filter(conn, query, filter, cache.as_mut()),
                 Token::Fav => apply_favorite_filter(conn, query, filter, cache.as_mut()),
                 Token::Comment => apply_comment_filter(conn, query, filter, cache.as_mut()),
+                Token::CommentText => apply_comment_text_filter(conn, query, filter, cache.as_mut()),
                 Token::NoteText => apply_note_text_filter(conn, query, filter, cache.as_mut()),
                 Token::TagCount => apply_filter!(query, post_statistics::tag_count, filter, i64),
                 Token::CommentCount => apply_filter!(query, post_statistics::comment_count, filter, i64),
@@ -177,6 +269,7 @@ impl<'a> QueryBuilder<'a> {
                 Token::NoteCount => apply_filter!(query, post_statistics::note_count, filter, i64),
                 Token::FavCount => apply_filter!(query, post_statistics::favorite_count, filter, i64),
                 Token::FeatureCount => apply_filter!(query, post_statistics::feature_count, filter, i64),
+                Token::ViewCount => apply_filter!(query, post_statistics::view_count, filter, i64),
                 Token::Score => apply_filter!(query, post_statistics::score, filter, i64),
                 Token::CommentTime => apply_comment_time_filter(conn, query, filter, cache.as_mut()),
                 Token::FavTime => apply_favorite_time_filter(conn, query, filter, cache.as_mut()),
@@ -192,12 +285,32 @@ impl<'a> QueryBuilder<'a> {
         if self.search.random_sort {
             return apply_random_sort!(unsorted_query, self.search);
         }
+        let unsorted_query = match self.user_posts.and_then(UserPosts::time) {
+            Some(time) => unsorted_query.then_order_by(time.desc()),
+            None => unsorted_query,
+        };
 
         let default_sort = std::iter::once(ParsedSort {
             kind: Token::Id,
             order: Order::default(),
         });
-        let sorts = self.search.sorts.iter().copied().chain(default_sort);
+        // Posts created at the same time are ordered by when they were last edited
+        let creation_time_tiebreaker = self
+            .search
+            .sorts
+            .first()
+            .filter(|sort| matches!(sort.kind, Token::CreationTime))
+            .map(|_| ParsedSort {
+                kind: Token::LastEditTime,
+                order: Order::Desc,
+            });
+        let sorts = self
+            .search
+            .sorts
+            .iter()
+            .copied()
+            .chain(creation_time_tiebreaker)
+            .chain(default_sort);
         let query = sorts.fold(unsorted_query, |query, sort| match sort.kind {
             Token::Id => apply_sort!(query, post::id, sort),
             Token::FileSize => apply_sort!(query, post::file_size, sort),
@@ -220,11 +333,14 @@ impl<'a> QueryBuilder<'a> {
             Token::RelationCount => apply_sort!(query, post_statistics::relation_count, sort),
             Token::NoteCount => apply_sort!(query, post_statistics::note_count, sort),
             Token::FeatureCount => apply_sort!(query, post_statistics::feature_count, sort),
+            Token::ViewCount => apply_sort!(query, post_statistics::view_count, sort),
             Token::Score => apply_sort!(query, post_statistics::score, sort),
             Token::CommentTime => apply_sort!(query, post_statistics::last_comment_time, sort),
             Token::FavTime => apply_sort!(query, post_statistics::last_favorite_time, sort),
             Token::FeatureTime => apply_sort!(query, post_statistics::last_feature_time, sort),
-            Token::ContentChecksum | Token::NoteText | Token::Special => panic!("Invalid sort-style token!"),
+            Token::MimeType | Token::ContentChecksum | Token::CommentText | Token::NoteText | Token::Special => {
+                panic!("Invalid sort-style token!")
+            }
         });
         match self.search.extra_args {
             Some(args) => query.offset(args.offset).limit(args.limit),
@@ -243,6 +359,17 @@ impl<'a> QueryBuilder<'a> {
     }
 }
 
+impl Paginate for QueryBuilder<'_> {
+    fn total(&mut self, conn: &mut PgConnection) -> ApiResult<i64> {
+        self.count(conn)
+    }
+
+    fn page(&mut self, conn: &mut PgConnection, offset: i64, limit: i64) -> ApiResult<Vec<i64>> {
+        self.set_offset_and_limit(offset, limit);
+        self.load(conn)
+    }
+}
+
 type BoxedQuery<'a> =
     IntoBoxed<'a, LeftJoin<InnerJoin<Select<post::table, post::id>, post_statistics::table>, user::table>, Pg>;
 
@@ -259,12 +386,78 @@ type Bind = UncheckedBind<SqlLiteral<Float, UncheckedBind<SqlLiteral<Float>, pos
 
 /// Returns a SQL literal representing a post's aspect. This is used instead of
 /// `post::width / post::height` because it avoids integer truncation.
+/// Posts without a height have no aspect ratio rather than causing a division by zero.
 fn aspect_ratio() -> SqlLiteral<Float, Bind> {
     sql("CAST(")
         .bind(post::width)
-        .sql(" AS REAL) / CAST(")
+        .sql(" AS REAL) / CAST(NULLIF(")
         .bind(post::height)
-        .sql(" AS REAL)")
+        .sql(", 0) AS REAL)")
+}
+
+type FlagBind = UncheckedBind<
+    SqlLiteral<SmallInt, UncheckedBind<SqlLiteral<SmallInt>, post::flags>>,
+    AsExprOf<PostFlags, SmallInt>,
+>;
+
+/// Returns a SQL literal representing the bitwise and of a post's flags with `flags`.
+fn masked_flags(flags: PostFlags) -> FlagBind {
+    sql::<SmallInt>("")
+        .bind(post::flags)
+        .sql(" & ")
+        .bind::<SmallInt, _>(flags)
+}
+
+/// Returns an expression that is true for posts which aren't flagged as [PostFlag::Hidden].
+pub fn is_not_hidden() -> diesel::dsl::Eq<FlagBind, i16> {
+    masked_flags(PostFlags::new_with(PostFlag::Hidden)).eq(0)
+}
+
+/// Returns whether `client` is allowed to see posts flagged as [PostFlag::Hidden].
+pub fn can_view_hidden(client: Client) -> bool {
+    api::verify_privilege(client, config::privileges().post_view_hidden).is_ok()
+}
+
+/// Returns whether a post with the given `flags` and `safety` can be seen by `client`.
+pub fn is_visible(client: Client, flags: PostFlags, safety: PostSafety) -> bool {
+    (!flags.contains(PostFlag::Hidden) || can_view_hidden(client))
+        && (safety <= config::get().max_restricted_safety || can_view_any_safety(client))
+}
+
+/// Returns the subset of `post_ids` that `client` is allowed to see.
+pub fn visible_post_ids(conn: &mut PgConnection, client: Client, post_ids: &[i64]) -> QueryResult<HashSet<i64>> {
+    let query = post::table
+        .select(post::id)
+        .filter(post::id.eq_any(post_ids))
+        .into_boxed();
+    let query = match can_view_hidden(client) {
+        true => query,
+        false => query.filter(is_not_hidden()),
+    };
+    let query = match can_view_any_safety(client) {
+        true => query,
+        false => query.filter(post::safety.le(config::get().max_restricted_safety)),
+    };
+    query.load(conn).map(|visible_ids| visible_ids.into_iter().collect())
+}
+
+/// Returns whether `client` is allowed to see posts above the configured `max_restricted_safety`.
+pub fn can_view_any_safety(client: Client) -> bool {
+    api::verify_privilege(client, config::privileges().post_view_any_safety).is_ok()
+}
+
+fn apply_mime_type_filter<'a>(query: BoxedQuery<'a>, filter: UnparsedFilter<'a, Token>) -> ApiResult<BoxedQuery<'a>> {
+    // MIME types can be given in full (image/jpeg) or by file extension (jpeg)
+    let mime_types: Vec<MimeType> = filter
+        .condition
+        .split(',')
+        .map(|value| MimeType::from_str(value).or_else(|_| MimeType::from_extension(value)))
+        .collect::<Result<_, _>>()
+        .map_err(Box::from)?;
+    Ok(match filter.negated {
+        true => query.filter(post::mime_type.ne_all(mime_types)),
+        false => query.filter(post::mime_type.eq_any(mime_types)),
+    })
 }
 
 fn apply_checksum_filter<'a>(query: BoxedQuery<'a>, filter: UnparsedFilter<'a, Token>) -> ApiResult<BoxedQuery<'a>> {
@@ -278,17 +471,32 @@ fn apply_checksum_filter<'a>(query: BoxedQuery<'a>, filter: UnparsedFilter<'a, T
 
 fn apply_flag_filter<'a>(query: BoxedQuery<'a>, filter: UnparsedFilter<'a, Token>) -> ApiResult<BoxedQuery<'a>> {
     let flags: Vec<PostFlag> = parse::values(filter.condition)?;
-    let value = flags.into_iter().fold(PostFlags::new(), |value, flag| value | flag);
-    let bitwise_and = sql::<SmallInt>("")
-        .bind(post::flags)
-        .sql(" & ")
-        .bind::<SmallInt, _>(value);
+    let bitwise_and = masked_flags(PostFlags::from_slice(&flags));
     Ok(match filter.negated {
         true => query.filter(bitwise_and.eq(0)),
         false => query.filter(bitwise_and.ne(0)),
     })
 }
 
+fn apply_source_filter<'a>(query: BoxedQuery<'a>, filter: UnparsedFilter<'a, Token>) -> BoxedQuery<'a> {
+    // Posts without a source store an empty string
+    if filter.condition == "none" {
+        return match filter.negated {
+            true => query.filter(post::source.ne("")),
+            false => query.filter(post::source.eq("")),
+        };
+    }
+
+    // Wildcard searches through sources are case-insensitive
+    match parse::str_condition(filter.condition) {
+        StrCondition::Regular(_) => apply_str_filter!(query, post::source, filter),
+        StrCondition::WildCard(pattern) => match filter.negated {
+            true => query.filter(post::source.not_ilike(pattern)),
+            false => query.filter(post::source.ilike(pattern)),
+        },
+    }
+}
+
 fn apply_tag_filter<'a>(
     conn: &mut PgConnection,
     query: BoxedQuery<'a>,
@@ -322,6 +530,30 @@ fn apply_pool_filter<'a>(
     Ok(query)
 }
 
+fn apply_uploader_filter<'a>(
+    conn: &mut PgConnection,
+    query: BoxedQuery<'a>,
+    filter: UnparsedFilter<Token>,
+    cache: Option<&mut QueryCache>,
+) -> ApiResult<BoxedQuery<'a>> {
+    // Anonymous posts have no uploader name to match against, so they get a special condition
+    if filter.condition == "none" {
+        return Ok(match filter.negated {
+            true => query.filter(post::user_id.is_not_null()),
+            false => query.filter(post::user_id.is_null()),
+        });
+    }
+
+    // Matching on post ids rather than on the joined name keeps anonymous posts in negated searches
+    if let Some(cache) = cache {
+        let uploads = post::table.select(post::id).inner_join(user::table).into_boxed();
+        let filtered_posts = apply_str_filter!(uploads, user::name, filter.unnegated());
+        let post_ids: Vec<i64> = filtered_posts.load(conn)?;
+        cache.update(post_ids, filter.negated);
+    }
+    Ok(query)
+}
+
 fn apply_favorite_filter<'a>(
     conn: &mut PgConnection,
     query: BoxedQuery<'a>,
@@ -358,6 +590,26 @@ fn apply_comment_filter<'a>(
     Ok(query)
 }
 
+fn apply_comment_text_filter<'a>(
+    conn: &mut PgConnection,
+    query: BoxedQuery<'a>,
+    filter: UnparsedFilter<Token>,
+    cache: Option<&mut QueryCache>,
+) -> ApiResult<BoxedQuery<'a>> {
+    if let Some(cache) = cache {
+        let comments = comment::table.select(comment::post_id).into_boxed();
+
+        // Wildcard searches through comment text are case-insensitive
+        let filtered_posts = match parse::str_condition(filter.condition) {
+            StrCondition::Regular(_) => apply_str_filter!(comments, comment::text, filter.unnegated()),
+            StrCondition::WildCard(pattern) => comments.filter(comment::text.ilike(pattern)),
+        };
+        let post_ids: Vec<i64> = filtered_posts.load(conn)?;
+        cache.update(post_ids, filter.negated);
+    }
+    Ok(query)
+}
+
 fn apply_note_text_filter<'a>(
     conn: &mut PgConnection,
     query: BoxedQuery<'a>,
@@ -3,7 +3,7 @@ use crate::model::tag::TagName;
 use crate::schema::{
     database_statistics, tag, tag_category, tag_implication, tag_name, tag_statistics, tag_suggestion,
 };
-use crate::search::{Order, ParsedSort, QueryCache, SearchCriteria, UnparsedFilter};
+use crate::search::{Order, Paginate, ParsedSort, QueryCache, SearchCriteria, UnparsedFilter};
 use crate::{api, apply_filter, apply_random_sort, apply_sort, apply_str_filter, apply_time_filter};
 use diesel::dsl::{InnerJoin, IntoBoxed, Select};
 use diesel::pg::Pg;
@@ -136,6 +136,17 @@ impl<'a> QueryBuilder<'a> {
     }
 }
 
+impl Paginate for QueryBuilder<'_> {
+    fn total(&mut self, conn: &mut PgConnection) -> ApiResult<i64> {
+        self.count(conn)
+    }
+
+    fn page(&mut self, conn: &mut PgConnection, offset: i64, limit: i64) -> ApiResult<Vec<i64>> {
+        self.set_offset_and_limit(offset, limit);
+        self.load(conn)
+    }
+}
+
 type BoxedQuery<'a> =
     IntoBoxed<'a, InnerJoin<InnerJoin<Select<tag::table, tag::id>, tag_statistics::table>, tag_category::table>, Pg>;
 
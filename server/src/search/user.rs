@@ -1,6 +1,6 @@
 use crate::api::ApiResult;
 use crate::schema::{database_statistics, user};
-use crate::search::{Order, ParsedSort, SearchCriteria};
+use crate::search::{Order, Paginate, ParsedSort, SearchCriteria};
 use crate::{api, apply_random_sort, apply_sort, apply_str_filter, apply_time_filter};
 use diesel::dsl::{IntoBoxed, Select};
 use diesel::pg::Pg;
@@ -89,4 +89,15 @@ impl<'a> QueryBuilder<'a> {
     }
 }
 
+impl Paginate for QueryBuilder<'_> {
+    fn total(&mut self, conn: &mut PgConnection) -> ApiResult<i64> {
+        self.count(conn)
+    }
+
+    fn page(&mut self, conn: &mut PgConnection, offset: i64, limit: i64) -> ApiResult<Vec<i64>> {
+        self.set_offset_and_limit(offset, limit);
+        self.load(conn)
+    }
+}
+
 type BoxedQuery<'a> = IntoBoxed<'a, Select<user::table, user::id>, Pg>;
@@ -9,9 +9,11 @@ use serde::{Deserialize, Serialize};
 use std::borrow::Cow;
 use std::fmt::Display;
 use std::ops::Deref;
+use utoipa::ToSchema;
 
-#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, AsExpression, FromSqlRow)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, AsExpression, FromSqlRow, ToSchema)]
 #[diesel(sql_type = Text, sql_type = Citext)]
+#[schema(value_type = String)]
 pub struct SmallString(CompactString);
 
 impl SmallString {
@@ -69,3 +71,24 @@ where
         CompactString::from_utf8(value.as_bytes()).map(Self).map_err(Box::from)
     }
 }
+
+/// Renders markdown `text` as HTML, sanitizing the output so that it can be safely embedded in a page.
+pub fn render_markdown(text: &str) -> String {
+    let parser = pulldown_cmark::Parser::new(text);
+    let mut html = String::new();
+    pulldown_cmark::html::push_html(&mut html, parser);
+    ammonia::clean(&html)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn markdown() {
+        assert_eq!(render_markdown(""), "");
+        assert_eq!(render_markdown("A **big** body of water"), "<p>A <strong>big</strong> body of water</p>\n");
+        assert!(!render_markdown("<script>alert(1)</script>").contains("script"));
+        assert!(!render_markdown("[link](javascript:alert(1))").contains("javascript"));
+    }
+}
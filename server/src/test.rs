@@ -2,7 +2,7 @@ use crate::api::ApiResult;
 use crate::auth::header;
 use crate::db::{ConnectionPool, ConnectionResult};
 use crate::model::comment::{NewComment, NewCommentScore};
-use crate::model::enums::{AvatarStyle, MimeType, PostFlag, PostFlags, Score, UserRank};
+use crate::model::enums::{AvatarStyle, ChecksumAlgorithm, MimeType, PostFlag, PostFlags, Score, UserRank};
 use crate::model::enums::{PostSafety, PostType};
 use crate::model::pool::{NewPool, NewPoolCategory, NewPoolName, PoolPost};
 use crate::model::post::{NewPost, NewPostFeature, NewPostNote, PostFavorite, PostRelation, PostScore, PostTag};
@@ -21,6 +21,9 @@ use std::path::PathBuf;
 use std::sync::Mutex;
 use std::time::Duration;
 use uuid::Uuid;
+use warp::http::header::LOCATION;
+use warp::http::{Response, StatusCode};
+use warp::hyper::body::Bytes;
 
 pub const TEST_PASSWORD: &str = "test_password";
 pub const TEST_SALT: &str = "test_salt";
@@ -68,37 +71,80 @@ pub async fn verify_query(query: &str, relative_path: &str) -> ApiResult<()> {
 }
 
 pub async fn verify_query_with_user(user: &str, query: &str, relative_path: &str) -> ApiResult<()> {
-    let filter = api::routes();
-    let (method, path) = query.split_once(' ').unwrap();
-    let path = path.replace(' ', "%20"); // Percent-encode all spaces
-    let credentials = header::credentials_for(user, TEST_PASSWORD);
-    let basic_access_authentication = format!("Basic {credentials}");
+    verify_reply(user, query, relative_path, StatusCode::OK).await
+}
 
-    let request = warp::test::request()
-        .header("authorization", basic_access_authentication)
-        .method(method)
-        .path(&path);
+/// Verifies that a given `query` is answered with `expected_status` and the contents of a `reply_filepath`.
+/// Used to check the body of error replies.
+pub async fn verify_error(query: &str, relative_path: &str, expected_status: StatusCode) -> ApiResult<()> {
+    verify_reply("administrator", query, relative_path, expected_status).await
+}
 
+async fn verify_reply(user: &str, query: &str, relative_path: &str, expected_status: StatusCode) -> ApiResult<()> {
     // Optionally specify a body
     let body_path = body_path(relative_path);
-    let reply = match body_path.try_exists()? {
-        true => {
-            let body = std::fs::read_to_string(body_path)?;
-            request.body(body).reply(&filter).await
-        }
-        false => request.reply(&filter).await,
+    let body = match body_path.try_exists()? {
+        true => Some(std::fs::read_to_string(body_path)?),
+        false => None,
     };
+    let reply = send_query(user, query, body).await;
     let actual_body = std::str::from_utf8(reply.body())?;
 
     let file_contents = std::fs::read_to_string(reply_path(relative_path))?;
     let expected_body: String = file_contents.split_whitespace().collect();
     let actual_body: String = actual_body.split_whitespace().collect();
 
-    assert_eq!(reply.status(), 200);
+    assert_eq!(reply.status(), expected_status);
     assert_eq!(actual_body, expected_body);
     Ok(())
 }
 
+/// Verifies that a given `query` sent by `user` is answered with `expected_status`.
+pub async fn verify_status_with_user(user: &str, query: &str, expected_status: StatusCode) {
+    let reply = send_query(user, query, None).await;
+    assert_eq!(reply.status(), expected_status);
+}
+
+/// Verifies that a given `query` sent by `user` is redirected to a location ending with `expected_location`.
+pub async fn verify_redirect_with_user(user: &str, query: &str, expected_location: &str) {
+    let reply = send_query(user, query, None).await;
+    assert_eq!(reply.status(), StatusCode::MOVED_PERMANENTLY);
+    let location = reply.headers()[LOCATION].to_str().unwrap();
+    assert!(location.ends_with(expected_location), "{location} doesn't end with {expected_location}");
+}
+
+/// Verifies that a given `query` sent by `user` is answered with `expected_status` and returns the body of the reply.
+pub async fn reply_body_with_user(user: &str, query: &str, expected_status: StatusCode) -> String {
+    let reply = send_query(user, query, None).await;
+    assert_eq!(reply.status(), expected_status);
+    String::from_utf8(reply.body().to_vec()).unwrap()
+}
+
+/// Verifies that a given `query` with the request body at `relative_path` is answered with `expected_status`.
+pub async fn verify_status_with_body(query: &str, relative_path: &str, expected_status: StatusCode) -> ApiResult<()> {
+    let body = std::fs::read_to_string(body_path(relative_path))?;
+    let reply = send_query("administrator", query, Some(body)).await;
+    assert_eq!(reply.status(), expected_status);
+    Ok(())
+}
+
+async fn send_query(user: &str, query: &str, body: Option<String>) -> Response<Bytes> {
+    let filter = api::routes();
+    let (method, path) = query.split_once(' ').unwrap();
+    let path = path.replace(' ', "%20"); // Percent-encode all spaces
+    let credentials = header::credentials_for(user, TEST_PASSWORD);
+    let basic_access_authentication = format!("Basic {credentials}");
+
+    let request = warp::test::request()
+        .header("authorization", basic_access_authentication)
+        .method(method)
+        .path(&path);
+    match body {
+        Some(body) => request.body(body).reply(&filter).await,
+        None => request.reply(&filter).await,
+    }
+}
+
 const DATABASE_NAME: &str = "__test";
 
 const USERS: &[NewUser] = &[
@@ -229,6 +275,7 @@ const POSTS: &[NewPost] = &[
         flags: PostFlags::new(),
         source: "My hard drive",
         description: "0101100010",
+        checksum_algorithm: ChecksumAlgorithm::Blake3,
     },
     NewPost {
         user_id: Some(2),
@@ -243,6 +290,7 @@ const POSTS: &[NewPost] = &[
         flags: PostFlags::new(),
         source: "",
         description: "",
+        checksum_algorithm: ChecksumAlgorithm::Blake3,
     },
     NewPost {
         user_id: Some(2),
@@ -257,6 +305,7 @@ const POSTS: &[NewPost] = &[
         flags: PostFlags::new(),
         source: "Wikipedia",
         description: "",
+        checksum_algorithm: ChecksumAlgorithm::Blake3,
     },
     NewPost {
         user_id: Some(2),
@@ -271,6 +320,7 @@ const POSTS: &[NewPost] = &[
         flags: PostFlags::new(),
         source: "",
         description: "description9000",
+        checksum_algorithm: ChecksumAlgorithm::Blake3,
     },
     NewPost {
         user_id: None,
@@ -285,6 +335,7 @@ const POSTS: &[NewPost] = &[
         flags: PostFlags::new_with(PostFlag::Sound),
         source: "???",
         description: "descriptor",
+        checksum_algorithm: ChecksumAlgorithm::Blake3,
     },
 ];
 
@@ -329,6 +380,7 @@ const fn new_user(name: &'static str, email: Option<&'static str>, rank: UserRan
         email,
         rank,
         avatar_style: AvatarStyle::Manual,
+        email_verification_token: None,
     }
 }
 
@@ -8,6 +8,7 @@ use std::ops::{Deref, DerefMut};
 use time::error::ComponentRange;
 use time::serde::rfc3339;
 use time::{Date, Month, OffsetDateTime, PrimitiveDateTime};
+use utoipa::ToSchema;
 
 /// Used for timing things. Prints how long the object lived when dropped.
 pub struct Timer<'a> {
@@ -39,8 +40,11 @@ impl Drop for Timer<'_> {
 }
 
 /// A wrapper for [OffsetDateTime] that serializes/deserializes according to RFC 3339.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, AsExpression, FromSqlRow)]
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, AsExpression, FromSqlRow, ToSchema,
+)]
 #[diesel(sql_type = Timestamptz)]
+#[schema(value_type = String, format = DateTime)]
 pub struct DateTime(#[serde(with = "rfc3339")] OffsetDateTime);
 
 impl DateTime {
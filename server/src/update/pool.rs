@@ -6,6 +6,7 @@ use crate::schema::{pool, pool_name, pool_post};
 use crate::string::SmallString;
 use crate::time::DateTime;
 use diesel::prelude::*;
+use std::collections::HashSet;
 
 /// Updates last_edit_time of pool with given `pool_id`.
 pub fn last_edit_time(conn: &mut PgConnection, pool_id: i64) -> ApiResult<()> {
@@ -22,9 +23,11 @@ pub fn add_names(
     current_name_count: i32,
     names: Vec<SmallString>,
 ) -> ApiResult<()> {
-    names
-        .iter()
-        .try_for_each(|name| api::verify_matches_regex(name, RegexType::Pool))?;
+    api::verify_fields(
+        names
+            .iter()
+            .map(|name| ("names", api::verify_matches_regex(name, RegexType::Pool))),
+    )?;
 
     let updated_names: Vec<_> = names
         .iter()
@@ -71,3 +74,30 @@ pub fn delete_posts(conn: &mut PgConnection, pool_id: i64) -> QueryResult<usize>
         .filter(pool_post::pool_id.eq(pool_id))
         .execute(conn)
 }
+
+/// Moves `posts` to the front of the pool with id `pool_id`, in the given order.
+/// The remaining posts of the pool keep their relative order after them.
+/// Returns an error if `posts` contains duplicates or posts that aren't in the pool.
+pub fn reorder_posts(conn: &mut PgConnection, pool_id: i64, posts: Vec<i64>) -> ApiResult<()> {
+    let current_posts: Vec<i64> = pool_post::table
+        .select(pool_post::post_id)
+        .filter(pool_post::pool_id.eq(pool_id))
+        .order_by(pool_post::order)
+        .load(conn)?;
+
+    let reordered_posts: HashSet<i64> = posts.iter().copied().collect();
+    let all_in_pool = posts.iter().all(|post_id| current_posts.contains(post_id));
+    if reordered_posts.len() != posts.len() || !all_in_pool {
+        return Err(api::Error::InvalidPoolOrder);
+    }
+
+    let remaining_posts = current_posts
+        .into_iter()
+        .filter(|post_id| !reordered_posts.contains(post_id));
+    for (order, post_id) in posts.into_iter().chain(remaining_posts).enumerate() {
+        diesel::update(pool_post::table.find((pool_id, post_id)))
+            .set(pool_post::order.eq(order as i64))
+            .execute(conn)?;
+    }
+    Ok(())
+}
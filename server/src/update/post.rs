@@ -1,13 +1,19 @@
-use crate::api::ApiResult;
+use crate::api::{self, ApiResult};
 use crate::content::hash::PostHash;
 use crate::content::thumbnail::ThumbnailCategory;
 use crate::filesystem;
+use crate::model::enums::ResourceType;
 use crate::model::post::{PostRelation, PostTag};
-use crate::resource::post::Note;
-use crate::schema::{post, post_note, post_relation, post_tag};
+use crate::resource::post::{Note, NoteUpdate};
+use crate::schema::{post, post_note, post_relation, post_statistics, post_tag};
 use crate::time::DateTime;
 use diesel::prelude::*;
 use image::DynamicImage;
+use std::collections::{HashMap, HashSet};
+use std::sync::{LazyLock, Mutex};
+
+/// Views that haven't been written to the database yet, by post id.
+static PENDING_VIEWS: LazyLock<Mutex<HashMap<i64, i64>>> = LazyLock::new(|| Mutex::new(HashMap::new()));
 
 /// Updates last_edit_time of post with given `post_id`.
 pub fn last_edit_time(conn: &mut PgConnection, post_id: i64) -> ApiResult<()> {
@@ -17,6 +23,37 @@ pub fn last_edit_time(conn: &mut PgConnection, post_id: i64) -> ApiResult<()> {
     Ok(())
 }
 
+/// Counts a view of the post with given `post_id`. Views are buffered in memory and
+/// only written to the database by [flush_views], so that viewing posts doesn't cause writes.
+pub fn record_view(post_id: i64) {
+    *PENDING_VIEWS.lock().unwrap().entry(post_id).or_default() += 1;
+}
+
+/// Adds buffered views to the view counts of their posts. If this fails, the views are
+/// kept so that they can be written by the next flush.
+pub fn flush_views(conn: &mut PgConnection) -> QueryResult<()> {
+    let pending_views = std::mem::take(&mut *PENDING_VIEWS.lock().unwrap());
+    if pending_views.is_empty() {
+        return Ok(());
+    }
+
+    let result = conn.transaction(|conn| {
+        for (&post_id, &views) in pending_views.iter() {
+            diesel::update(post_statistics::table.find(post_id))
+                .set(post_statistics::view_count.eq(post_statistics::view_count + views))
+                .execute(conn)?;
+        }
+        Ok(())
+    });
+    if result.is_err() {
+        let mut views_since_flush = PENDING_VIEWS.lock().unwrap();
+        for (post_id, views) in pending_views {
+            *views_since_flush.entry(post_id).or_default() += views;
+        }
+    }
+    result
+}
+
 /// Updates custom thumbnail for post.
 pub fn custom_thumbnail(conn: &mut PgConnection, post_hash: &PostHash, thumbnail: DynamicImage) -> ApiResult<()> {
     filesystem::delete_post_thumbnail(post_hash, ThumbnailCategory::Custom)?;
@@ -28,10 +65,12 @@ pub fn custom_thumbnail(conn: &mut PgConnection, post_hash: &PostHash, thumbnail
 }
 
 /// Creates relations for the post with id `post_id`, symmetrically.
+/// Repeated ids and relations of the post to itself are ignored.
 pub fn create_relations(conn: &mut PgConnection, post_id: i64, relations: Vec<i64>) -> QueryResult<()> {
-    let new_relations: Vec<_> = relations
-        .iter()
-        .flat_map(|&other_id| PostRelation::new_pair(post_id, other_id))
+    let related_ids: HashSet<i64> = relations.into_iter().filter(|&other_id| other_id != post_id).collect();
+    let new_relations: Vec<_> = related_ids
+        .into_iter()
+        .flat_map(|other_id| PostRelation::new_pair(post_id, other_id))
         .collect();
     diesel::insert_into(post_relation::table)
         .values(new_relations)
@@ -39,7 +78,7 @@ pub fn create_relations(conn: &mut PgConnection, post_id: i64, relations: Vec<i6
     Ok(())
 }
 
-/// Deletes all relations involving the post with id `post_id`.
+/// Deletes all relations involving the post with id `post_id`, in both directions.
 /// Returns number of relations deleted.
 pub fn delete_relations(conn: &mut PgConnection, post_id: i64) -> QueryResult<usize> {
     diesel::delete(post_relation::table)
@@ -74,10 +113,37 @@ pub fn add_notes(conn: &mut PgConnection, post_id: i64, notes: Vec<Note>) -> Que
     Ok(())
 }
 
-/// Deletes all notes from post with id `post_id`.
-/// Returns number of notes deleted.
-pub fn delete_notes(conn: &mut PgConnection, post_id: i64) -> QueryResult<usize> {
+/// Replaces the notes of post with id `post_id` with `notes`. Notes with an id are updated in place,
+/// notes without one are created, and existing notes missing from `notes` are deleted.
+pub fn update_notes(conn: &mut PgConnection, post_id: i64, notes: Vec<NoteUpdate>) -> ApiResult<()> {
+    let kept_note_ids: Vec<i64> = notes.iter().filter_map(|note| note.id).collect();
     diesel::delete(post_note::table)
         .filter(post_note::post_id.eq(post_id))
-        .execute(conn)
+        .filter(post_note::id.ne_all(&kept_note_ids))
+        .execute(conn)?;
+
+    for note in notes {
+        let polygon = note.polygon.as_flattened();
+        match note.id {
+            Some(note_id) => {
+                let updated_rows = diesel::update(post_note::table.find(note_id))
+                    .filter(post_note::post_id.eq(post_id))
+                    .set((post_note::polygon.eq(polygon), post_note::text.eq(&note.text)))
+                    .execute(conn)?;
+                if updated_rows == 0 {
+                    return Err(api::Error::NotFound(ResourceType::PostNote));
+                }
+            }
+            None => {
+                diesel::insert_into(post_note::table)
+                    .values((
+                        post_note::post_id.eq(post_id),
+                        post_note::polygon.eq(polygon),
+                        post_note::text.eq(&note.text),
+                    ))
+                    .execute(conn)?;
+            }
+        }
+    }
+    Ok(())
 }
@@ -7,8 +7,10 @@ use crate::schema::{tag, tag_implication, tag_name, tag_suggestion};
 use crate::string::SmallString;
 use crate::time::DateTime;
 use crate::{api, config};
+use diesel::dsl::exists;
 use diesel::prelude::*;
-use std::collections::HashSet;
+use std::collections::hash_map::Entry;
+use std::collections::{HashMap, HashSet};
 
 /// Updates last_edit_time of tag with given `tag_id`.
 pub fn last_edit_time(conn: &mut PgConnection, tag_id: i64) -> ApiResult<()> {
@@ -25,9 +27,12 @@ pub fn add_names(
     current_name_count: i32,
     names: Vec<SmallString>,
 ) -> ApiResult<()> {
-    names
-        .iter()
-        .try_for_each(|name| api::verify_matches_regex(name, RegexType::Tag))?;
+    api::verify_fields(
+        names
+            .iter()
+            .map(|name| ("names", api::verify_matches_regex(name, RegexType::Tag))),
+    )?;
+    names.iter().try_for_each(|name| check_tag_name_available(conn, name))?;
 
     let new_names: Vec<_> = names
         .iter()
@@ -39,6 +44,16 @@ pub fn add_names(
     Ok(())
 }
 
+/// Checks that `name` isn't used by any existing tag. Tag names are case-insensitive,
+/// so `name` is also taken if it only differs from an existing name by case.
+pub fn check_tag_name_available(conn: &mut PgConnection, name: &str) -> ApiResult<()> {
+    let taken: bool = diesel::select(exists(tag_name::table.filter(tag_name::name.eq(name)))).get_result(conn)?;
+    match taken {
+        true => Err(api::Error::TagNameTaken(SmallString::new(name))),
+        false => Ok(()),
+    }
+}
+
 /// Deletes all names for the tag with id `tag_id`.
 /// Returns number of names deleted.
 pub fn delete_names(conn: &mut PgConnection, tag_id: i64) -> QueryResult<usize> {
@@ -48,24 +63,84 @@ pub fn delete_names(conn: &mut PgConnection, tag_id: i64) -> QueryResult<usize>
 }
 
 /// Adds `implied_ids` to the list of implications for the tag with id `tag_id`.
+/// Fails if any of the new implications would form a cycle.
 pub fn add_implications(conn: &mut PgConnection, tag_id: i64, implied_ids: Vec<i64>) -> ApiResult<()> {
+    implied_ids
+        .iter()
+        .try_for_each(|&child_id| check_implication_cycle(conn, tag_id, child_id))?;
+
     let new_implications: Vec<_> = implied_ids
         .into_iter()
-        .map(|child_id| {
-            (tag_id != child_id)
-                .then_some(TagImplication {
-                    parent_id: tag_id,
-                    child_id,
-                })
-                .ok_or(api::Error::CyclicDependency(ResourceType::TagImplication))
+        .map(|child_id| TagImplication {
+            parent_id: tag_id,
+            child_id,
         })
-        .collect::<Result<_, _>>()?;
+        .collect();
     diesel::insert_into(tag_implication::table)
         .values(new_implications)
         .execute(conn)?;
     Ok(())
 }
 
+/// Checks that making the tag with id `parent_id` imply the tag with id `child_id` wouldn't
+/// form a cycle in the implication graph. On failure, the error lists the names of the tags
+/// along the cycle, starting and ending with the parent.
+pub fn check_implication_cycle(conn: &mut PgConnection, parent_id: i64, child_id: i64) -> ApiResult<()> {
+    let Some(path) = implication_path(conn, child_id, parent_id)? else {
+        return Ok(());
+    };
+
+    let chain: Vec<i64> = std::iter::once(parent_id).chain(path).collect();
+    let names: HashMap<i64, SmallString> = tag_name::table
+        .select((tag_name::tag_id, tag_name::name))
+        .filter(tag_name::tag_id.eq_any(&chain))
+        .filter(tag_name::order.eq(0))
+        .load(conn)?
+        .into_iter()
+        .collect();
+    let chain = chain
+        .iter()
+        .map(|tag_id| names.get(tag_id).map(|name| name.to_string()).unwrap_or_default())
+        .collect::<Vec<_>>()
+        .join(" -> ");
+    Err(api::Error::ImplicationCycle(chain))
+}
+
+/// Searches the implication graph breadth-first for a chain of implications leading from the tag with
+/// id `from_id` to the tag with id `to_id`. Returns the ids along the shortest such chain, including both ends.
+///
+/// Each level of the search is retrieved with a single query, so the number of queries is bounded by
+/// the length of the chain rather than the number of tags visited.
+fn implication_path(conn: &mut PgConnection, from_id: i64, to_id: i64) -> QueryResult<Option<Vec<i64>>> {
+    let mut predecessors = HashMap::from([(from_id, from_id)]);
+    let mut frontier = vec![from_id];
+    while !frontier.is_empty() && !predecessors.contains_key(&to_id) {
+        let implications: Vec<(i64, i64)> = tag_implication::table
+            .select((tag_implication::parent_id, tag_implication::child_id))
+            .filter(tag_implication::parent_id.eq_any(&frontier))
+            .load(conn)?;
+        frontier.clear();
+        for (parent_id, child_id) in implications {
+            if let Entry::Vacant(entry) = predecessors.entry(child_id) {
+                entry.insert(parent_id);
+                frontier.push(child_id);
+            }
+        }
+    }
+    if !predecessors.contains_key(&to_id) {
+        return Ok(None);
+    }
+
+    let mut path = vec![to_id];
+    let mut current_id = to_id;
+    while current_id != from_id {
+        current_id = predecessors[&current_id];
+        path.push(current_id);
+    }
+    path.reverse();
+    Ok(Some(path))
+}
+
 /// Adds `suggested_ids` to the list of suggestions for the tag with id `tag_id`.
 pub fn add_suggestions(conn: &mut PgConnection, tag_id: i64, suggested_ids: Vec<i64>) -> ApiResult<()> {
     let new_suggestions: Vec<_> = suggested_ids
@@ -89,11 +164,13 @@ pub fn add_suggestions(conn: &mut PgConnection, tag_id: i64, suggested_ids: Vec<
 /// Returned ids will be distinct.
 ///
 /// Requires tag creation privileges if new names are given.
-/// Checks that each new name matches on the Tag regex.
+/// Checks that each new name matches on the Tag regex, reporting invalid names as errors of
+/// the request body field `field`.
 pub fn get_or_create_tag_ids(
     conn: &mut PgConnection,
     client: Client,
     names: &[SmallString],
+    field: &'static str,
     detect_cyclic_dependencies: bool,
 ) -> ApiResult<Vec<i64>> {
     let mut implied_ids: Vec<i64> = tag_name::table
@@ -132,9 +209,11 @@ pub fn get_or_create_tag_ids(
         .iter()
         .filter(|name| !existing_names.contains(&name.to_lowercase()))
         .collect();
-    new_tag_names
-        .iter()
-        .try_for_each(|name| api::verify_matches_regex(name, RegexType::Tag))?;
+    api::verify_fields(
+        new_tag_names
+            .iter()
+            .map(|name| (field, api::verify_matches_regex(name, RegexType::Tag))),
+    )?;
 
     // Create new tags if given unique names
     if !new_tag_names.is_empty() {
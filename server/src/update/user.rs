@@ -1,5 +1,7 @@
 use crate::api::ApiResult;
-use crate::schema::user;
+use crate::model::enums::UserRank;
+use crate::model::user::NewUserAudit;
+use crate::schema::{user, user_audit};
 use crate::time::DateTime;
 use crate::{db, filesystem};
 use diesel::prelude::*;
@@ -32,3 +34,27 @@ pub fn avatar(conn: &mut PgConnection, user_id: i64, name: &str, avatar: Dynamic
         .execute(conn)?;
     Ok(())
 }
+
+/// Changes the rank of user with given `user_id` to `new_rank`.
+/// Actual changes are recorded in the audit log along with the id of the user who made them.
+pub fn rank(conn: &mut PgConnection, user_id: i64, changed_by: Option<i64>, new_rank: UserRank) -> ApiResult<()> {
+    let old_rank: UserRank = user::table.find(user_id).select(user::rank).first(conn)?;
+    if old_rank == new_rank {
+        return Ok(());
+    }
+
+    diesel::update(user::table.find(user_id))
+        .set(user::rank.eq(new_rank))
+        .execute(conn)?;
+
+    let audit_entry = NewUserAudit {
+        user_id,
+        changed_by,
+        old_rank,
+        new_rank,
+    };
+    diesel::insert_into(user_audit::table)
+        .values(audit_entry)
+        .execute(conn)?;
+    Ok(())
+}